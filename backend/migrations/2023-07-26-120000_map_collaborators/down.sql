@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE map_collaborators;
+DROP TYPE collaborator_role;
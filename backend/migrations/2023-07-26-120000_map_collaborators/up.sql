@@ -0,0 +1,15 @@
+CREATE TYPE collaborator_role AS ENUM (
+    'owner',
+    'editor',
+    'viewer'
+);
+
+CREATE TABLE map_collaborators (
+    map_id integer NOT NULL,
+    user_id uuid NOT NULL,
+    role collaborator_role NOT NULL,
+    PRIMARY KEY (map_id, user_id),
+    FOREIGN KEY (map_id) REFERENCES maps (id) ON DELETE CASCADE
+);
+
+CREATE INDEX map_collaborators_user_id_idx ON map_collaborators (user_id);
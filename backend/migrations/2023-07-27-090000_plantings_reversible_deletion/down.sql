@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE plantings
+DROP COLUMN create_date,
+DROP COLUMN delete_date;
@@ -0,0 +1,3 @@
+ALTER TABLE plantings
+ADD COLUMN create_date date NOT NULL DEFAULT CURRENT_DATE,
+ADD COLUMN delete_date date;
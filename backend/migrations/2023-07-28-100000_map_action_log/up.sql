@@ -0,0 +1,12 @@
+CREATE TABLE map_action_log (
+    id bigserial PRIMARY KEY,
+    map_id integer NOT NULL,
+    user_id uuid NOT NULL,
+    action_id uuid NOT NULL,
+    changes jsonb NOT NULL,
+    is_undone boolean NOT NULL DEFAULT false,
+    created_at timestamp NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    FOREIGN KEY (map_id) REFERENCES maps (id) ON DELETE CASCADE
+);
+
+CREATE INDEX map_action_log_map_id_user_id_idx ON map_action_log (map_id, user_id, id);
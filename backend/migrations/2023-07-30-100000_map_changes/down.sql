@@ -0,0 +1,19 @@
+-- This file should undo anything in `up.sql`
+
+DROP TRIGGER base_layer_images_create_tombstone ON base_layer_images;
+DROP TRIGGER layers_create_tombstone ON layers;
+DROP FUNCTION create_base_layer_image_tombstone;
+DROP FUNCTION create_layer_tombstone;
+DROP TABLE tombstones;
+
+DROP TRIGGER base_layer_images_set_updated_at ON base_layer_images;
+DROP TRIGGER layers_set_updated_at ON layers;
+DROP TRIGGER plantings_set_updated_at ON plantings;
+DROP FUNCTION set_updated_at;
+
+ALTER TABLE base_layer_images
+DROP COLUMN updated_at;
+ALTER TABLE layers
+DROP COLUMN updated_at;
+ALTER TABLE plantings
+DROP COLUMN updated_at;
@@ -0,0 +1,65 @@
+-- Track when plantings, layers and base layer images were changed,
+-- so clients can fetch only the changes since their last update.
+ALTER TABLE plantings
+ADD COLUMN updated_at timestamp NOT NULL DEFAULT (now() AT TIME ZONE 'UTC');
+ALTER TABLE layers
+ADD COLUMN updated_at timestamp NOT NULL DEFAULT (now() AT TIME ZONE 'UTC');
+ALTER TABLE base_layer_images
+ADD COLUMN updated_at timestamp NOT NULL DEFAULT (now() AT TIME ZONE 'UTC');
+
+-- Unlike `diesel_set_updated_at` the value set by the backend is always overwritten,
+-- so all timestamps come from the same clock.
+CREATE FUNCTION set_updated_at() RETURNS trigger AS $$
+BEGIN
+    NEW.updated_at := now() AT TIME ZONE 'UTC';
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER plantings_set_updated_at
+BEFORE INSERT OR UPDATE ON plantings
+FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+CREATE TRIGGER layers_set_updated_at
+BEFORE INSERT OR UPDATE ON layers
+FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+CREATE TRIGGER base_layer_images_set_updated_at
+BEFORE INSERT OR UPDATE ON base_layer_images
+FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+
+-- Layers and base layer images are deleted permanently,
+-- so their deletion is remembered here.
+-- Deleted plantings are kept for some time anyway (see `delete_date`).
+-- There is no foreign key to `maps`, as tombstones are also created while a map is deleted.
+CREATE TABLE tombstones (
+    id bigserial PRIMARY KEY,
+    map_id integer NOT NULL,
+    layer_id integer,
+    base_layer_image_id uuid,
+    deleted_at timestamp NOT NULL DEFAULT (now() AT TIME ZONE 'UTC')
+);
+
+CREATE INDEX tombstones_map_id_deleted_at_idx ON tombstones (map_id, deleted_at);
+
+CREATE FUNCTION create_layer_tombstone() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO tombstones (map_id, layer_id) VALUES (OLD.map_id, OLD.id);
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Images deleted together with their layer don't need a tombstone,
+-- in this case the layer can't be found anymore and nothing is inserted.
+CREATE FUNCTION create_base_layer_image_tombstone() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO tombstones (map_id, base_layer_image_id)
+    SELECT layers.map_id, OLD.id FROM layers WHERE layers.id = OLD.layer_id;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER layers_create_tombstone
+AFTER DELETE ON layers
+FOR EACH ROW EXECUTE PROCEDURE create_layer_tombstone();
+CREATE TRIGGER base_layer_images_create_tombstone
+AFTER DELETE ON base_layer_images
+FOR EACH ROW EXECUTE PROCEDURE create_base_layer_image_tombstone();
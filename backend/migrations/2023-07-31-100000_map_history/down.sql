@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE map_history;
+DROP TYPE history_entity_type;
@@ -0,0 +1,17 @@
+CREATE TYPE history_entity_type AS ENUM ('planting', 'layer', 'base_layer_image');
+
+CREATE TABLE map_history (
+    id bigserial PRIMARY KEY,
+    map_id integer NOT NULL,
+    user_id uuid NOT NULL,
+    action_id uuid NOT NULL,
+    entity_type history_entity_type NOT NULL,
+    layer_id integer NOT NULL,
+    entity_id uuid,
+    before jsonb,
+    after jsonb,
+    created_at timestamp NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    FOREIGN KEY (map_id) REFERENCES maps (id) ON DELETE CASCADE
+);
+
+CREATE INDEX map_history_map_id_idx ON map_history (map_id, id);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE layer_preferences;
+ALTER TABLE layers DROP COLUMN order_index;
@@ -0,0 +1,20 @@
+ALTER TABLE layers ADD COLUMN order_index integer NOT NULL DEFAULT 0;
+
+UPDATE layers
+SET order_index = ordered.order_index
+FROM (
+    SELECT id, row_number() OVER (PARTITION BY map_id ORDER BY id) - 1 AS order_index
+    FROM layers
+) AS ordered
+WHERE layers.id = ordered.id;
+
+CREATE TABLE layer_preferences (
+    user_id uuid NOT NULL,
+    layer_id integer NOT NULL,
+    is_visible boolean NOT NULL DEFAULT true,
+    opacity real NOT NULL DEFAULT 1,
+    is_selected boolean NOT NULL DEFAULT false,
+    PRIMARY KEY (user_id, layer_id),
+    FOREIGN KEY (layer_id) REFERENCES layers (id) ON DELETE CASCADE,
+    CHECK (opacity >= 0 AND opacity <= 1)
+);
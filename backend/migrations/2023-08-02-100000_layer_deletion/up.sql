@@ -0,0 +1,2 @@
+-- Layers are marked for deletion first, so they can be restored together with their content.
+ALTER TABLE layers ADD COLUMN deletion_date date;
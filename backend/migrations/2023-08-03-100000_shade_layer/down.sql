@@ -0,0 +1,17 @@
+-- This file should undo anything in `up.sql`
+
+DELETE FROM map_history WHERE entity_type::text = 'shade_area';
+ALTER TYPE history_entity_type RENAME TO history_entity_type_old;
+CREATE TYPE history_entity_type AS ENUM ('planting', 'layer', 'base_layer_image');
+ALTER TABLE map_history
+ALTER COLUMN entity_type TYPE history_entity_type
+USING entity_type::text::history_entity_type;
+DROP TYPE history_entity_type_old;
+
+DROP TRIGGER shade_areas_create_tombstone ON shade_areas;
+DROP FUNCTION create_shade_area_tombstone;
+DELETE FROM tombstones WHERE shade_area_id IS NOT NULL;
+ALTER TABLE tombstones DROP COLUMN shade_area_id;
+
+DROP TABLE shade_areas;
+DROP FUNCTION check_shade_layer_type;
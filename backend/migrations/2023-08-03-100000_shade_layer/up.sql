@@ -0,0 +1,46 @@
+-- Areas on a shade layer that have the same intensity of shade.
+CREATE TABLE shade_areas (
+    id uuid PRIMARY KEY,
+    layer_id integer NOT NULL,
+    shade shade NOT NULL,
+    geometry geometry (POLYGON, 4326) NOT NULL,
+    updated_at timestamp NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    FOREIGN KEY (layer_id) REFERENCES layers (id) ON DELETE CASCADE
+);
+
+CREATE INDEX shade_areas_layer_id_idx ON shade_areas (layer_id);
+
+CREATE FUNCTION check_shade_layer_type() RETURNS trigger AS $$
+BEGIN
+    IF (SELECT type FROM layers WHERE id = NEW.layer_id) != 'shade' THEN
+        RAISE EXCEPTION 'Layer type must be "shade"';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER shade_areas_check_layer_type
+BEFORE INSERT OR UPDATE ON shade_areas
+FOR EACH ROW EXECUTE PROCEDURE check_shade_layer_type();
+CREATE TRIGGER shade_areas_set_updated_at
+BEFORE INSERT OR UPDATE ON shade_areas
+FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+
+-- Shade areas are deleted permanently like base layer images.
+ALTER TABLE tombstones ADD COLUMN shade_area_id uuid;
+
+-- Areas deleted together with their layer don't need a tombstone,
+-- in this case the layer can't be found anymore and nothing is inserted.
+CREATE FUNCTION create_shade_area_tombstone() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO tombstones (map_id, shade_area_id)
+    SELECT layers.map_id, OLD.id FROM layers WHERE layers.id = OLD.layer_id;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER shade_areas_create_tombstone
+AFTER DELETE ON shade_areas
+FOR EACH ROW EXECUTE PROCEDURE create_shade_area_tombstone();
+
+ALTER TYPE history_entity_type ADD VALUE 'shade_area';
@@ -0,0 +1,19 @@
+-- This file should undo anything in `up.sql`
+
+DELETE FROM map_history WHERE entity_type::text = 'soil_zone';
+ALTER TYPE history_entity_type RENAME TO history_entity_type_old;
+CREATE TYPE history_entity_type AS ENUM (
+    'planting', 'layer', 'base_layer_image', 'shade_area'
+);
+ALTER TABLE map_history
+ALTER COLUMN entity_type TYPE history_entity_type
+USING entity_type::text::history_entity_type;
+DROP TYPE history_entity_type_old;
+
+DROP TRIGGER soil_zones_create_tombstone ON soil_zones;
+DROP FUNCTION create_soil_zone_tombstone;
+DELETE FROM tombstones WHERE soil_zone_id IS NOT NULL;
+ALTER TABLE tombstones DROP COLUMN soil_zone_id;
+
+DROP TABLE soil_zones;
+DROP FUNCTION check_soil_layer_type;
@@ -0,0 +1,47 @@
+-- Zones on a soil layer that have the same pH value and texture.
+CREATE TABLE soil_zones (
+    id uuid PRIMARY KEY,
+    layer_id integer NOT NULL,
+    soil_ph soil_ph NOT NULL,
+    soil_texture soil_texture NOT NULL,
+    geometry geometry (POLYGON, 4326) NOT NULL,
+    updated_at timestamp NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    FOREIGN KEY (layer_id) REFERENCES layers (id) ON DELETE CASCADE
+);
+
+CREATE INDEX soil_zones_layer_id_idx ON soil_zones (layer_id);
+
+CREATE FUNCTION check_soil_layer_type() RETURNS trigger AS $$
+BEGIN
+    IF (SELECT type FROM layers WHERE id = NEW.layer_id) != 'soil' THEN
+        RAISE EXCEPTION 'Layer type must be "soil"';
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER soil_zones_check_layer_type
+BEFORE INSERT OR UPDATE ON soil_zones
+FOR EACH ROW EXECUTE PROCEDURE check_soil_layer_type();
+CREATE TRIGGER soil_zones_set_updated_at
+BEFORE INSERT OR UPDATE ON soil_zones
+FOR EACH ROW EXECUTE PROCEDURE set_updated_at();
+
+-- Soil zones are deleted permanently like shade areas.
+ALTER TABLE tombstones ADD COLUMN soil_zone_id uuid;
+
+-- Zones deleted together with their layer don't need a tombstone,
+-- in this case the layer can't be found anymore and nothing is inserted.
+CREATE FUNCTION create_soil_zone_tombstone() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO tombstones (map_id, soil_zone_id)
+    SELECT layers.map_id, OLD.id FROM layers WHERE layers.id = OLD.layer_id;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER soil_zones_create_tombstone
+AFTER DELETE ON soil_zones
+FOR EACH ROW EXECUTE PROCEDURE create_soil_zone_tombstone();
+
+ALTER TYPE history_entity_type ADD VALUE 'soil_zone';
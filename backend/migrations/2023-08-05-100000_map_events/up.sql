@@ -0,0 +1,10 @@
+CREATE TABLE map_events (
+    id bigserial PRIMARY KEY,
+    map_id integer NOT NULL,
+    data text NOT NULL,
+    layer_ids integer [],
+    created_at timestamp NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    FOREIGN KEY (map_id) REFERENCES maps (id) ON DELETE CASCADE
+);
+
+CREATE INDEX map_events_map_id_idx ON map_events (map_id, id);
@@ -0,0 +1,14 @@
+CREATE TABLE map_clients (
+    map_id integer NOT NULL,
+    user_id uuid NOT NULL,
+    client_id uuid NOT NULL,
+    connection_id uuid NOT NULL,
+    selected_layer_id integer,
+    layer_ids integer [],
+    expires_at timestamp NOT NULL,
+    PRIMARY KEY (map_id, user_id, client_id),
+    FOREIGN KEY (map_id) REFERENCES maps (id) ON DELETE CASCADE
+);
+
+CREATE INDEX map_clients_connection_id_idx ON map_clients (connection_id);
+CREATE INDEX map_clients_expires_at_idx ON map_clients (expires_at);
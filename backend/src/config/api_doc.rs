@@ -10,23 +10,41 @@ use utoipa_swagger_ui::SwaggerUi;
 use super::auth::Config;
 use crate::{
     controller::{
-        base_layer_image, blossoms, config, guided_tours, layers, map, plant_layer,
-        planting_suggestions, plantings, plants, seed, users,
+        base_layer_image, blossoms, config, guided_tours, layers, map, map_collaborators,
+        plant_layer, planting_suggestions, plantings, plants, presence, seed, shade_areas,
+        soil_zones, users,
     },
     model::{
         dto::{
+            history::{HistoryEntityDto, MapHistoryEntryDto},
+            planting_warnings::{PlantingWarningDto, PlantingWarningKind},
             plantings::{
-                MovePlantingDto, NewPlantingDto, PlantingDto, TransformPlantingDto,
-                UpdatePlantingDto,
+                BatchCreatePlantingsDto, BatchDeletePlantingsDto, BatchUpdatePlantingDto,
+                BatchUpdatePlantingsDto, MovePlantingDto, NewPlantingDto, PlantingDto,
+                RestorePlantingDto, TransformPlantingDto, UpdatePlantingDto,
             },
-            BaseLayerImageDto, ConfigDto, Coordinates, GainedBlossomsDto, GuidedToursDto, LayerDto,
-            MapDto, NewLayerDto, NewMapDto, NewSeedDto, PageLayerDto, PageMapDto,
-            PagePlantsSummaryDto, PageSeedDto, PlantsSummaryDto, RelationDto, RelationsDto,
-            SeedDto, UpdateBaseLayerImageDto, UpdateGuidedToursDto, UpdateMapDto, UsersDto,
+            shade_areas::{
+                DeleteShadeAreaDto, NewShadeAreaDto, ShadeAreaDto, ShadeAtPointDto,
+                UpdateShadeAreaDto,
+            },
+            soil_zones::{DeleteSoilZoneDto, NewSoilZoneDto, SoilZoneDto, UpdateSoilZoneDto},
+            sync::{
+                SyncDeleteDto, SyncDto, SyncOperation, SyncOperationDto, SyncOperationResultDto,
+                SyncResultDto, SyncStatus, SyncUpdateBaseLayerImageDto,
+            },
+            BaseLayerImageDto, ConfigDto, Coordinates, DeleteLayerDto, DuplicateLayerDto,
+            GainedBlossomsDto, GuidedToursDto, LayerDto, LayerPreferencesDto, MapChangesDto,
+            MapCollaboratorDto, MapDto, NewLayerDto, NewMapCollaboratorDto, NewMapDto, NewSeedDto,
+            PageLayerDto, PageMapDto, PageMapHistoryEntryDto, PagePlantsSummaryDto, PageSeedDto,
+            PlantsSummaryDto, PromoteLayerDto, RelationDto, RelationsDto, RestoreLayerDto, SeedDto,
+            UpdateBaseLayerImageDto, UpdateGuidedToursDto, UpdateLayerDto,
+            UpdateLayerPreferencesDto, UpdateMapCollaboratorDto, UpdateMapDto,
+            UpdateSubscriptionDto, UpdateUserPresenceDto, UpdatedMapDto, UserPresenceDto, UsersDto,
         },
         r#enum::{
+            collaborator_role::CollaboratorRole, history_entity_type::HistoryEntityType,
             privacy_option::PrivacyOption, quality::Quality, quantity::Quantity,
-            relation_type::RelationType,
+            relation_type::RelationType, shade::Shade, soil_ph::SoilPh, soil_texture::SoilTexture,
         },
     },
 };
@@ -81,8 +99,15 @@ struct PlantsApiDoc;
     paths(
         map::find,
         map::find_by_id,
+        map::find_changes,
+        map::find_history,
         map::create,
-        map::update
+        map::update,
+        map::delete,
+        map::restore,
+        map::undo,
+        map::redo,
+        map::sync
     ),
     components(
         schemas(
@@ -90,14 +115,68 @@ struct PlantsApiDoc;
             MapDto,
             NewMapDto,
             UpdateMapDto,
+            UpdatedMapDto,
             PrivacyOption,
-            Coordinates
+            Coordinates,
+            MapChangesDto,
+            PageMapHistoryEntryDto,
+            MapHistoryEntryDto,
+            HistoryEntityDto,
+            HistoryEntityType,
+            SyncDto,
+            SyncOperationDto,
+            SyncOperation,
+            SyncDeleteDto,
+            SyncUpdateBaseLayerImageDto,
+            SyncResultDto,
+            SyncOperationResultDto,
+            SyncStatus
         )
     ),
     modifiers(&SecurityAddon)
 )]
 struct MapApiDoc;
 
+/// Struct used by [`utoipa`] to generate `OpenApi` documentation for all map collaborator endpoints.
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        map_collaborators::find,
+        map_collaborators::create,
+        map_collaborators::update,
+        map_collaborators::delete
+    ),
+    components(
+        schemas(
+            MapCollaboratorDto,
+            NewMapCollaboratorDto,
+            UpdateMapCollaboratorDto,
+            CollaboratorRole
+        )
+    ),
+    modifiers(&SecurityAddon)
+)]
+struct MapCollaboratorsApiDoc;
+
+/// Struct used by [`utoipa`] to generate `OpenApi` documentation for all presence endpoints.
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        presence::find,
+        presence::update,
+        presence::update_subscription
+    ),
+    components(
+        schemas(
+            UserPresenceDto,
+            UpdateUserPresenceDto,
+            UpdateSubscriptionDto
+        )
+    ),
+    modifiers(&SecurityAddon)
+)]
+struct PresenceApiDoc;
+
 /// Struct used by [`utoipa`] to generate `OpenApi` documentation for all layer endpoints.
 #[derive(OpenApi)]
 #[openapi(
@@ -105,12 +184,25 @@ struct MapApiDoc;
         layers::find,
         layers::find_by_id,
         layers::create,
-        layers::delete
+        layers::delete,
+        layers::restore,
+        layers::duplicate,
+        layers::promote,
+        layers::update,
+        layers::find_preferences,
+        layers::update_preferences
     ),
     components(
         schemas(
             LayerDto,
             NewLayerDto,
+            DuplicateLayerDto,
+            UpdateLayerDto,
+            DeleteLayerDto,
+            RestoreLayerDto,
+            PromoteLayerDto,
+            LayerPreferencesDto,
+            UpdateLayerPreferencesDto,
             PageLayerDto
         )
     ),
@@ -123,13 +215,16 @@ struct LayerApiDoc;
 #[openapi(
     paths(
         plant_layer::heatmap,
-        plant_layer::find_relations
+        plant_layer::find_relations,
+        plant_layer::find_warnings
     ),
     components(
         schemas(
             RelationsDto,
             RelationDto,
-            RelationType
+            RelationType,
+            PlantingWarningDto,
+            PlantingWarningKind
         )
     ),
     modifiers(&SecurityAddon)
@@ -155,6 +250,53 @@ struct PlantLayerApiDoc;
 )]
 struct BaseLayerImagesApiDoc;
 
+/// Struct used by [`utoipa`] to generate `OpenApi` documentation for all shade area endpoints.
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        shade_areas::find,
+        shade_areas::find_shade_at,
+        shade_areas::create,
+        shade_areas::update,
+        shade_areas::delete
+    ),
+    components(
+        schemas(
+            ShadeAreaDto,
+            NewShadeAreaDto,
+            UpdateShadeAreaDto,
+            DeleteShadeAreaDto,
+            ShadeAtPointDto,
+            Shade
+        )
+    ),
+    modifiers(&SecurityAddon)
+)]
+struct ShadeAreasApiDoc;
+
+/// Struct used by [`utoipa`] to generate `OpenApi` documentation for all soil zone endpoints.
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        soil_zones::find,
+        soil_zones::create,
+        soil_zones::update,
+        soil_zones::delete
+    ),
+    components(
+        schemas(
+            SoilZoneDto,
+            NewSoilZoneDto,
+            UpdateSoilZoneDto,
+            DeleteSoilZoneDto,
+            SoilPh,
+            SoilTexture
+        )
+    ),
+    modifiers(&SecurityAddon)
+)]
+struct SoilZonesApiDoc;
+
 /// Struct used by [`utoipa`] to generate `OpenApi` documentation for all plantings endpoints.
 #[derive(OpenApi)]
 #[openapi(
@@ -162,7 +304,11 @@ struct BaseLayerImagesApiDoc;
         plantings::find,
         plantings::create,
         plantings::update,
-        plantings::delete
+        plantings::delete,
+        plantings::restore,
+        plantings::create_batch,
+        plantings::update_batch,
+        plantings::delete_batch
     ),
     components(
         schemas(
@@ -170,7 +316,12 @@ struct BaseLayerImagesApiDoc;
             NewPlantingDto,
             UpdatePlantingDto,
             TransformPlantingDto,
-            MovePlantingDto
+            MovePlantingDto,
+            RestorePlantingDto,
+            BatchCreatePlantingsDto,
+            BatchUpdatePlantingDto,
+            BatchUpdatePlantingsDto,
+            BatchDeletePlantingsDto
         )
     ),
     modifiers(&SecurityAddon)
@@ -248,9 +399,13 @@ pub fn config(cfg: &mut web::ServiceConfig) {
     openapi.merge(PlantsApiDoc::openapi());
     openapi.merge(PlantingSuggestionsApiDoc::openapi());
     openapi.merge(MapApiDoc::openapi());
+    openapi.merge(MapCollaboratorsApiDoc::openapi());
+    openapi.merge(PresenceApiDoc::openapi());
     openapi.merge(LayerApiDoc::openapi());
     openapi.merge(PlantLayerApiDoc::openapi());
     openapi.merge(BaseLayerImagesApiDoc::openapi());
+    openapi.merge(ShadeAreasApiDoc::openapi());
+    openapi.merge(SoilZonesApiDoc::openapi());
     openapi.merge(PlantingsApiDoc::openapi());
     openapi.merge(UsersApiDoc::openapi());
 
@@ -1,9 +1,11 @@
 //! Configuration of the server.
 
-use std::env;
+use std::{env, time::Duration};
 
 use dotenvy::dotenv;
 
+use crate::sse::broadcaster::DEFAULT_PING_INTERVAL;
+
 /// Configuration data for the server.
 pub struct Config {
     /// The address and port the server should be started on.
@@ -16,6 +18,8 @@ pub struct Config {
     pub auth_discovery_uri: String,
     /// The `client_id` the frontend should use to log in its users.
     pub client_id: String,
+    /// The interval in which Server-Sent Events clients are pinged to find disconnected ones.
+    pub sse_ping_interval: Duration,
 }
 
 impl Config {
@@ -41,12 +45,17 @@ impl Config {
             .map_err(|_| "Failed to get AUTH_DISCOVERY_URI from environment.")?;
         let client_id = env::var("AUTH_CLIENT_ID")
             .map_err(|_| "Failed to get AUTH_CLIENT_ID from environment.")?;
+        let sse_ping_interval = match env::var("SSE_PING_INTERVAL") {
+            Ok(seconds) => Duration::from_secs(seconds.parse::<u64>().map_err(|e| e.to_string())?),
+            Err(_) => DEFAULT_PING_INTERVAL,
+        };
 
         Ok(Self {
             bind_address: (host, port),
             database_url,
             auth_discovery_uri,
             client_id,
+            sse_ping_interval,
         })
     }
 }
@@ -3,6 +3,7 @@
 mod claims;
 pub mod jwks;
 pub mod middleware;
+pub mod sse_user_info;
 pub mod user_info;
 
 use jsonwebtoken::jwk::JwkSet;
@@ -0,0 +1,55 @@
+//! Contains [`SseUserInfo`] which authenticates clients connecting to the event stream.
+
+use actix_http::StatusCode;
+use actix_utils::future::{ready, Ready};
+use actix_web::{web::Query, FromRequest};
+use serde::Deserialize;
+
+use crate::error::ServiceError;
+
+use super::{claims::Claims, user_info::UserInfo};
+
+/// Name of the cookie the token can be provided in.
+pub const TOKEN_COOKIE: &str = "access_token";
+
+/// Query parameter the token can be provided in.
+#[derive(Debug, Deserialize)]
+struct TokenQuery {
+    /// The access token of the user.
+    token: Option<String>,
+}
+
+/// Information about the user connecting to the event stream.
+///
+/// `EventSource` can't set the `Authorization` header, so the token is taken from the
+/// `token` query parameter or the [`TOKEN_COOKIE`] cookie instead.
+#[derive(Debug, Clone)]
+pub struct SseUserInfo(pub UserInfo);
+
+impl FromRequest for SseUserInfo {
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Error = ServiceError;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        _payload: &mut actix_http::Payload,
+    ) -> Self::Future {
+        let token = Query::<TokenQuery>::from_query(req.query_string())
+            .ok()
+            .and_then(|query| query.into_inner().token)
+            .or_else(|| {
+                req.cookie(TOKEN_COOKIE)
+                    .map(|cookie| cookie.value().to_owned())
+            });
+
+        ready(token.map_or_else(
+            || {
+                Err(ServiceError::new(
+                    StatusCode::UNAUTHORIZED,
+                    "missing token".to_owned(),
+                ))
+            },
+            |token| Claims::validate(&token).map(|claims| Self(claims.into())),
+        ))
+    }
+}
@@ -3,6 +3,7 @@
 use crate::sse::broadcaster::Broadcaster;
 use actix_web::web::Data;
 
+use crate::config::app::Config;
 use crate::db::connection;
 
 /// Data available to all controllers.
@@ -17,10 +18,19 @@ pub struct AppDataInner {
 ///
 /// # Panics
 /// If the database pool can not be initialized.
-#[must_use]
-pub fn init(database_url: &str) -> Data<AppDataInner> {
-    let pool = connection::init_pool(database_url);
-    let broadcaster = Broadcaster::new();
+/// If the connection for sharing broadcasts between instances can not be established.
+pub async fn init(config: &Config) -> Data<AppDataInner> {
+    let pool = connection::init_pool(&config.database_url);
+    let broadcaster = match Broadcaster::with_pubsub(
+        pool.clone(),
+        &config.database_url,
+        config.sse_ping_interval,
+    )
+    .await
+    {
+        Ok(broadcaster) => broadcaster,
+        Err(e) => panic!("Error while connecting broadcaster: {e}"),
+    };
 
     Data::new(AppDataInner { pool, broadcaster })
 }
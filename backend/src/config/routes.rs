@@ -5,8 +5,8 @@ use actix_web::{middleware::NormalizePath, web};
 use actix_web_httpauth::middleware::HttpAuthentication;
 
 use crate::controller::{
-    base_layer_image, blossoms, config, guided_tours, layers, map, plant_layer,
-    planting_suggestions, plantings, plants, seed, sse, users,
+    base_layer_image, blossoms, config, guided_tours, layers, map, map_collaborators, plant_layer,
+    planting_suggestions, plantings, plants, presence, seed, shade_areas, soil_zones, sse, users,
 };
 
 use super::auth::middleware::validator;
@@ -32,39 +32,29 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             web::scope("/maps")
                 .service(map::find)
                 .service(map::find_by_id)
+                .service(map::find_changes)
+                .service(map::find_history)
                 .service(map::create)
                 .service(map::update)
+                .service(map::delete)
+                .service(map::restore)
+                .service(map::undo)
+                .service(map::redo)
+                .service(map::sync)
                 .service(
-                    web::scope("/{map_id}/layers")
-                        .service(layers::find)
-                        .service(layers::find_by_id)
-                        .service(layers::create)
-                        .service(layers::delete)
-                        .service(
-                            web::scope("/base/images")
-                                .service(base_layer_image::create)
-                                .service(base_layer_image::update)
-                                .service(base_layer_image::delete),
-                        )
-                        .service(
-                            web::scope("/base/{layer_id}/images").service(base_layer_image::find),
-                        )
-                        .service(
-                            web::scope("/plants")
-                                .service(plant_layer::heatmap)
-                                .service(plant_layer::find_relations)
-                                .service(
-                                    web::scope("/suggestions").service(planting_suggestions::find),
-                                )
-                                .service(
-                                    web::scope("/plantings")
-                                        .service(plantings::find)
-                                        .service(plantings::create)
-                                        .service(plantings::update)
-                                        .service(plantings::delete),
-                                ),
-                        ),
-                ),
+                    web::scope("/{map_id}/collaborators")
+                        .service(map_collaborators::find)
+                        .service(map_collaborators::create)
+                        .service(map_collaborators::update)
+                        .service(map_collaborators::delete),
+                )
+                .service(
+                    web::scope("/{map_id}/presence")
+                        .service(presence::find)
+                        .service(presence::update)
+                        .service(presence::update_subscription),
+                )
+                .service(layers_scope()),
         )
         .service(
             web::scope("/tours")
@@ -77,8 +67,70 @@ pub fn config(cfg: &mut web::ServiceConfig) {
         .wrap(NormalizePath::trim())
         .wrap(auth);
 
-    let sse_route = web::scope("/api/updates/maps").service(sse::connect_to_map);
+    // Event streams authenticate with a token in the query or a cookie, as `EventSource` can't set headers.
+    let sse_route = web::scope("/api/updates").service(sse::connect_to_map);
     let config_route = web::scope("/api/config").service(config::get);
 
     cfg.service(sse_route).service(config_route).service(routes);
 }
+
+/// Defines the routes of the layers of a map and their content.
+fn layers_scope() -> actix_web::Scope {
+    web::scope("/{map_id}/layers")
+        .service(layers::find)
+        // Needs to be registered before the route matching `/{id}`.
+        .service(layers::find_preferences)
+        .service(layers::find_by_id)
+        .service(layers::create)
+        .service(layers::delete)
+        .service(layers::restore)
+        .service(layers::duplicate)
+        .service(layers::promote)
+        .service(layers::update)
+        .service(layers::update_preferences)
+        .service(
+            web::scope("/base/images")
+                .service(base_layer_image::create)
+                .service(base_layer_image::update)
+                .service(base_layer_image::delete),
+        )
+        .service(web::scope("/base/{layer_id}/images").service(base_layer_image::find))
+        .service(
+            web::scope("/shade/areas")
+                .service(shade_areas::create)
+                .service(shade_areas::update)
+                .service(shade_areas::delete),
+        )
+        .service(
+            web::scope("/shade/{layer_id}")
+                .service(shade_areas::find)
+                .service(shade_areas::find_shade_at),
+        )
+        .service(
+            web::scope("/soil/zones")
+                .service(soil_zones::create)
+                .service(soil_zones::update)
+                .service(soil_zones::delete),
+        )
+        .service(web::scope("/soil/{layer_id}").service(soil_zones::find))
+        .service(
+            web::scope("/plants")
+                .service(plant_layer::heatmap)
+                .service(plant_layer::find_relations)
+                .service(plant_layer::find_warnings)
+                .service(web::scope("/suggestions").service(planting_suggestions::find))
+                .service(
+                    web::scope("/plantings")
+                        .service(plantings::find)
+                        .service(plantings::create)
+                        // Batch routes need to be registered before
+                        // the routes matching `/{planting_id}`.
+                        .service(plantings::create_batch)
+                        .service(plantings::update_batch)
+                        .service(plantings::delete_batch)
+                        .service(plantings::update)
+                        .service(plantings::delete)
+                        .service(plantings::restore),
+                ),
+        )
+}
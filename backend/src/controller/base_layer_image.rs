@@ -38,9 +38,13 @@ use crate::{model::dto::UpdateBaseLayerImageDto, service::base_layer_images};
     )
 )]
 #[get("")]
-pub async fn find(path: Path<(i32, i32)>, app_data: Data<AppDataInner>) -> Result<HttpResponse> {
-    let (_map_id, layer_id) = path.into_inner();
-    let response = base_layer_images::find(&app_data, layer_id).await?;
+pub async fn find(
+    path: Path<(i32, i32)>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let (map_id, layer_id) = path.into_inner();
+    let response = base_layer_images::find(&app_data, map_id, layer_id, user_info.id).await?;
     Ok(HttpResponse::Ok().json(response))
 }
 
@@ -68,13 +72,15 @@ pub async fn create(
     app_data: Data<AppDataInner>,
     user_info: UserInfo,
 ) -> Result<HttpResponse> {
+    let map_id = path.into_inner();
     let create_dto = json.0;
-    let dto = base_layer_images::create(create_dto.clone(), &app_data).await?;
+    let dto =
+        base_layer_images::create(create_dto.clone(), map_id, user_info.id, &app_data).await?;
 
     app_data
         .broadcaster
         .broadcast(
-            path.into_inner(),
+            map_id,
             Action::CreateBaseLayerImage(CreateBaseLayerImageActionPayload::new(
                 dto.clone(),
                 user_info.id,
@@ -117,6 +123,8 @@ pub async fn update(
     let dto = base_layer_images::update(
         base_layer_image_id,
         update_base_layer_image.clone(),
+        map_id,
+        user_info.id,
         &app_data,
     )
     .await?;
@@ -163,7 +171,14 @@ pub async fn delete(
     let (map_id, base_layer_image_id) = path.into_inner();
     let delete_dto = json.0;
 
-    base_layer_images::delete_by_id(base_layer_image_id, &app_data).await?;
+    let image = base_layer_images::delete_by_id(
+        base_layer_image_id,
+        map_id,
+        user_info.id,
+        delete_dto.action_id,
+        &app_data,
+    )
+    .await?;
 
     app_data
         .broadcaster
@@ -171,6 +186,7 @@ pub async fn delete(
             map_id,
             Action::DeleteBaseLayerImage(DeleteBaseLayerImageActionPayload::new(
                 base_layer_image_id,
+                image.layer_id,
                 user_info.id,
                 delete_dto.action_id,
             )),
@@ -1,13 +1,25 @@
 //! Layer endpoints.
 
 use actix_web::{
-    delete, get, post,
+    delete, get, patch, post, put,
     web::{Data, Json, Path, Query},
     HttpResponse, Result,
 };
 
+use crate::config::auth::user_info::UserInfo;
+use crate::model::dto::actions::{
+    Action, BatchActionPayload, CreateLayerActionPayload, DeleteLayerActionPayload,
+    UpdateLayerActionPayload,
+};
 use crate::{config::data::AppDataInner, model::dto::LayerSearchParameters};
-use crate::{model::dto::NewLayerDto, service::layer};
+use crate::{
+    model::dto::{
+        DeleteLayerDto, DuplicateLayerDto, LayerDto, NewLayerDto, PromoteLayerDto, RestoreLayerDto,
+        UpdateLayerDto, UpdateLayerPreferencesDto,
+    },
+    service::layer,
+};
+use uuid::Uuid;
 
 /// Endpoint for searching layers.
 ///
@@ -30,12 +42,16 @@ use crate::{model::dto::NewLayerDto, service::layer};
 pub async fn find(
     search_query: Query<LayerSearchParameters>,
     map_id: Path<i32>,
+    user_info: UserInfo,
     app_data: Data<AppDataInner>,
 ) -> Result<HttpResponse> {
-    let mut search_params = search_query.into_inner();
-    search_params.map_id = Some(map_id.into_inner());
-
-    let response = layer::find(search_params, &app_data).await?;
+    let response = layer::find(
+        map_id.into_inner(),
+        search_query.into_inner(),
+        user_info.id,
+        &app_data,
+    )
+    .await?;
     Ok(HttpResponse::Ok().json(response))
 }
 
@@ -58,10 +74,11 @@ pub async fn find(
 #[get("/{id}")]
 pub async fn find_by_id(
     path: Path<(i32, i32)>,
+    user_info: UserInfo,
     app_data: Data<AppDataInner>,
 ) -> Result<HttpResponse> {
-    let (_, id) = path.into_inner();
-    let response = layer::find_by_id(id, &app_data).await?;
+    let (map_id, id) = path.into_inner();
+    let response = layer::find_by_id(id, map_id, user_info.id, &app_data).await?;
     Ok(HttpResponse::Ok().json(response))
 }
 
@@ -85,13 +102,34 @@ pub async fn find_by_id(
 #[post("")]
 pub async fn create(
     new_layer: Json<NewLayerDto>,
+    map_id: Path<i32>,
+    user_info: UserInfo,
     app_data: Data<AppDataInner>,
 ) -> Result<HttpResponse> {
-    let dto = layer::create(new_layer.0, &app_data).await?;
+    let map_id = map_id.into_inner();
+    let mut new_layer = new_layer.into_inner();
+    new_layer.map_id = map_id;
+
+    let action_id = new_layer.action_id;
+    let dto = layer::create(new_layer, user_info.id, &app_data).await?;
+
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::CreateLayer(CreateLayerActionPayload::new(
+                dto.clone(),
+                user_info.id,
+                action_id,
+            )),
+        )
+        .await;
+
     Ok(HttpResponse::Created().json(dto))
 }
 
-/// Endpoint for deleting a layer.
+/// Endpoint for marking a layer for deletion.
+/// The last main layer of a type every map needs can't be deleted.
 ///
 /// # Errors
 /// * If the connection to the database could not be established.
@@ -100,16 +138,295 @@ pub async fn create(
     params(
         ("map_id" = i32, Path, description = "The id of the map the layer is on"),
     ),
+    request_body = DeleteLayerDto,
     responses(
-        (status = 200, description = "Delete a layer")
+        (status = 200, description = "Delete a layer"),
+        (status = 409, description = "The layer is the last main layer of a required type")
     ),
     security(
         ("oauth2" = [])
     )
 )]
 #[delete("/{id}")]
-pub async fn delete(path: Path<(i32, i32)>, app_data: Data<AppDataInner>) -> Result<HttpResponse> {
-    let (_, layer_id) = path.into_inner();
-    layer::delete_by_id(layer_id, &app_data).await?;
+pub async fn delete(
+    path: Path<(i32, i32)>,
+    json: Json<DeleteLayerDto>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let (map_id, layer_id) = path.into_inner();
+    let action_id = json.action_id;
+    layer::delete_by_id(layer_id, map_id, user_info.id, action_id, &app_data).await?;
+
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::DeleteLayer(DeleteLayerActionPayload::new(
+                layer_id,
+                user_info.id,
+                action_id,
+            )),
+        )
+        .await;
+
     Ok(HttpResponse::Ok().finish())
 }
+
+/// Endpoint for restoring a layer that was marked for deletion together with its content.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+    ),
+    request_body = RestoreLayerDto,
+    responses(
+        (status = 200, description = "Restore a deleted layer", body = LayerDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[post("/{id}/restore")]
+pub async fn restore(
+    path: Path<(i32, i32)>,
+    json: Json<RestoreLayerDto>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let (map_id, layer_id) = path.into_inner();
+    let action_id = json.action_id;
+    let dto = layer::restore(layer_id, map_id, user_info.id, action_id, &app_data).await?;
+
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::CreateLayer(CreateLayerActionPayload::new(
+                dto.clone(),
+                user_info.id,
+                action_id,
+            )),
+        )
+        .await;
+
+    Ok(HttpResponse::Ok().json(dto))
+}
+
+/// Endpoint for duplicating a layer with all its content as a new alternative layer.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+    ),
+    request_body = DuplicateLayerDto,
+    responses(
+        (status = 201, description = "Duplicate a layer", body = LayerDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[post("/{id}/duplicate")]
+pub async fn duplicate(
+    path: Path<(i32, i32)>,
+    json: Json<DuplicateLayerDto>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let (map_id, layer_id) = path.into_inner();
+    let action_id = json.action_id;
+    let (dto, content_actions) =
+        layer::duplicate(layer_id, map_id, json.into_inner(), user_info.id, &app_data).await?;
+
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::CreateLayer(CreateLayerActionPayload::new(
+                dto.clone(),
+                user_info.id,
+                action_id,
+            )),
+        )
+        .await;
+
+    if !content_actions.is_empty() {
+        app_data
+            .broadcaster
+            .broadcast(
+                map_id,
+                Action::Batch(BatchActionPayload::new(
+                    content_actions,
+                    user_info.id,
+                    action_id,
+                )),
+            )
+            .await;
+    }
+
+    Ok(HttpResponse::Created().json(dto))
+}
+
+/// Endpoint for making an alternative layer the main layer of its type.
+/// The previous main layer becomes an alternative.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+    ),
+    request_body = PromoteLayerDto,
+    responses(
+        (status = 200, description = "Promote an alternative layer, returns the changed layers", body = VecLayerDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[post("/{id}/promote")]
+pub async fn promote(
+    path: Path<(i32, i32)>,
+    json: Json<PromoteLayerDto>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let (map_id, layer_id) = path.into_inner();
+    let action_id = json.action_id;
+    let changed = layer::promote(layer_id, map_id, user_info.id, action_id, &app_data).await?;
+
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            update_layers_action(&changed, user_info.id, action_id),
+        )
+        .await;
+
+    Ok(HttpResponse::Ok().json(changed))
+}
+
+/// Endpoint for renaming a layer or moving it to another position.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+    ),
+    request_body = UpdateLayerDto,
+    responses(
+        (status = 200, description = "Update a layer", body = LayerDto),
+        (status = 409, description = "Another layer of the map already has the name")
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[patch("/{id}")]
+pub async fn update(
+    path: Path<(i32, i32)>,
+    json: Json<UpdateLayerDto>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let (map_id, layer_id) = path.into_inner();
+    let action_id = json.action_id;
+    let changed =
+        layer::update(layer_id, map_id, json.into_inner(), user_info.id, &app_data).await?;
+
+    // Moving a layer can change the positions of the other layers as well.
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            update_layers_action(&changed, user_info.id, action_id),
+        )
+        .await;
+
+    Ok(HttpResponse::Ok().json(changed.first()))
+}
+
+/// Endpoint for fetching the preferences of the requesting user for the layers of a map.
+/// Layers without preferences are visible, opaque and not selected.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map"),
+    ),
+    responses(
+        (status = 200, description = "Fetch the layer preferences", body = Vec<LayerPreferencesDto>)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[get("/preferences")]
+pub async fn find_preferences(
+    map_id: Path<i32>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let response = layer::find_preferences(*map_id, user_info.id, &app_data).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Endpoint for setting the preferences of the requesting user for a layer.
+/// The preferences only affect the requesting user.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+    ),
+    request_body = UpdateLayerPreferencesDto,
+    responses(
+        (status = 200, description = "Set the layer preferences", body = LayerPreferencesDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[put("/{id}/preferences")]
+pub async fn update_preferences(
+    path: Path<(i32, i32)>,
+    json: Json<UpdateLayerPreferencesDto>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let (map_id, layer_id) = path.into_inner();
+    let response =
+        layer::update_preferences(layer_id, map_id, json.into_inner(), user_info.id, &app_data)
+            .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Layers changed by the same request are broadcast as one batch,
+/// so other clients never see only some of them changed.
+fn update_layers_action(changed: &[LayerDto], user_id: Uuid, action_id: Uuid) -> Action {
+    let actions = changed
+        .iter()
+        .map(|dto| {
+            Action::UpdateLayer(UpdateLayerActionPayload::new(
+                dto.clone(),
+                user_id,
+                action_id,
+            ))
+        })
+        .collect();
+    Action::Batch(BatchActionPayload::new(actions, user_id, action_id))
+}
@@ -2,19 +2,23 @@
 
 use actix_web::web::Query;
 use actix_web::{
-    get, patch, post,
+    delete, get, patch, post,
     web::{Data, Json, Path},
     HttpResponse, Result,
 };
 
 use crate::config::auth::user_info::UserInfo;
 use crate::config::data::AppDataInner;
-use crate::model::dto::{MapSearchParameters, PageParameters, UpdateMapDto};
+use crate::model::dto::actions::{Action, BatchActionPayload, UpdateMapActionPayload};
+use crate::model::dto::history::MapHistorySearchParameters;
+use crate::model::dto::sync::SyncDto;
+use crate::model::dto::{MapChangesParameters, MapSearchParameters, PageParameters, UpdateMapDto};
 use crate::{model::dto::NewMapDto, service};
 
 /// Endpoint for fetching or searching all [`Map`](crate::model::entity::Map).
 /// Search parameters are taken from the URLs query string (e.g. .../api/maps?is_inactive=false&per_page=5).
 /// If no page parameters are provided, the first page is returned.
+/// Only maps visible to the requesting user are returned.
 ///
 /// # Errors
 /// * If the connection to the database could not be established.
@@ -35,11 +39,13 @@ use crate::{model::dto::NewMapDto, service};
 pub async fn find(
     search_query: Query<MapSearchParameters>,
     page_query: Query<PageParameters>,
+    user_info: UserInfo,
     app_data: Data<AppDataInner>,
 ) -> Result<HttpResponse> {
     let response = service::map::find(
         search_query.into_inner(),
         page_query.into_inner(),
+        user_info.id,
         &app_data,
     )
     .await?;
@@ -60,8 +66,83 @@ pub async fn find(
     )
 )]
 #[get("/{map_id}")]
-pub async fn find_by_id(map_id: Path<i32>, app_data: Data<AppDataInner>) -> Result<HttpResponse> {
-    let response = service::map::find_by_id(*map_id, &app_data).await?;
+pub async fn find_by_id(
+    map_id: Path<i32>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let response = service::map::find_by_id(*map_id, user_info.id, &app_data).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Endpoint for fetching the changes of the content of a [`Map`](crate::model::entity::Map)
+/// since a point in time, e.g. to catch up after the connection was lost.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+/// * If the changes are older than the deleted content is kept.
+#[utoipa::path(
+    context_path = "/api/maps",
+    params(
+        MapChangesParameters
+    ),
+    responses(
+        (status = 200, description = "Fetch the changes of a map", body = MapChangesDto),
+        (status = 410, description = "The changes are no longer available")
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[get("/{map_id}/changes")]
+pub async fn find_changes(
+    map_id: Path<i32>,
+    query: Query<MapChangesParameters>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let response =
+        service::map::find_changes(*map_id, user_info.id, query.since, &app_data).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Endpoint for fetching the history of the changes made to a [`Map`](crate::model::entity::Map).
+/// Search parameters are taken from the URLs query string (e.g. `.../api/maps/1/history?entity_type=planting`).
+/// If no page parameters are provided, the first page is returned.
+/// The latest changes are returned first.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+/// * If the requesting user is not an owner of the map.
+#[utoipa::path(
+    context_path = "/api/maps",
+    params(
+        MapHistorySearchParameters,
+        PageParameters
+    ),
+    responses(
+        (status = 200, description = "Fetch the history of a map", body = PageMapHistoryEntryDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[get("/{map_id}/history")]
+pub async fn find_history(
+    map_id: Path<i32>,
+    search_query: Query<MapHistorySearchParameters>,
+    page_query: Query<PageParameters>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let response = service::map_history::find(
+        map_id.into_inner(),
+        user_info.id,
+        search_query.into_inner(),
+        page_query.into_inner(),
+        &app_data,
+    )
+    .await?;
     Ok(HttpResponse::Ok().json(response))
 }
 
@@ -97,7 +178,7 @@ pub async fn create(
     context_path = "/api/maps",
     request_body = UpdateMapDto,
     responses(
-        (status = 200, description = "Update a map", body = MapDto)
+        (status = 200, description = "Update a map", body = UpdatedMapDto)
     ),
     security(
         ("oauth2" = [])
@@ -110,12 +191,169 @@ pub async fn update(
     user_info: UserInfo,
     app_data: Data<AppDataInner>,
 ) -> Result<HttpResponse> {
-    let response = service::map::update(
-        map_update_json.0,
-        map_id.into_inner(),
-        user_info.id,
-        &app_data,
+    let map_id = map_id.into_inner();
+    let action_id = map_update_json.action_id;
+    let response = service::map::update(map_update_json.0, map_id, user_info.id, &app_data).await?;
+
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::UpdateMap(UpdateMapActionPayload::new(
+                response.map.clone(),
+                user_info.id,
+                action_id,
+            )),
+        )
+        .await;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Endpoint for marking a [`Map`](crate::model::entity::Map) for deletion.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps",
+    responses(
+        (status = 200, description = "Mark a map for deletion", body = MapDto)
+    ),
+    security(
+        ("oauth2" = [])
     )
-    .await?;
+)]
+#[delete("/{map_id}")]
+pub async fn delete(
+    map_id: Path<i32>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let response = service::map::delete_by_id(map_id.into_inner(), user_info.id, &app_data).await?;
     Ok(HttpResponse::Ok().json(response))
 }
+
+/// Endpoint for restoring a [`Map`](crate::model::entity::Map) that was marked for deletion.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps",
+    responses(
+        (status = 200, description = "Restore a deleted map", body = MapDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[post("/{map_id}/restore")]
+pub async fn restore(
+    map_id: Path<i32>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let response = service::map::restore(map_id.into_inner(), user_info.id, &app_data).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Endpoint for undoing the latest action of the requesting user on the map.
+///
+/// The undo is broadcast to all users of the map and returned as [`Action`].
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps",
+    responses(
+        (status = 200, description = "Undo the latest action of the user")
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[post("/{map_id}/undo")]
+pub async fn undo(
+    map_id: Path<i32>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let map_id = map_id.into_inner();
+    let action = service::map_action_log::undo(map_id, user_info.id, &app_data).await?;
+
+    app_data.broadcaster.broadcast(map_id, action.clone()).await;
+
+    Ok(HttpResponse::Ok().json(action))
+}
+
+/// Endpoint for redoing the latest undone action of the requesting user on the map.
+///
+/// The redo is broadcast to all users of the map and returned as [`Action`].
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps",
+    responses(
+        (status = 200, description = "Redo the latest undone action of the user")
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[post("/{map_id}/redo")]
+pub async fn redo(
+    map_id: Path<i32>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let map_id = map_id.into_inner();
+    let action = service::map_action_log::redo(map_id, user_info.id, &app_data).await?;
+
+    app_data.broadcaster.broadcast(map_id, action.clone()).await;
+
+    Ok(HttpResponse::Ok().json(action))
+}
+
+/// Endpoint for applying the operations done while editing the map offline.
+///
+/// The operations are applied in a single transaction in the order of their timestamps.
+/// Operations that conflict with changes made in the meantime are discarded,
+/// the result of every operation is returned.
+/// The accepted operations are broadcast as one [`Action::Batch`].
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps",
+    request_body = SyncDto,
+    responses(
+        (status = 200, description = "Apply the operations done offline", body = SyncResultDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[post("/{map_id}/sync")]
+pub async fn sync(
+    map_id: Path<i32>,
+    json: Json<SyncDto>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let map_id = map_id.into_inner();
+    let dto = json.into_inner();
+    let action_id = dto.action_id;
+    let (result, actions) = service::sync::sync(map_id, user_info.id, dto, &app_data).await?;
+
+    if !actions.is_empty() {
+        app_data
+            .broadcaster
+            .broadcast(
+                map_id,
+                Action::Batch(BatchActionPayload::new(actions, user_info.id, action_id)),
+            )
+            .await;
+    }
+
+    Ok(HttpResponse::Ok().json(result))
+}
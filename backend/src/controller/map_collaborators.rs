@@ -0,0 +1,145 @@
+//! Map collaborator endpoints.
+
+use actix_web::{
+    delete, get, patch, post,
+    web::{Data, Json, Path},
+    HttpResponse, Result,
+};
+use uuid::Uuid;
+
+use crate::config::auth::user_info::UserInfo;
+use crate::config::data::AppDataInner;
+use crate::model::dto::{NewMapCollaboratorDto, UpdateMapCollaboratorDto};
+use crate::service::map_collaborators;
+
+/// Endpoint for listing all collaborators of a map.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+/// * If the requesting user is not a member of the map.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/collaborators",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map"),
+    ),
+    responses(
+        (status = 200, description = "List all collaborators of the map", body = Vec<MapCollaboratorDto>)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[get("")]
+pub async fn find(
+    map_id: Path<i32>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let response = map_collaborators::find(map_id.into_inner(), user_info.id, &app_data).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Endpoint for inviting a user to collaborate on a map.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+/// * If the requesting user is not an owner of the map.
+/// * If the invited user already is a member of the map.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/collaborators",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map"),
+    ),
+    request_body = NewMapCollaboratorDto,
+    responses(
+        (status = 201, description = "Invite a collaborator", body = MapCollaboratorDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[post("")]
+pub async fn create(
+    new_collaborator: Json<NewMapCollaboratorDto>,
+    map_id: Path<i32>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let response = map_collaborators::create(
+        new_collaborator.into_inner(),
+        map_id.into_inner(),
+        user_info.id,
+        &app_data,
+    )
+    .await?;
+    Ok(HttpResponse::Created().json(response))
+}
+
+/// Endpoint for changing the role of a collaborator.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+/// * If the requesting user is not an owner of the map.
+/// * If the user is not a collaborator of the map.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/collaborators",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map"),
+        ("user_id" = Uuid, Path, description = "The id of the collaborator"),
+    ),
+    request_body = UpdateMapCollaboratorDto,
+    responses(
+        (status = 200, description = "Change the role of a collaborator", body = MapCollaboratorDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[patch("/{user_id}")]
+pub async fn update(
+    update: Json<UpdateMapCollaboratorDto>,
+    path: Path<(i32, Uuid)>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let (map_id, collaborator_id) = path.into_inner();
+    let response = map_collaborators::update(
+        update.into_inner(),
+        map_id,
+        collaborator_id,
+        user_info.id,
+        &app_data,
+    )
+    .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Endpoint for removing a collaborator from a map.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+/// * If the requesting user is not allowed to remove the collaborator.
+/// * If the user is not a collaborator of the map.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/collaborators",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map"),
+        ("user_id" = Uuid, Path, description = "The id of the collaborator"),
+    ),
+    responses(
+        (status = 200, description = "Remove a collaborator")
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[delete("/{user_id}")]
+pub async fn delete(
+    path: Path<(i32, Uuid)>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let (map_id, collaborator_id) = path.into_inner();
+    map_collaborators::delete(map_id, collaborator_id, user_info.id, &app_data).await?;
+    Ok(HttpResponse::Ok().finish())
+}
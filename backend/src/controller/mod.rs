@@ -6,10 +6,14 @@ pub mod config;
 pub mod guided_tours;
 pub mod layers;
 pub mod map;
+pub mod map_collaborators;
 pub mod plant_layer;
 pub mod planting_suggestions;
 pub mod plantings;
 pub mod plants;
+pub mod presence;
 pub mod seed;
+pub mod shade_areas;
+pub mod soil_zones;
 pub mod sse;
 pub mod users;
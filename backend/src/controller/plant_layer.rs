@@ -8,13 +8,17 @@ use actix_web::{
 
 use crate::{
     config::data::AppDataInner,
-    model::dto::{HeatMapQueryParams, RelationSearchParameters},
+    model::dto::{
+        planting_warnings::PlantingWarningsParameters, HeatMapQueryParams, RelationSearchParameters,
+    },
     service::plant_layer,
 };
 
 /// Endpoint for generating a heatmap signaling ideal locations for planting the plant.
 ///
 /// Grey pixels signal areas where the plant shouldn't be planted, while green areas signal ideal locations.
+/// Areas with more shade than the plant tolerates according to the main shade layer
+/// or with soil that doesn't suit the plant according to the main soil layer are greyed out.
 ///
 /// The resulting heatmap does represent actual coordinates, meaning the pixel at (0,0) is not necessarily at coordinates (0,0).
 /// Instead the image has to be moved and scaled to fit inside the maps boundaries.
@@ -107,3 +111,36 @@ pub async fn find_relations(
     let response = plant_layer::find_relations(search_query.into_inner(), &app_data).await?;
     Ok(HttpResponse::Ok().json(response))
 }
+
+/// Endpoint for finding the plantings on a layer that are placed somewhere their plant won't grow well,
+/// e.g. in more shade than the plant tolerates or on soil that doesn't suit the plant
+/// according to the main shade and soil layers of the map.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+/// * If the layer is not a plant layer of the map.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/plants",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+        PlantingWarningsParameters
+    ),
+    responses(
+        (status = 200, description = "Find the warnings of the plantings", body = Vec<PlantingWarningDto>),
+        (status = 400, description = "The layer is not a plant layer of the map")
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[get("/warnings")]
+pub async fn find_warnings(
+    search_query: Query<PlantingWarningsParameters>,
+    map_id: Path<i32>,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let response =
+        plant_layer::find_warnings(map_id.into_inner(), search_query.into_inner(), &app_data)
+            .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
@@ -9,20 +9,19 @@ use uuid::Uuid;
 
 use crate::{
     config::auth::user_info::UserInfo,
-    model::dto::actions::{
-        Action, UpdatePlantingAddDateActionPayload, UpdatePlantingRemoveDateActionPayload,
-    },
+    model::dto::actions::{Action, BatchActionPayload},
 };
 use crate::{
     config::data::AppDataInner,
     model::dto::actions::{
-        CreatePlantActionPayload, DeletePlantActionPayload, MovePlantActionPayload,
-        TransformPlantActionPayload,
+        CreatePlantActionPayload, DeletePlantActionPayload, RestorePlantActionPayload,
     },
 };
 use crate::{
     model::dto::plantings::{
-        DeletePlantingDto, NewPlantingDto, PlantingSearchParameters, UpdatePlantingDto,
+        BatchCreatePlantingsDto, BatchDeletePlantingsDto, BatchUpdatePlantingsDto,
+        DeletePlantingDto, NewPlantingDto, PlantingSearchParameters, RestorePlantingDto,
+        UpdatePlantingDto,
     },
     service::plantings,
 };
@@ -46,10 +45,18 @@ use crate::{
 )]
 #[get("")]
 pub async fn find(
+    map_id: Path<i32>,
     search_params: Query<PlantingSearchParameters>,
+    user_info: UserInfo,
     app_data: Data<AppDataInner>,
 ) -> Result<HttpResponse> {
-    let response = plantings::find(search_params.into_inner(), &app_data).await?;
+    let response = plantings::find(
+        map_id.into_inner(),
+        search_params.into_inner(),
+        user_info.id,
+        &app_data,
+    )
+    .await?;
     Ok(HttpResponse::Ok().json(response))
 }
 
@@ -77,13 +84,14 @@ pub async fn create(
     app_data: Data<AppDataInner>,
     user_info: UserInfo,
 ) -> Result<HttpResponse> {
+    let map_id = path.into_inner();
     let new_planting = json.0;
-    let dto = plantings::create(new_planting.clone(), &app_data).await?;
+    let dto = plantings::create(new_planting.clone(), map_id, user_info.id, &app_data).await?;
 
     app_data
         .broadcaster
         .broadcast(
-            path.into_inner(),
+            map_id,
             Action::CreatePlanting(CreatePlantActionPayload::new(
                 dto,
                 user_info.id,
@@ -106,7 +114,8 @@ pub async fn create(
     ),
     request_body = UpdatePlantingDto,
     responses(
-        (status = 200, description = "Update a planting", body = PlantingDto)
+        (status = 200, description = "Update a planting", body = PlantingDto),
+        (status = 409, description = "The planting was changed in the meantime, returns the current planting", body = PlantingDto)
     ),
     security(
         ("oauth2" = [])
@@ -122,28 +131,21 @@ pub async fn update(
     let (map_id, planting_id) = path.into_inner();
     let update_planting = json.0;
 
-    let planting = plantings::update(planting_id, update_planting, &app_data).await?;
-
-    let action = match update_planting {
-        UpdatePlantingDto::Transform(action_dto) => Action::TransformPlanting(
-            TransformPlantActionPayload::new(planting, user_info.id, action_dto.action_id),
-        ),
-        UpdatePlantingDto::Move(action_dto) => Action::MovePlanting(MovePlantActionPayload::new(
-            planting,
-            user_info.id,
-            action_dto.action_id,
-        )),
-        UpdatePlantingDto::UpdateAddDate(action_dto) => Action::UpdatePlantingAddDate(
-            UpdatePlantingAddDateActionPayload::new(planting, user_info.id, action_dto.action_id),
-        ),
-        UpdatePlantingDto::UpdateRemoveDate(action_dto) => {
-            Action::UpdatePlantingRemoveDate(UpdatePlantingRemoveDateActionPayload::new(
-                planting,
-                user_info.id,
-                action_dto.action_id,
-            ))
-        }
-    };
+    let planting = plantings::update(
+        planting_id,
+        update_planting,
+        map_id,
+        user_info.id,
+        &app_data,
+    )
+    .await?;
+
+    let action = Action::update_planting(
+        update_planting,
+        planting,
+        user_info.id,
+        update_planting.action_id(),
+    );
 
     app_data.broadcaster.broadcast(map_id, action).await;
 
@@ -177,7 +179,14 @@ pub async fn delete(
     let (map_id, planting_id) = path.into_inner();
     let delete_planting = json.0;
 
-    plantings::delete_by_id(planting_id, &app_data).await?;
+    let planting = plantings::delete_by_id(
+        planting_id,
+        map_id,
+        user_info.id,
+        delete_planting.action_id,
+        &app_data,
+    )
+    .await?;
 
     app_data
         .broadcaster
@@ -185,6 +194,7 @@ pub async fn delete(
             map_id,
             Action::DeletePlanting(DeletePlantActionPayload::new(
                 planting_id,
+                planting.layer_id,
                 user_info.id,
                 delete_planting.action_id,
             )),
@@ -193,3 +203,211 @@ pub async fn delete(
 
     Ok(HttpResponse::Ok().finish())
 }
+
+/// Endpoint for restoring a deleted `Planting`.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/plants/plantings",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+    ),
+    request_body = RestorePlantingDto,
+    responses(
+        (status = 200, description = "Restore a deleted planting", body = PlantingDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[post("/{planting_id}/restore")]
+pub async fn restore(
+    path: Path<(i32, Uuid)>,
+    json: Json<RestorePlantingDto>,
+    app_data: Data<AppDataInner>,
+    user_info: UserInfo,
+) -> Result<HttpResponse> {
+    let (map_id, planting_id) = path.into_inner();
+    let restore_planting = json.0;
+
+    let planting = plantings::restore_by_id(
+        planting_id,
+        map_id,
+        user_info.id,
+        restore_planting.action_id,
+        &app_data,
+    )
+    .await?;
+
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::RestorePlanting(RestorePlantActionPayload::new(
+                planting,
+                user_info.id,
+                restore_planting.action_id,
+            )),
+        )
+        .await;
+
+    Ok(HttpResponse::Ok().json(planting))
+}
+
+/// Endpoint for creating many `Planting`s at once.
+///
+/// All plantings are created in a single transaction and broadcast as one [`Action::Batch`].
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/plants/plantings",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+    ),
+    request_body = BatchCreatePlantingsDto,
+    responses(
+        (status = 201, description = "Create many plantings", body = Vec<PlantingDto>)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[post("/batch")]
+pub async fn create_batch(
+    path: Path<i32>,
+    json: Json<BatchCreatePlantingsDto>,
+    app_data: Data<AppDataInner>,
+    user_info: UserInfo,
+) -> Result<HttpResponse> {
+    let map_id = path.into_inner();
+    let BatchCreatePlantingsDto {
+        plantings: new_plantings,
+        action_id,
+    } = json.into_inner();
+
+    let dtos =
+        plantings::create_batch(new_plantings, map_id, user_info.id, action_id, &app_data).await?;
+
+    let actions = dtos
+        .iter()
+        .map(|dto| {
+            Action::CreatePlanting(CreatePlantActionPayload::new(*dto, user_info.id, action_id))
+        })
+        .collect();
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::Batch(BatchActionPayload::new(actions, user_info.id, action_id)),
+        )
+        .await;
+
+    Ok(HttpResponse::Created().json(dtos))
+}
+
+/// Endpoint for updating many `Planting`s at once.
+///
+/// All plantings are updated in a single transaction and broadcast as one [`Action::Batch`].
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/plants/plantings",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+    ),
+    request_body = BatchUpdatePlantingsDto,
+    responses(
+        (status = 200, description = "Update many plantings", body = Vec<PlantingDto>),
+        (status = 409, description = "A planting was changed in the meantime, returns its current state", body = PlantingDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[patch("/batch")]
+pub async fn update_batch(
+    path: Path<i32>,
+    json: Json<BatchUpdatePlantingsDto>,
+    app_data: Data<AppDataInner>,
+    user_info: UserInfo,
+) -> Result<HttpResponse> {
+    let map_id = path.into_inner();
+    let BatchUpdatePlantingsDto { updates, action_id } = json.into_inner();
+
+    let dtos = plantings::update_batch(updates.clone(), map_id, user_info.id, action_id, &app_data)
+        .await?;
+
+    let actions = updates
+        .iter()
+        .zip(dtos.iter())
+        .map(|(batch_update, dto)| {
+            Action::update_planting(batch_update.update, *dto, user_info.id, action_id)
+        })
+        .collect();
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::Batch(BatchActionPayload::new(actions, user_info.id, action_id)),
+        )
+        .await;
+
+    Ok(HttpResponse::Ok().json(dtos))
+}
+
+/// Endpoint for deleting many `Planting`s at once.
+///
+/// All plantings are deleted in a single transaction and broadcast as one [`Action::Batch`].
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/plants/plantings",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+    ),
+    request_body = BatchDeletePlantingsDto,
+    responses(
+        (status = 200, description = "Delete many plantings")
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[delete("/batch")]
+pub async fn delete_batch(
+    path: Path<i32>,
+    json: Json<BatchDeletePlantingsDto>,
+    app_data: Data<AppDataInner>,
+    user_info: UserInfo,
+) -> Result<HttpResponse> {
+    let map_id = path.into_inner();
+    let BatchDeletePlantingsDto { ids, action_id } = json.into_inner();
+
+    let plantings =
+        plantings::delete_batch(ids, map_id, user_info.id, action_id, &app_data).await?;
+
+    let actions = plantings
+        .into_iter()
+        .map(|planting| {
+            Action::DeletePlanting(DeletePlantActionPayload::new(
+                planting.id,
+                planting.layer_id,
+                user_info.id,
+                action_id,
+            ))
+        })
+        .collect();
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::Batch(BatchActionPayload::new(actions, user_info.id, action_id)),
+        )
+        .await;
+
+    Ok(HttpResponse::Ok().finish())
+}
@@ -0,0 +1,100 @@
+//! Presence endpoints.
+
+use actix_web::{
+    get, put,
+    web::{Data, Json, Path},
+    HttpResponse, Result,
+};
+
+use crate::config::auth::user_info::UserInfo;
+use crate::config::data::AppDataInner;
+use crate::model::dto::{UpdateSubscriptionDto, UpdateUserPresenceDto};
+use crate::service::presence;
+
+/// Endpoint for listing all users currently connected to a map.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+/// * If the map is not visible to the requesting user.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/presence",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map"),
+    ),
+    responses(
+        (status = 200, description = "List all users connected to the map", body = Vec<UserPresenceDto>)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[get("")]
+pub async fn find(
+    map_id: Path<i32>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let response = presence::find(map_id.into_inner(), user_info.id, &app_data).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Endpoint for setting the layer the requesting user has selected on a map.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+/// * If the map is not visible to the requesting user.
+/// * If the layer is not part of the map.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/presence",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map"),
+    ),
+    request_body = UpdateUserPresenceDto,
+    responses(
+        (status = 200, description = "Set the selected layer of the user")
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[put("")]
+pub async fn update(
+    map_id: Path<i32>,
+    json: Json<UpdateUserPresenceDto>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    presence::update(map_id.into_inner(), user_info.id, json.0, &app_data).await?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Endpoint for setting the layers a client of the requesting user receives actions for.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+/// * If the map is not visible to the requesting user.
+/// * If any of the layers is not part of the map.
+/// * If the client is not connected to the map.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/presence",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map"),
+    ),
+    request_body = UpdateSubscriptionDto,
+    responses(
+        (status = 200, description = "Set the layers the client receives actions for")
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[put("/subscription")]
+pub async fn update_subscription(
+    map_id: Path<i32>,
+    json: Json<UpdateSubscriptionDto>,
+    user_info: UserInfo,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    presence::update_subscription(map_id.into_inner(), user_info.id, json.0, &app_data).await?;
+    Ok(HttpResponse::Ok().finish())
+}
@@ -0,0 +1,221 @@
+//! `ShadeArea` endpoints.
+
+use actix_web::{
+    delete, get, patch, post,
+    web::{Data, Json, Path, Query},
+    HttpResponse, Result,
+};
+use uuid::Uuid;
+
+use crate::{
+    config::{auth::user_info::UserInfo, data::AppDataInner},
+    model::dto::{
+        actions::{
+            Action, CreateShadeAreaActionPayload, DeleteShadeAreaActionPayload,
+            UpdateShadeAreaActionPayload,
+        },
+        shade_areas::{
+            DeleteShadeAreaDto, NewShadeAreaDto, ShadeAtPointParameters, UpdateShadeAreaDto,
+        },
+    },
+    service::shade_areas,
+};
+
+/// Endpoint for listing the shade areas of a shade layer.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/shade/{layer_id}",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+        ("layer_id" = i32, Path, description = "The id of the shade layer"),
+    ),
+    responses(
+        (status = 200, description = "Find shade areas", body = Vec<ShadeAreaDto>)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[get("/areas")]
+pub async fn find(path: Path<(i32, i32)>, app_data: Data<AppDataInner>) -> Result<HttpResponse> {
+    let (_map_id, layer_id) = path.into_inner();
+    let response = shade_areas::find(layer_id, &app_data).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Endpoint for finding out how much shade a point of the map gets.
+/// The point is taken from the URLs query string (e.g. `.../shade/1/point?x=100&y=200`).
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+/// * If the layer is not a shade layer of the map.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/shade/{layer_id}",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+        ("layer_id" = i32, Path, description = "The id of the shade layer"),
+        ShadeAtPointParameters
+    ),
+    responses(
+        (status = 200, description = "Find the shade at a point", body = ShadeAtPointDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[get("/point")]
+pub async fn find_shade_at(
+    path: Path<(i32, i32)>,
+    query: Query<ShadeAtPointParameters>,
+    app_data: Data<AppDataInner>,
+) -> Result<HttpResponse> {
+    let (map_id, layer_id) = path.into_inner();
+    let response =
+        shade_areas::find_shade_at(map_id, layer_id, query.into_inner(), &app_data).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Endpoint for creating a new shade area.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/shade/areas",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+    ),
+    request_body = NewShadeAreaDto,
+    responses(
+        (status = 201, description = "Create a shade area", body = ShadeAreaDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[post("")]
+pub async fn create(
+    path: Path<i32>,
+    json: Json<NewShadeAreaDto>,
+    app_data: Data<AppDataInner>,
+    user_info: UserInfo,
+) -> Result<HttpResponse> {
+    let map_id = path.into_inner();
+    let create_dto = json.into_inner();
+    let action_id = create_dto.action_id;
+    let dto = shade_areas::create(create_dto, map_id, user_info.id, &app_data).await?;
+
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::CreateShadeArea(CreateShadeAreaActionPayload::new(
+                dto.clone(),
+                user_info.id,
+                action_id,
+            )),
+        )
+        .await;
+
+    Ok(HttpResponse::Created().json(dto))
+}
+
+/// Endpoint for changing the intensity or the outline of a shade area.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/shade/areas",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+        ("shade_area_id" = Uuid, Path, description = "The id of the shade area to update"),
+    ),
+    request_body = UpdateShadeAreaDto,
+    responses(
+        (status = 200, description = "Update a shade area", body = ShadeAreaDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[patch("/{shade_area_id}")]
+pub async fn update(
+    path: Path<(i32, Uuid)>,
+    json: Json<UpdateShadeAreaDto>,
+    app_data: Data<AppDataInner>,
+    user_info: UserInfo,
+) -> Result<HttpResponse> {
+    let (map_id, shade_area_id) = path.into_inner();
+    let update_dto = json.into_inner();
+    let action_id = update_dto.action_id;
+    let dto =
+        shade_areas::update(shade_area_id, update_dto, map_id, user_info.id, &app_data).await?;
+
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::UpdateShadeArea(UpdateShadeAreaActionPayload::new(
+                dto.clone(),
+                user_info.id,
+                action_id,
+            )),
+        )
+        .await;
+
+    Ok(HttpResponse::Ok().json(dto))
+}
+
+/// Endpoint for deleting a shade area.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/shade/areas",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+        ("shade_area_id" = Uuid, Path, description = "The id of the shade area to delete"),
+    ),
+    request_body = DeleteShadeAreaDto,
+    responses(
+        (status = 200, description = "Delete a shade area")
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[delete("/{shade_area_id}")]
+pub async fn delete(
+    path: Path<(i32, Uuid)>,
+    json: Json<DeleteShadeAreaDto>,
+    app_data: Data<AppDataInner>,
+    user_info: UserInfo,
+) -> Result<HttpResponse> {
+    let (map_id, shade_area_id) = path.into_inner();
+    let delete_dto = json.into_inner();
+
+    let area = shade_areas::delete_by_id(
+        shade_area_id,
+        map_id,
+        user_info.id,
+        delete_dto.action_id,
+        &app_data,
+    )
+    .await?;
+
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::DeleteShadeArea(DeleteShadeAreaActionPayload::new(
+                shade_area_id,
+                area.layer_id,
+                user_info.id,
+                delete_dto.action_id,
+            )),
+        )
+        .await;
+
+    Ok(HttpResponse::Ok().finish())
+}
@@ -0,0 +1,186 @@
+//! `SoilZone` endpoints.
+
+use actix_web::{
+    delete, get, patch, post,
+    web::{Data, Json, Path},
+    HttpResponse, Result,
+};
+use uuid::Uuid;
+
+use crate::{
+    config::{auth::user_info::UserInfo, data::AppDataInner},
+    model::dto::{
+        actions::{
+            Action, CreateSoilZoneActionPayload, DeleteSoilZoneActionPayload,
+            UpdateSoilZoneActionPayload,
+        },
+        soil_zones::{DeleteSoilZoneDto, NewSoilZoneDto, UpdateSoilZoneDto},
+    },
+    service::soil_zones,
+};
+
+/// Endpoint for listing the soil zones of a soil layer.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/soil/{layer_id}",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+        ("layer_id" = i32, Path, description = "The id of the soil layer"),
+    ),
+    responses(
+        (status = 200, description = "Find soil zones", body = Vec<SoilZoneDto>)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[get("/zones")]
+pub async fn find(path: Path<(i32, i32)>, app_data: Data<AppDataInner>) -> Result<HttpResponse> {
+    let (_map_id, layer_id) = path.into_inner();
+    let response = soil_zones::find(layer_id, &app_data).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Endpoint for creating a new soil zone.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/soil/zones",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+    ),
+    request_body = NewSoilZoneDto,
+    responses(
+        (status = 201, description = "Create a soil zone", body = SoilZoneDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[post("")]
+pub async fn create(
+    path: Path<i32>,
+    json: Json<NewSoilZoneDto>,
+    app_data: Data<AppDataInner>,
+    user_info: UserInfo,
+) -> Result<HttpResponse> {
+    let map_id = path.into_inner();
+    let create_dto = json.into_inner();
+    let action_id = create_dto.action_id;
+    let dto = soil_zones::create(create_dto, map_id, user_info.id, &app_data).await?;
+
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::CreateSoilZone(CreateSoilZoneActionPayload::new(
+                dto.clone(),
+                user_info.id,
+                action_id,
+            )),
+        )
+        .await;
+
+    Ok(HttpResponse::Created().json(dto))
+}
+
+/// Endpoint for changing the soil conditions or the outline of a soil zone.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/soil/zones",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+        ("soil_zone_id" = Uuid, Path, description = "The id of the soil zone to update"),
+    ),
+    request_body = UpdateSoilZoneDto,
+    responses(
+        (status = 200, description = "Update a soil zone", body = SoilZoneDto)
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[patch("/{soil_zone_id}")]
+pub async fn update(
+    path: Path<(i32, Uuid)>,
+    json: Json<UpdateSoilZoneDto>,
+    app_data: Data<AppDataInner>,
+    user_info: UserInfo,
+) -> Result<HttpResponse> {
+    let (map_id, soil_zone_id) = path.into_inner();
+    let update_dto = json.into_inner();
+    let action_id = update_dto.action_id;
+    let dto = soil_zones::update(soil_zone_id, update_dto, map_id, user_info.id, &app_data).await?;
+
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::UpdateSoilZone(UpdateSoilZoneActionPayload::new(
+                dto.clone(),
+                user_info.id,
+                action_id,
+            )),
+        )
+        .await;
+
+    Ok(HttpResponse::Ok().json(dto))
+}
+
+/// Endpoint for deleting a soil zone.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+#[utoipa::path(
+    context_path = "/api/maps/{map_id}/layers/soil/zones",
+    params(
+        ("map_id" = i32, Path, description = "The id of the map the layer is on"),
+        ("soil_zone_id" = Uuid, Path, description = "The id of the soil zone to delete"),
+    ),
+    request_body = DeleteSoilZoneDto,
+    responses(
+        (status = 200, description = "Delete a soil zone")
+    ),
+    security(
+        ("oauth2" = [])
+    )
+)]
+#[delete("/{soil_zone_id}")]
+pub async fn delete(
+    path: Path<(i32, Uuid)>,
+    json: Json<DeleteSoilZoneDto>,
+    app_data: Data<AppDataInner>,
+    user_info: UserInfo,
+) -> Result<HttpResponse> {
+    let (map_id, soil_zone_id) = path.into_inner();
+    let delete_dto = json.into_inner();
+
+    let zone = soil_zones::delete_by_id(
+        soil_zone_id,
+        map_id,
+        user_info.id,
+        delete_dto.action_id,
+        &app_data,
+    )
+    .await?;
+
+    app_data
+        .broadcaster
+        .broadcast(
+            map_id,
+            Action::DeleteSoilZone(DeleteSoilZoneActionPayload::new(
+                soil_zone_id,
+                zone.layer_id,
+                user_info.id,
+                delete_dto.action_id,
+            )),
+        )
+        .await;
+
+    Ok(HttpResponse::Ok().finish())
+}
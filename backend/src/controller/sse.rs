@@ -3,18 +3,45 @@
 use actix_web::{
     get,
     web::{Data, Query},
-    Responder,
+    Responder, Result,
 };
 
+use uuid::Uuid;
+
+use crate::config::auth::sse_user_info::SseUserInfo;
 use crate::config::data::AppDataInner;
 use crate::model::dto::ConnectToMapQueryParams;
+use crate::service;
+use crate::sse::last_event_id::LastEventId;
 
 /// Create a new SSE client.
-#[get("")]
+///
+/// The token of the user has to be provided in the `token` query parameter or the `access_token` cookie.
+/// Only users that are allowed to see the map can connect to it.
+/// Clients reconnecting with a `Last-Event-ID` header receive the actions they missed.
+/// Clients that provide a `client_id` can change the layers they receive actions for
+/// via [`update_subscription`](crate::controller::presence::update_subscription).
+///
+/// # Errors
+/// * If the token is missing or invalid.
+/// * If the map does not exist or is not visible to the user.
+#[get("/maps")]
 pub async fn connect_to_map(
     query: Query<ConnectToMapQueryParams>,
+    user_info: SseUserInfo,
+    last_event_id: LastEventId,
     state: Data<AppDataInner>,
-) -> impl Responder {
+) -> Result<impl Responder> {
     let query = query.into_inner();
-    state.broadcaster.new_client(query.map_id).await
+    let _ = service::map::find_by_id(query.map_id, user_info.0.id, &state).await?;
+
+    Ok(state
+        .broadcaster
+        .new_client(
+            query.map_id,
+            query.client_id.unwrap_or_else(Uuid::new_v4),
+            user_info.0.id,
+            last_event_id.0.as_deref(),
+        )
+        .await)
 }
@@ -1,6 +1,12 @@
 //! Scheduled tasks for the database.
+//!
+//! Timestamps are compared with the clock they were written with:
+//! dates like `deletion_date` are set by the backend in UTC,
+//! timestamps like `deleted_at` or `created_at` are set by the database in UTC.
 
-use chrono::{Days, Utc};
+use chrono::{Days, NaiveDate, Utc};
+use diesel::dsl::{now, IntervalDsl};
+use diesel::query_builder::{QueryFragment, QueryId};
 use diesel::{debug_query, pg::Pg, QueryDsl};
 use diesel::{BoolExpressionMethods, ExpressionMethods};
 use diesel_async::RunQueryDsl;
@@ -8,35 +14,134 @@ use log::debug;
 use std::time::Duration;
 
 use super::connection::Pool;
-use crate::schema::maps;
+use super::function::timezone;
+use crate::schema::{layers, map_clients, map_events, maps, plantings, tombstones};
 
 /// How often the deleted maps are cleaned up in seconds.
 const CLEANUP_MAPS_INTERVAL: u64 = 60 * 60 * 24;
 
-/// Permanently remove deleted maps older than 30 days from the database.
-/// Runs every [`CLEANUP_MAPS_INTERVAL`] seconds.
-pub async fn cleanup_maps(pool: Pool) -> ! {
-    loop {
-        tokio::time::sleep(Duration::from_secs(CLEANUP_MAPS_INTERVAL)).await;
+/// How often the deleted layers are cleaned up in seconds.
+const CLEANUP_LAYERS_INTERVAL: u64 = 60 * 60 * 24;
+
+/// How often the deleted plantings are cleaned up in seconds.
+const CLEANUP_PLANTINGS_INTERVAL: u64 = 60 * 60 * 24;
+
+/// How often the tombstones of deleted entities are cleaned up in seconds.
+const CLEANUP_TOMBSTONES_INTERVAL: u64 = 60 * 60 * 24;
+
+/// How often the broadcast events are cleaned up in seconds.
+const CLEANUP_MAP_EVENTS_INTERVAL: u64 = 60 * 10;
+
+/// How often the disconnected map clients are cleaned up in seconds.
+const CLEANUP_MAP_CLIENTS_INTERVAL: u64 = 60 * 10;
+
+/// How many minutes broadcast events are kept for clients that reconnect.
+pub const EVENT_RETENTION_MINUTES: i32 = 60;
 
-        log::info!("Running maps cleanup...");
+/// How many days deleted entities are kept before they are removed permanently.
+pub const DELETION_RETENTION_DAYS: u32 = 30;
 
-        let Some(one_month_ago) = Utc::now().date_naive().checked_sub_days(Days::new(30)) else {
-            log::error!("Failed to calculate date one month ago");
-            continue;
-        };
-        let query = diesel::delete(
+/// Permanently remove deleted maps older than [`DELETION_RETENTION_DAYS`] from the database.
+/// Runs every [`CLEANUP_MAPS_INTERVAL`] seconds.
+pub async fn cleanup_maps(pool: Pool) -> ! {
+    run_cleanup(pool, "maps", CLEANUP_MAPS_INTERVAL, || {
+        diesel::delete(
             maps::table.filter(
                 maps::deletion_date
                     .is_not_null()
-                    .and(maps::deletion_date.lt(one_month_ago)),
+                    .and(maps::deletion_date.lt(deletion_cutoff())),
+            ),
+        )
+    })
+    .await
+}
+
+/// Permanently remove layers marked for deletion older than [`DELETION_RETENTION_DAYS`]
+/// together with their content from the database.
+/// Runs every [`CLEANUP_LAYERS_INTERVAL`] seconds.
+pub async fn cleanup_layers(pool: Pool) -> ! {
+    run_cleanup(pool, "layers", CLEANUP_LAYERS_INTERVAL, || {
+        diesel::delete(
+            layers::table.filter(
+                layers::deletion_date
+                    .is_not_null()
+                    .and(layers::deletion_date.lt(deletion_cutoff())),
+            ),
+        )
+    })
+    .await
+}
+
+/// Permanently remove deleted plantings older than [`DELETION_RETENTION_DAYS`] from the database.
+/// Runs every [`CLEANUP_PLANTINGS_INTERVAL`] seconds.
+pub async fn cleanup_plantings(pool: Pool) -> ! {
+    run_cleanup(pool, "plantings", CLEANUP_PLANTINGS_INTERVAL, || {
+        diesel::delete(
+            plantings::table.filter(
+                plantings::delete_date
+                    .is_not_null()
+                    .and(plantings::delete_date.lt(deletion_cutoff())),
+            ),
+        )
+    })
+    .await
+}
+
+/// Permanently remove tombstones older than [`DELETION_RETENTION_DAYS`] from the database.
+/// Runs every [`CLEANUP_TOMBSTONES_INTERVAL`] seconds.
+pub async fn cleanup_tombstones(pool: Pool) -> ! {
+    run_cleanup(pool, "tombstones", CLEANUP_TOMBSTONES_INTERVAL, || {
+        diesel::delete(
+            tombstones::table.filter(
+                tombstones::deleted_at
+                    .lt(timezone("UTC", now) - i64::from(DELETION_RETENTION_DAYS).days()),
             ),
-        );
+        )
+    })
+    .await
+}
+
+/// Permanently remove broadcast events older than [`EVENT_RETENTION_MINUTES`] from the database.
+/// Runs every [`CLEANUP_MAP_EVENTS_INTERVAL`] seconds.
+pub async fn cleanup_map_events(pool: Pool) -> ! {
+    run_cleanup(pool, "map events", CLEANUP_MAP_EVENTS_INTERVAL, || {
+        diesel::delete(map_events::table.filter(
+            map_events::created_at.lt(timezone("UTC", now) - EVENT_RETENTION_MINUTES.minutes()),
+        ))
+    })
+    .await
+}
+
+/// Permanently remove map clients disconnected longer than [`EVENT_RETENTION_MINUTES`] from the database.
+///
+/// Clients reconnecting later have to reload the map anyway.
+/// Runs every [`CLEANUP_MAP_CLIENTS_INTERVAL`] seconds.
+pub async fn cleanup_map_clients(pool: Pool) -> ! {
+    run_cleanup(pool, "map clients", CLEANUP_MAP_CLIENTS_INTERVAL, || {
+        diesel::delete(map_clients::table.filter(
+            map_clients::expires_at.lt(timezone("UTC", now) - EVENT_RETENTION_MINUTES.minutes()),
+        ))
+    })
+    .await
+}
+
+/// Execute the delete query built by `query` every `interval_seconds` and log how many `name` were removed.
+async fn run_cleanup<F, Q>(pool: Pool, name: &str, interval_seconds: u64, query: F) -> !
+where
+    F: Fn() -> Q + Send,
+    Q: QueryFragment<Pg> + QueryId + Send,
+{
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval_seconds)).await;
+
+        log::info!("Running {name} cleanup...");
+
+        let query = query();
         debug!("{}", debug_query::<Pg, _>(&query));
 
         match pool.get().await {
             Ok(mut conn) => match query.execute(&mut conn).await {
-                Ok(delete_rows) => log::info!("Removed {delete_rows} maps"),
+                Ok(delete_rows) => log::info!("Removed {delete_rows} {name}"),
                 Err(e) => log::error!("Failed to execute query: {}", e),
             },
             Err(e) => {
@@ -45,3 +150,8 @@ pub async fn cleanup_maps(pool: Pool) -> ! {
         }
     }
 }
+
+/// Entities deleted before this date are removed permanently.
+fn deletion_cutoff() -> NaiveDate {
+    Utc::now().date_naive() - Days::new(DELETION_RETENTION_DAYS.into())
+}
@@ -4,7 +4,7 @@ use diesel::{
     expression::AsExpression,
     pg::Pg,
     sql_function,
-    sql_types::{Array, Float, Nullable, SqlType, Text},
+    sql_types::{Array, Float, Nullable, SqlType, Text, Timestamptz},
     Expression,
 };
 
@@ -18,6 +18,16 @@ sql_function! {
     ) -> Text
 }
 
+sql_function! {
+    /// The SQL function `timezone`.
+    ///
+    /// Used to convert a timestamp with time zone to the time in the given zone.
+    fn timezone(
+        zone: Text,
+        timestamp: Timestamptz
+    ) -> Timestamp
+}
+
 sql_function! {
     /// The `pg_trgm` SQL function `similarity`.
     ///
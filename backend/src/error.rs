@@ -7,6 +7,7 @@ use actix_web::{
 use derive_more::{Display, Error};
 use diesel::result::Error as DieselError;
 use diesel_async::pooled_connection::deadpool::PoolError;
+use serde::Serialize;
 
 /// The default error used by the server.
 #[derive(Debug, Display, Error)]
@@ -17,6 +18,8 @@ pub struct ServiceError {
     pub status_code: StatusCode,
     /// The reason for the error.
     pub reason: String,
+    /// If the reason is a JSON document instead of plain text.
+    pub is_json: bool,
 }
 
 impl ServiceError {
@@ -26,6 +29,21 @@ impl ServiceError {
         Self {
             status_code,
             reason,
+            is_json: false,
+        }
+    }
+
+    /// Creates a new service error responding with `content` as JSON,
+    /// e.g. to return the current state of an entity on a conflict.
+    #[must_use]
+    pub fn with_json<T: Serialize>(status_code: StatusCode, content: &T) -> Self {
+        match serde_json::to_string(content) {
+            Ok(reason) => Self {
+                status_code,
+                reason,
+                is_json: true,
+            },
+            Err(e) => Self::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
         }
     }
 }
@@ -36,8 +54,13 @@ impl ResponseError for ServiceError {
     }
 
     fn error_response(&self) -> HttpResponse {
+        let content_type = if self.is_json {
+            ContentType::json()
+        } else {
+            ContentType::plaintext()
+        };
         HttpResponse::build(self.status_code())
-            .insert_header(ContentType::plaintext())
+            .insert_header(content_type)
             .body(self.reason.clone())
     }
 }
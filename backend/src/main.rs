@@ -50,9 +50,15 @@
 #![allow(clippy::multiple_crate_versions)]
 
 use actix_cors::Cors;
-use actix_web::{http, middleware::Logger, App, HttpServer};
+use actix_web::{dev::ServiceRequest, http, middleware::Logger, App, HttpServer};
 use config::{api_doc, auth::Config, routes};
-use db::{connection::Pool, cronjobs::cleanup_maps};
+use db::{
+    connection::Pool,
+    cronjobs::{
+        cleanup_layers, cleanup_map_clients, cleanup_map_events, cleanup_maps, cleanup_plantings,
+        cleanup_tombstones,
+    },
+};
 use log::info;
 
 pub mod config;
@@ -85,7 +91,7 @@ async fn main() -> std::io::Result<()> {
         config.bind_address.0, config.bind_address.1
     );
 
-    let data = config::data::init(&config.database_url);
+    let data = config::data::init(&config).await;
     start_cronjobs(data.pool.clone());
 
     HttpServer::new(move || {
@@ -94,7 +100,7 @@ async fn main() -> std::io::Result<()> {
             .app_data(data.clone())
             .configure(routes::config)
             .configure(api_doc::config)
-            .wrap(Logger::default())
+            .wrap(logger_configuration())
     })
     .shutdown_timeout(5)
     .bind(config.bind_address)?
@@ -119,7 +125,33 @@ fn cors_configuration() -> Cors {
         .max_age(3600)
 }
 
+/// Create the access logger of the server.
+///
+/// Uses the same format as [`Logger::default`],
+/// but leaves out the query of requests for Server-Sent Events, as it may contain the access token of the user.
+fn logger_configuration() -> Logger {
+    Logger::new(r#"%a "%{request_line}xi" %s %b "%{Referer}i" "%{User-Agent}i" %T"#)
+        .custom_request_replace("request_line", request_line)
+}
+
+/// Format the request line of `req` for the access log.
+fn request_line(req: &ServiceRequest) -> String {
+    let uri = req.uri();
+    let path = if uri.path().starts_with("/api/updates") {
+        uri.path()
+    } else {
+        uri.path_and_query()
+            .map_or_else(|| uri.path(), |path| path.as_str())
+    };
+    format!("{} {} {:?}", req.method(), path, req.version())
+}
+
 /// Start all scheduled jobs that get run in the backend.
 fn start_cronjobs(pool: Pool) {
-    tokio::spawn(cleanup_maps(pool));
+    tokio::spawn(cleanup_maps(pool.clone()));
+    tokio::spawn(cleanup_layers(pool.clone()));
+    tokio::spawn(cleanup_plantings(pool.clone()));
+    tokio::spawn(cleanup_map_events(pool.clone()));
+    tokio::spawn(cleanup_map_clients(pool.clone()));
+    tokio::spawn(cleanup_tombstones(pool));
 }
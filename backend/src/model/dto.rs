@@ -1,36 +1,50 @@
 //! DTOs of `PermaplanT`.
 #![allow(clippy::module_name_repetitions)] // There needs to be a difference between DTOs and entities otherwise imports will be messy.
 
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
 use postgis_diesel::types::{Point, Polygon};
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
+use self::history::MapHistoryEntryDto;
 use self::plantings::PlantingDto;
+use self::shade_areas::ShadeAreaDto;
+use self::soil_zones::SoilZoneDto;
 
 use super::r#enum::{
-    experience::Experience, layer_type::LayerType, membership::Membership,
-    plant_spread::PlantSpread, privacy_option::PrivacyOption, quality::Quality, quantity::Quantity,
-    relation_type::RelationType, salutation::Salutation,
+    collaborator_role::CollaboratorRole, experience::Experience, layer_type::LayerType,
+    membership::Membership, plant_spread::PlantSpread, privacy_option::PrivacyOption,
+    quality::Quality, quantity::Quantity, relation_type::RelationType, salutation::Salutation,
 };
 
+pub mod action_log;
 pub mod actions;
 pub mod base_layer_images_impl;
 pub mod blossoms_impl;
 pub mod coordinates_impl;
 pub mod guided_tours_impl;
+pub mod history;
+pub mod history_impl;
 pub mod layer_impl;
+pub mod layer_preferences_impl;
+pub mod map_collaborator_impl;
 pub mod map_impl;
 pub mod new_layer_impl;
 pub mod new_map_impl;
 pub mod new_seed_impl;
 pub mod page_impl;
+pub mod planting_warnings;
 pub mod plantings;
 pub mod plantings_impl;
 pub mod plants_impl;
 pub mod seed_impl;
+pub mod shade_areas;
+pub mod shade_areas_impl;
+pub mod soil_zones;
+pub mod soil_zones_impl;
+pub mod sync;
 pub mod update_map_impl;
 pub mod users_impl;
 
@@ -180,7 +194,8 @@ pub struct PageParameters {
     PagePlantsSummaryDto = Page<PlantsSummaryDto>,
     PageSeedDto = Page<SeedDto>,
     PageMapDto = Page<MapDto>,
-    PageLayerDto = Page<LayerDto>
+    PageLayerDto = Page<LayerDto>,
+    PageMapHistoryEntryDto = Page<MapHistoryEntryDto>
 )]
 pub struct Page<T> {
     /// Resulting records.
@@ -210,7 +225,7 @@ pub struct TimelinePage<T> {
 
 /// The whole information of a map.
 #[typeshare]
-#[derive(Serialize, Deserialize, ToSchema)]
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
 pub struct MapDto {
     /// The id of the map.
     pub id: i32,
@@ -302,6 +317,21 @@ pub struct UpdateMapDto {
     #[typeshare(serialized_as = "Option<object>")]
     #[schema(value_type = Option<Object>)]
     pub geometry: Option<Polygon<Point>>,
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// The result of updating a map.
+#[typeshare]
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct UpdatedMapDto {
+    /// The updated map.
+    pub map: MapDto,
+    /// Plantings that are placed outside of the geometry of the map.
+    ///
+    /// Only filled if the geometry was changed.
+    /// The plantings are kept, it is up to the user to move or remove them.
+    pub plantings_outside_geometry: Vec<PlantingDto>,
 }
 
 /// Query parameters for searching maps.
@@ -313,9 +343,15 @@ pub struct MapSearchParameters {
     /// Whether or not the map is active.
     pub is_inactive: Option<bool>,
     /// The owner of the map.
+    ///
+    /// Maps the user collaborates on are included as well.
     pub owner_id: Option<Uuid>,
     /// The selected privacy of the map.
     pub privacy: Option<PrivacyOption>,
+    /// Whether to search for deleted maps instead of existing ones.
+    ///
+    /// Deleted maps are only returned to their owners.
+    pub is_deleted: Option<bool>,
 }
 
 /// Support struct for transmitting latitude/longitude coordinates.
@@ -330,7 +366,7 @@ pub struct Coordinates {
 
 /// The whole information of a map version.
 #[typeshare]
-#[derive(Serialize, Deserialize, ToSchema)]
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
 pub struct LayerDto {
     /// The id of the layer.
     pub id: i32,
@@ -342,6 +378,8 @@ pub struct LayerDto {
     pub name: String,
     /// A flag indicating if this layer is an user created alternative.
     pub is_alternative: bool,
+    /// The position of the layer in the list of layers of the map, starting at 0.
+    pub order_index: i32,
 }
 
 /// The information of a layer neccessary for its creation.
@@ -356,6 +394,88 @@ pub struct NewLayerDto {
     pub name: String,
     /// A flag indicating if this layer is an user created alternative.
     pub is_alternative: bool,
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// Used to change a layer.
+/// Only the given values are changed.
+#[typeshare]
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct UpdateLayerDto {
+    /// The new name of the layer.
+    /// It has to be unique on the map.
+    pub name: Option<String>,
+    /// The new position of the layer in the list of layers of the map.
+    /// The following layers are moved back by one.
+    pub order_index: Option<i32>,
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// Used to mark a layer for deletion.
+/// The id of the layer is passed in the path.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct DeleteLayerDto {
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// Used to restore a layer marked for deletion.
+/// The id of the layer is passed in the path.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct RestoreLayerDto {
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// Used to make an alternative layer the main layer of its type.
+/// The id of the layer is passed in the path.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct PromoteLayerDto {
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// How a user wants to see a layer, independent of the other users of the map.
+#[typeshare]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LayerPreferencesDto {
+    /// The id of the layer.
+    pub layer_id: i32,
+    /// Whether the layer is shown.
+    pub is_visible: bool,
+    /// The opacity of the layer from 0 (transparent) to 1 (opaque).
+    pub opacity: f32,
+    /// Whether the layer is the alternative of its type the user works on.
+    /// If no alternative of a type is selected, the main layer is used.
+    pub is_selected: bool,
+}
+
+/// Used to change the preferences of the requesting user for a layer.
+#[typeshare]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateLayerPreferencesDto {
+    /// Whether the layer is shown.
+    pub is_visible: bool,
+    /// The opacity of the layer from 0 (transparent) to 1 (opaque).
+    pub opacity: f32,
+    /// Whether the layer is the alternative of its type the user works on.
+    /// Selecting a layer deselects the other layers of its type.
+    pub is_selected: bool,
+}
+
+/// The information needed to duplicate a layer.
+#[typeshare]
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DuplicateLayerDto {
+    /// The name of the new alternative layer.
+    pub name: String,
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
 }
 
 /// Query parameters for searching layers.
@@ -376,8 +496,86 @@ pub struct LayerSearchParameters {
 pub struct ConnectToMapQueryParams {
     /// The id of the map to connect to.
     pub map_id: i32,
-    /// The id of the user connecting to the map.
-    pub user_id: String,
+    /// The access token of the user connecting to the map.
+    /// Can be omitted if the token is provided as cookie.
+    pub token: Option<String>,
+    /// An id chosen by the client to identify its connection.
+    /// Required to change the layers the client is subscribed to.
+    pub client_id: Option<Uuid>,
+}
+
+/// A user currently connected to a map.
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct UserPresenceDto {
+    /// The id of the user.
+    pub user_id: Uuid,
+    /// The layer the user has selected.
+    pub selected_layer_id: Option<i32>,
+}
+
+/// The information for changing the layer a user has selected.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct UpdateUserPresenceDto {
+    /// The layer the user has selected.
+    pub selected_layer_id: Option<i32>,
+}
+
+/// Query parameters for fetching the changes of a map.
+#[typeshare]
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct MapChangesParameters {
+    /// Only changes at or after this point in time (UTC) are returned.
+    /// Use the `timestamp` of the previous [`MapChangesDto`].
+    /// Changes at or after that time are returned again, even if they were already contained.
+    #[typeshare(serialized_as = "String")]
+    pub since: NaiveDateTime,
+}
+
+/// The changes of the content of a map since a point in time.
+/// Entities created and updated afterwards are contained in full, deleted ones by their id.
+#[typeshare]
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct MapChangesDto {
+    /// The point in time (UTC) up to which all changes were fetched.
+    /// It is not later than the start of any transaction still running at that time,
+    /// as changes committed later get the start time of their transaction as timestamp.
+    /// Pass it as `since` to fetch the next changes.
+    #[typeshare(serialized_as = "String")]
+    pub timestamp: NaiveDateTime,
+    /// The created and updated plantings.
+    pub plantings: Vec<PlantingDto>,
+    /// The ids of the deleted plantings.
+    pub deleted_plantings: Vec<Uuid>,
+    /// The created and updated layers.
+    pub layers: Vec<LayerDto>,
+    /// The ids of the deleted layers.
+    /// The content of a deleted layer is deleted as well, even if it is not listed.
+    pub deleted_layers: Vec<i32>,
+    /// The created and updated base layer images.
+    pub base_layer_images: Vec<BaseLayerImageDto>,
+    /// The ids of the deleted base layer images.
+    pub deleted_base_layer_images: Vec<Uuid>,
+    /// The created and updated shade areas.
+    pub shade_areas: Vec<ShadeAreaDto>,
+    /// The ids of the deleted shade areas.
+    pub deleted_shade_areas: Vec<Uuid>,
+    /// The created and updated soil zones.
+    pub soil_zones: Vec<SoilZoneDto>,
+    /// The ids of the deleted soil zones.
+    pub deleted_soil_zones: Vec<Uuid>,
+}
+
+/// The information for changing the layers a client receives actions for.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateSubscriptionDto {
+    /// The id the client connected to the map with.
+    pub client_id: Uuid,
+    /// The layers the client receives actions for.
+    /// If `None`, the client receives the actions of all layers.
+    pub layer_ids: Option<Vec<i32>>,
 }
 
 /// Search parameters for plant suggestions.
@@ -405,7 +603,7 @@ pub enum SuggestionType {
 
 /// Contains information about an image displayed on the base layer.
 #[typeshare]
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct BaseLayerImageDto {
     /// The id of the image.
     pub id: Uuid,
@@ -512,3 +710,33 @@ pub struct GainedBlossomsDto {
     /// The date on which the user gained this Blossom.
     pub gained_date: NaiveDate,
 }
+
+#[typeshare]
+#[derive(Serialize, Deserialize, ToSchema)]
+/// A user collaborating on a map.
+pub struct MapCollaboratorDto {
+    /// The id of the map.
+    pub map_id: i32,
+    /// The id of the collaborating user.
+    pub user_id: Uuid,
+    /// The role of the user on the map.
+    pub role: CollaboratorRole,
+}
+
+#[typeshare]
+#[derive(Serialize, Deserialize, ToSchema)]
+/// The information necessary for inviting a user to a map.
+pub struct NewMapCollaboratorDto {
+    /// The id of the invited user.
+    pub user_id: Uuid,
+    /// The role of the user on the map.
+    pub role: CollaboratorRole,
+}
+
+#[typeshare]
+#[derive(Serialize, Deserialize, ToSchema)]
+/// The information for changing the role of a collaborator.
+pub struct UpdateMapCollaboratorDto {
+    /// The new role of the user on the map.
+    pub role: CollaboratorRole,
+}
@@ -0,0 +1,117 @@
+//! All DTOs associated with the action log used for undo and redo.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{
+    plantings::PlantingDto, shade_areas::ShadeAreaDto, soil_zones::SoilZoneDto, BaseLayerImageDto,
+};
+
+/// A change of a single entity caused by an action.
+///
+/// `before` and `after` are `None` if the entity did not exist before or after the change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum EntityChange {
+    /// A planting was created, updated, deleted or restored.
+    Planting {
+        /// The id of the planting.
+        id: Uuid,
+        /// The planting before the change.
+        before: Option<PlantingDto>,
+        /// The planting after the change.
+        after: Option<PlantingDto>,
+    },
+    /// A base layer image was created, updated or deleted.
+    BaseLayerImage {
+        /// The id of the image.
+        id: Uuid,
+        /// The image before the change.
+        before: Option<BaseLayerImageDto>,
+        /// The image after the change.
+        after: Option<BaseLayerImageDto>,
+    },
+    /// A shade area was created, updated or deleted.
+    ShadeArea {
+        /// The id of the shade area.
+        id: Uuid,
+        /// The shade area before the change.
+        before: Option<ShadeAreaDto>,
+        /// The shade area after the change.
+        after: Option<ShadeAreaDto>,
+    },
+    /// A soil zone was created, updated or deleted.
+    SoilZone {
+        /// The id of the soil zone.
+        id: Uuid,
+        /// The soil zone before the change.
+        before: Option<SoilZoneDto>,
+        /// The soil zone after the change.
+        after: Option<SoilZoneDto>,
+    },
+}
+
+/// An entity whose changes are recorded in the action log.
+pub trait LoggedEntity: Sized {
+    /// Describe a change of the entity with id `id`.
+    fn change(id: Uuid, before: Option<Self>, after: Option<Self>) -> EntityChange;
+}
+
+impl LoggedEntity for PlantingDto {
+    fn change(id: Uuid, before: Option<Self>, after: Option<Self>) -> EntityChange {
+        EntityChange::Planting { id, before, after }
+    }
+}
+
+impl LoggedEntity for BaseLayerImageDto {
+    fn change(id: Uuid, before: Option<Self>, after: Option<Self>) -> EntityChange {
+        EntityChange::BaseLayerImage { id, before, after }
+    }
+}
+
+impl LoggedEntity for ShadeAreaDto {
+    fn change(id: Uuid, before: Option<Self>, after: Option<Self>) -> EntityChange {
+        EntityChange::ShadeArea { id, before, after }
+    }
+}
+
+impl LoggedEntity for SoilZoneDto {
+    fn change(id: Uuid, before: Option<Self>, after: Option<Self>) -> EntityChange {
+        EntityChange::SoilZone { id, before, after }
+    }
+}
+
+impl EntityChange {
+    /// Describe a change of the entity with id `id`.
+    #[must_use]
+    pub fn new<T: LoggedEntity>(id: Uuid, before: Option<T>, after: Option<T>) -> Self {
+        T::change(id, before, after)
+    }
+
+    /// Get the change that reverts this change.
+    #[must_use]
+    pub fn inverse(self) -> Self {
+        match self {
+            Self::Planting { id, before, after } => Self::Planting {
+                id,
+                before: after,
+                after: before,
+            },
+            Self::BaseLayerImage { id, before, after } => Self::BaseLayerImage {
+                id,
+                before: after,
+                after: before,
+            },
+            Self::ShadeArea { id, before, after } => Self::ShadeArea {
+                id,
+                before: after,
+                after: before,
+            },
+            Self::SoilZone { id, before, after } => Self::SoilZone {
+                id,
+                before: after,
+                after: before,
+            },
+        }
+    }
+}
@@ -7,13 +7,20 @@
 // Don't make the `new` functions const, there might come more fields in the future.
 #![allow(clippy::missing_const_for_fn)]
 
-use crate::model::dto::plantings::PlantingDto;
+use crate::model::dto::plantings::{PlantingDto, UpdatePlantingDto};
+use crate::model::dto::shade_areas::ShadeAreaDto;
+use crate::model::dto::soil_zones::SoilZoneDto;
+use crate::model::r#enum::{
+    layer_type::LayerType, privacy_option::PrivacyOption, shade::Shade, soil_ph::SoilPh,
+    soil_texture::SoilTexture,
+};
 use chrono::NaiveDate;
+use postgis_diesel::types::{Point, Polygon};
 use serde::Serialize;
 use typeshare::typeshare;
 use uuid::Uuid;
 
-use super::BaseLayerImageDto;
+use super::{BaseLayerImageDto, Coordinates, LayerDto, MapDto};
 
 #[typeshare]
 #[derive(Debug, Serialize, Clone)]
@@ -25,6 +32,8 @@ pub enum Action {
     CreatePlanting(CreatePlantActionPayload),
     /// An action used to broadcast deletion of a plant.
     DeletePlanting(DeletePlantActionPayload),
+    /// An action used to broadcast restoration of a deleted plant.
+    RestorePlanting(RestorePlantActionPayload),
     /// An action used to broadcast movement of a plant.
     MovePlanting(MovePlantActionPayload),
     /// An action used to broadcast transformation of a plant.
@@ -35,28 +44,134 @@ pub enum Action {
     UpdateBaseLayerImage(UpdateBaseLayerImageActionPayload),
     /// An action used to broadcast deletion of a baseLayerImage.
     DeleteBaseLayerImage(DeleteBaseLayerImageActionPayload),
+    /// An action used to broadcast creation of a shade area.
+    CreateShadeArea(CreateShadeAreaActionPayload),
+    /// An action used to broadcast update of a shade area.
+    UpdateShadeArea(UpdateShadeAreaActionPayload),
+    /// An action used to broadcast deletion of a shade area.
+    DeleteShadeArea(DeleteShadeAreaActionPayload),
+    /// An action used to broadcast creation of a soil zone.
+    CreateSoilZone(CreateSoilZoneActionPayload),
+    /// An action used to broadcast update of a soil zone.
+    UpdateSoilZone(UpdateSoilZoneActionPayload),
+    /// An action used to broadcast deletion of a soil zone.
+    DeleteSoilZone(DeleteSoilZoneActionPayload),
     /// An action used to update the `add_date` of a plant.
     UpdatePlantingAddDate(UpdatePlantingAddDateActionPayload),
     /// An action used to update the `remove_date` of a plant.
     UpdatePlantingRemoveDate(UpdatePlantingRemoveDateActionPayload),
+    /// An action used to broadcast creation of a layer.
+    CreateLayer(CreateLayerActionPayload),
+    /// An action used to broadcast an update of a layer, e.g. promoting an alternative.
+    UpdateLayer(UpdateLayerActionPayload),
+    /// An action used to broadcast deletion of a layer.
+    DeleteLayer(DeleteLayerActionPayload),
+    /// An action used to broadcast changes to the metadata of the map, e.g. its name or location.
+    UpdateMap(UpdateMapActionPayload),
+    /// An action used to broadcast many actions that were applied together.
+    Batch(BatchActionPayload),
+    /// An action used to broadcast that a user opened the map.
+    UserJoined(UserPresenceActionPayload),
+    /// An action used to broadcast that a user left the map.
+    UserLeft(UserPresenceActionPayload),
 }
 
 impl Action {
+    /// Get the action that broadcasts an update of a planting.
+    #[must_use]
+    pub fn update_planting(
+        update: UpdatePlantingDto,
+        planting: PlantingDto,
+        user_id: Uuid,
+        action_id: Uuid,
+    ) -> Self {
+        match update {
+            UpdatePlantingDto::Transform(_) => Self::TransformPlanting(
+                TransformPlantActionPayload::new(planting, user_id, action_id),
+            ),
+            UpdatePlantingDto::Move(_) => {
+                Self::MovePlanting(MovePlantActionPayload::new(planting, user_id, action_id))
+            }
+            UpdatePlantingDto::UpdateAddDate(_) => Self::UpdatePlantingAddDate(
+                UpdatePlantingAddDateActionPayload::new(planting, user_id, action_id),
+            ),
+            UpdatePlantingDto::UpdateRemoveDate(_) => Self::UpdatePlantingRemoveDate(
+                UpdatePlantingRemoveDateActionPayload::new(planting, user_id, action_id),
+            ),
+        }
+    }
+
     /// Returns the `action_id` of the action.
     #[must_use]
     pub fn action_id(&self) -> Uuid {
         match self {
             Self::CreatePlanting(payload) => payload.action_id,
             Self::DeletePlanting(payload) => payload.action_id,
+            Self::RestorePlanting(payload) => payload.action_id,
             Self::MovePlanting(payload) => payload.action_id,
             Self::TransformPlanting(payload) => payload.action_id,
             Self::CreateBaseLayerImage(payload) => payload.action_id,
             Self::UpdateBaseLayerImage(payload) => payload.action_id,
             Self::DeleteBaseLayerImage(payload) => payload.action_id,
+            Self::CreateShadeArea(payload) => payload.action_id,
+            Self::UpdateShadeArea(payload) => payload.action_id,
+            Self::DeleteShadeArea(payload) => payload.action_id,
+            Self::CreateSoilZone(payload) => payload.action_id,
+            Self::UpdateSoilZone(payload) => payload.action_id,
+            Self::DeleteSoilZone(payload) => payload.action_id,
             Self::UpdatePlantingAddDate(payload) => payload.action_id,
             Self::UpdatePlantingRemoveDate(payload) => payload.action_id,
+            Self::CreateLayer(payload) => payload.action_id,
+            Self::UpdateLayer(payload) => payload.action_id,
+            Self::DeleteLayer(payload) => payload.action_id,
+            Self::UpdateMap(payload) => payload.action_id,
+            Self::Batch(payload) => payload.action_id,
+            Self::UserJoined(payload) | Self::UserLeft(payload) => payload.action_id,
         }
     }
+
+    /// Returns the ids of the layers the action changes.
+    ///
+    /// Returns `None` if the action concerns the whole map, e.g. the list of layers or its users.
+    #[must_use]
+    pub fn layer_ids(&self) -> Option<Vec<i32>> {
+        let layer_id = match self {
+            Self::CreatePlanting(payload) => payload.layer_id,
+            Self::DeletePlanting(payload) => payload.layer_id,
+            Self::RestorePlanting(payload) => payload.layer_id,
+            Self::MovePlanting(payload) => payload.layer_id,
+            Self::TransformPlanting(payload) => payload.layer_id,
+            Self::CreateBaseLayerImage(payload) => payload.layer_id,
+            Self::UpdateBaseLayerImage(payload) => payload.layer_id,
+            Self::DeleteBaseLayerImage(payload) => payload.layer_id,
+            Self::CreateShadeArea(payload) => payload.layer_id,
+            Self::UpdateShadeArea(payload) => payload.layer_id,
+            Self::DeleteShadeArea(payload) => payload.layer_id,
+            Self::CreateSoilZone(payload) => payload.layer_id,
+            Self::UpdateSoilZone(payload) => payload.layer_id,
+            Self::DeleteSoilZone(payload) => payload.layer_id,
+            Self::UpdatePlantingAddDate(payload) => payload.layer_id,
+            Self::UpdatePlantingRemoveDate(payload) => payload.layer_id,
+            Self::Batch(payload) => {
+                let mut layer_ids = Vec::new();
+                for action in &payload.actions {
+                    for layer_id in action.layer_ids()? {
+                        if !layer_ids.contains(&layer_id) {
+                            layer_ids.push(layer_id);
+                        }
+                    }
+                }
+                return Some(layer_ids);
+            }
+            Self::CreateLayer(_)
+            | Self::UpdateLayer(_)
+            | Self::DeleteLayer(_)
+            | Self::UpdateMap(_)
+            | Self::UserJoined(_)
+            | Self::UserLeft(_) => return None,
+        };
+        Some(vec![layer_id])
+    }
 }
 
 #[typeshare]
@@ -79,6 +194,7 @@ pub struct CreatePlantActionPayload {
     scale_y: f32,
     add_date: Option<NaiveDate>,
     remove_date: Option<NaiveDate>,
+    version: i32,
 }
 
 impl CreatePlantActionPayload {
@@ -99,6 +215,7 @@ impl CreatePlantActionPayload {
             scale_y: payload.scale_y,
             add_date: payload.add_date,
             remove_date: payload.remove_date,
+            version: payload.version,
         }
     }
 }
@@ -111,15 +228,63 @@ pub struct DeletePlantActionPayload {
     user_id: Uuid,
     action_id: Uuid,
     id: Uuid,
+    layer_id: i32,
 }
 
 impl DeletePlantActionPayload {
     #[must_use]
-    pub fn new(id: Uuid, user_id: Uuid, action_id: Uuid) -> Self {
+    pub fn new(id: Uuid, layer_id: i32, user_id: Uuid, action_id: Uuid) -> Self {
         Self {
             user_id,
             action_id,
             id,
+            layer_id,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+/// The payload of the [`Action::RestorePlanting`].
+/// This struct should always match [`PlantingDto`].
+#[serde(rename_all = "camelCase")]
+pub struct RestorePlantActionPayload {
+    user_id: Uuid,
+    action_id: Uuid,
+    id: Uuid,
+    layer_id: i32,
+    plant_id: i32,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    rotation: f32,
+    scale_x: f32,
+    scale_y: f32,
+    add_date: Option<NaiveDate>,
+    remove_date: Option<NaiveDate>,
+    version: i32,
+}
+
+impl RestorePlantActionPayload {
+    #[must_use]
+    pub fn new(payload: PlantingDto, user_id: Uuid, action_id: Uuid) -> Self {
+        Self {
+            user_id,
+            action_id,
+            id: payload.id,
+            layer_id: payload.layer_id,
+            plant_id: payload.plant_id,
+            x: payload.x,
+            y: payload.y,
+            width: payload.width,
+            height: payload.height,
+            rotation: payload.rotation,
+            scale_x: payload.scale_x,
+            scale_y: payload.scale_y,
+            add_date: payload.add_date,
+            remove_date: payload.remove_date,
+            version: payload.version,
         }
     }
 }
@@ -132,8 +297,10 @@ pub struct MovePlantActionPayload {
     user_id: Uuid,
     action_id: Uuid,
     id: Uuid,
+    layer_id: i32,
     x: i32,
     y: i32,
+    version: i32,
 }
 
 impl MovePlantActionPayload {
@@ -143,8 +310,10 @@ impl MovePlantActionPayload {
             user_id,
             action_id,
             id: payload.id,
+            layer_id: payload.layer_id,
             x: payload.x,
             y: payload.y,
+            version: payload.version,
         }
     }
 }
@@ -157,11 +326,13 @@ pub struct TransformPlantActionPayload {
     user_id: Uuid,
     action_id: Uuid,
     id: Uuid,
+    layer_id: i32,
     x: i32,
     y: i32,
     rotation: f32,
     scale_x: f32,
     scale_y: f32,
+    version: i32,
 }
 
 impl TransformPlantActionPayload {
@@ -171,11 +342,13 @@ impl TransformPlantActionPayload {
             user_id,
             action_id,
             id: payload.id,
+            layer_id: payload.layer_id,
             x: payload.x,
             y: payload.y,
             rotation: payload.rotation,
             scale_x: payload.scale_x,
             scale_y: payload.scale_y,
+            version: payload.version,
         }
     }
 }
@@ -218,15 +391,17 @@ pub struct DeleteBaseLayerImageActionPayload {
     user_id: Uuid,
     action_id: Uuid,
     id: Uuid,
+    layer_id: i32,
 }
 
 impl DeleteBaseLayerImageActionPayload {
     #[must_use]
-    pub fn new(id: Uuid, user_id: Uuid, action_id: Uuid) -> Self {
+    pub fn new(id: Uuid, layer_id: i32, user_id: Uuid, action_id: Uuid) -> Self {
         Self {
             user_id,
             action_id,
             id,
+            layer_id,
         }
     }
 }
@@ -260,6 +435,172 @@ impl UpdateBaseLayerImageActionPayload {
     }
 }
 
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+/// The payload of the [`Action::CreateShadeArea`].
+/// This struct should always match [`ShadeAreaDto`].
+#[serde(rename_all = "camelCase")]
+pub struct CreateShadeAreaActionPayload {
+    user_id: Uuid,
+    action_id: Uuid,
+    id: Uuid,
+    layer_id: i32,
+    shade: Shade,
+    #[typeshare(serialized_as = "object")]
+    geometry: Polygon<Point>,
+}
+
+impl CreateShadeAreaActionPayload {
+    #[must_use]
+    pub fn new(payload: ShadeAreaDto, user_id: Uuid, action_id: Uuid) -> Self {
+        Self {
+            user_id,
+            action_id,
+            id: payload.id,
+            layer_id: payload.layer_id,
+            shade: payload.shade,
+            geometry: payload.geometry,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+/// The payload of the [`Action::UpdateShadeArea`].
+/// This struct should always match [`ShadeAreaDto`].
+#[serde(rename_all = "camelCase")]
+pub struct UpdateShadeAreaActionPayload {
+    user_id: Uuid,
+    action_id: Uuid,
+    id: Uuid,
+    layer_id: i32,
+    shade: Shade,
+    #[typeshare(serialized_as = "object")]
+    geometry: Polygon<Point>,
+}
+
+impl UpdateShadeAreaActionPayload {
+    #[must_use]
+    pub fn new(payload: ShadeAreaDto, user_id: Uuid, action_id: Uuid) -> Self {
+        Self {
+            user_id,
+            action_id,
+            id: payload.id,
+            layer_id: payload.layer_id,
+            shade: payload.shade,
+            geometry: payload.geometry,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+/// The payload of the [`Action::DeleteShadeArea`].
+#[serde(rename_all = "camelCase")]
+pub struct DeleteShadeAreaActionPayload {
+    user_id: Uuid,
+    action_id: Uuid,
+    id: Uuid,
+    layer_id: i32,
+}
+
+impl DeleteShadeAreaActionPayload {
+    #[must_use]
+    pub fn new(id: Uuid, layer_id: i32, user_id: Uuid, action_id: Uuid) -> Self {
+        Self {
+            user_id,
+            action_id,
+            id,
+            layer_id,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+/// The payload of the [`Action::CreateSoilZone`].
+/// This struct should always match [`SoilZoneDto`].
+#[serde(rename_all = "camelCase")]
+pub struct CreateSoilZoneActionPayload {
+    user_id: Uuid,
+    action_id: Uuid,
+    id: Uuid,
+    layer_id: i32,
+    soil_ph: SoilPh,
+    soil_texture: SoilTexture,
+    #[typeshare(serialized_as = "object")]
+    geometry: Polygon<Point>,
+}
+
+impl CreateSoilZoneActionPayload {
+    #[must_use]
+    pub fn new(payload: SoilZoneDto, user_id: Uuid, action_id: Uuid) -> Self {
+        Self {
+            user_id,
+            action_id,
+            id: payload.id,
+            layer_id: payload.layer_id,
+            soil_ph: payload.soil_ph,
+            soil_texture: payload.soil_texture,
+            geometry: payload.geometry,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+/// The payload of the [`Action::UpdateSoilZone`].
+/// This struct should always match [`SoilZoneDto`].
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSoilZoneActionPayload {
+    user_id: Uuid,
+    action_id: Uuid,
+    id: Uuid,
+    layer_id: i32,
+    soil_ph: SoilPh,
+    soil_texture: SoilTexture,
+    #[typeshare(serialized_as = "object")]
+    geometry: Polygon<Point>,
+}
+
+impl UpdateSoilZoneActionPayload {
+    #[must_use]
+    pub fn new(payload: SoilZoneDto, user_id: Uuid, action_id: Uuid) -> Self {
+        Self {
+            user_id,
+            action_id,
+            id: payload.id,
+            layer_id: payload.layer_id,
+            soil_ph: payload.soil_ph,
+            soil_texture: payload.soil_texture,
+            geometry: payload.geometry,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+/// The payload of the [`Action::DeleteSoilZone`].
+#[serde(rename_all = "camelCase")]
+pub struct DeleteSoilZoneActionPayload {
+    user_id: Uuid,
+    action_id: Uuid,
+    id: Uuid,
+    layer_id: i32,
+}
+
+impl DeleteSoilZoneActionPayload {
+    #[must_use]
+    pub fn new(id: Uuid, layer_id: i32, user_id: Uuid, action_id: Uuid) -> Self {
+        Self {
+            user_id,
+            action_id,
+            id,
+            layer_id,
+        }
+    }
+}
+
 #[typeshare]
 #[derive(Debug, Serialize, Clone)]
 /// The payload of the [`Action::UpdatePlantingAddDate`].
@@ -268,7 +609,9 @@ pub struct UpdatePlantingAddDateActionPayload {
     user_id: Uuid,
     action_id: Uuid,
     id: Uuid,
+    layer_id: i32,
     add_date: Option<NaiveDate>,
+    version: i32,
 }
 
 impl UpdatePlantingAddDateActionPayload {
@@ -278,7 +621,9 @@ impl UpdatePlantingAddDateActionPayload {
             user_id,
             action_id,
             id: payload.id,
+            layer_id: payload.layer_id,
             add_date: payload.add_date,
+            version: payload.version,
         }
     }
 }
@@ -291,7 +636,9 @@ pub struct UpdatePlantingRemoveDateActionPayload {
     user_id: Uuid,
     action_id: Uuid,
     id: Uuid,
+    layer_id: i32,
     remove_date: Option<NaiveDate>,
+    version: i32,
 }
 
 impl UpdatePlantingRemoveDateActionPayload {
@@ -301,7 +648,168 @@ impl UpdatePlantingRemoveDateActionPayload {
             user_id,
             action_id,
             id: payload.id,
+            layer_id: payload.layer_id,
             remove_date: payload.remove_date,
+            version: payload.version,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+/// The payload of the [`Action::CreateLayer`].
+/// This struct should always match [`LayerDto`].
+#[serde(rename_all = "camelCase")]
+pub struct CreateLayerActionPayload {
+    user_id: Uuid,
+    action_id: Uuid,
+    id: i32,
+    map_id: i32,
+    layer_type: LayerType,
+    name: String,
+    is_alternative: bool,
+    order_index: i32,
+}
+
+impl CreateLayerActionPayload {
+    #[must_use]
+    pub fn new(payload: LayerDto, user_id: Uuid, action_id: Uuid) -> Self {
+        Self {
+            user_id,
+            action_id,
+            id: payload.id,
+            map_id: payload.map_id,
+            layer_type: payload.type_,
+            name: payload.name,
+            is_alternative: payload.is_alternative,
+            order_index: payload.order_index,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+/// The payload of the [`Action::UpdateLayer`].
+/// This struct should always match [`LayerDto`].
+#[serde(rename_all = "camelCase")]
+pub struct UpdateLayerActionPayload {
+    user_id: Uuid,
+    action_id: Uuid,
+    id: i32,
+    map_id: i32,
+    layer_type: LayerType,
+    name: String,
+    is_alternative: bool,
+    order_index: i32,
+}
+
+impl UpdateLayerActionPayload {
+    #[must_use]
+    pub fn new(payload: LayerDto, user_id: Uuid, action_id: Uuid) -> Self {
+        Self {
+            user_id,
+            action_id,
+            id: payload.id,
+            map_id: payload.map_id,
+            layer_type: payload.type_,
+            name: payload.name,
+            is_alternative: payload.is_alternative,
+            order_index: payload.order_index,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+/// The payload of the [`Action::DeleteLayer`].
+#[serde(rename_all = "camelCase")]
+pub struct DeleteLayerActionPayload {
+    user_id: Uuid,
+    action_id: Uuid,
+    id: i32,
+}
+
+impl DeleteLayerActionPayload {
+    #[must_use]
+    pub fn new(id: i32, user_id: Uuid, action_id: Uuid) -> Self {
+        Self {
+            user_id,
+            action_id,
+            id,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+/// The payload of the [`Action::UpdateMap`].
+/// Only contains the metadata of [`MapDto`] that can be changed by the owner.
+#[serde(rename_all = "camelCase")]
+pub struct UpdateMapActionPayload {
+    user_id: Uuid,
+    action_id: Uuid,
+    id: i32,
+    name: String,
+    privacy: PrivacyOption,
+    description: Option<String>,
+    location: Option<Coordinates>,
+    #[typeshare(serialized_as = "object")]
+    geometry: Polygon<Point>,
+}
+
+impl UpdateMapActionPayload {
+    #[must_use]
+    pub fn new(payload: MapDto, user_id: Uuid, action_id: Uuid) -> Self {
+        Self {
+            user_id,
+            action_id,
+            id: payload.id,
+            name: payload.name,
+            privacy: payload.privacy,
+            description: payload.description,
+            location: payload.location,
+            geometry: payload.geometry,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+/// The payload of the [`Action::Batch`].
+/// All contained actions share the `action_id` of the batch.
+#[serde(rename_all = "camelCase")]
+pub struct BatchActionPayload {
+    user_id: Uuid,
+    action_id: Uuid,
+    actions: Vec<Action>,
+}
+
+impl BatchActionPayload {
+    #[must_use]
+    pub fn new(actions: Vec<Action>, user_id: Uuid, action_id: Uuid) -> Self {
+        Self {
+            user_id,
+            action_id,
+            actions,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Serialize, Clone)]
+/// The payload of the [`Action::UserJoined`] and [`Action::UserLeft`].
+#[serde(rename_all = "camelCase")]
+pub struct UserPresenceActionPayload {
+    user_id: Uuid,
+    action_id: Uuid,
+}
+
+impl UserPresenceActionPayload {
+    #[must_use]
+    pub fn new(user_id: Uuid) -> Self {
+        Self {
+            user_id,
+            action_id: Uuid::new_v4(),
         }
     }
 }
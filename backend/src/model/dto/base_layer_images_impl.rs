@@ -1,5 +1,6 @@
 //! Contains the implementations related to [`BaseLayerImageDto`].
 
+use chrono::Utc;
 use uuid::Uuid;
 
 use crate::model::entity::BaseLayerImages;
@@ -27,6 +28,7 @@ impl From<(Uuid, UpdateBaseLayerImageDto)> for BaseLayerImages {
             path: dto.path,
             rotation: dto.rotation,
             scale: dto.scale,
+            updated_at: Utc::now().naive_utc(),
         }
     }
 }
@@ -39,6 +41,7 @@ impl From<BaseLayerImageDto> for BaseLayerImages {
             path: dto.path,
             rotation: dto.rotation,
             scale: dto.scale,
+            updated_at: Utc::now().naive_utc(),
         }
     }
 }
@@ -0,0 +1,72 @@
+//! All DTOs associated with the history of the changes made to a map.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use super::plantings::PlantingDto;
+use super::shade_areas::ShadeAreaDto;
+use super::soil_zones::SoilZoneDto;
+use super::{BaseLayerImageDto, LayerDto};
+use crate::model::r#enum::history_entity_type::HistoryEntityType;
+
+/// The state of a changed entity.
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", content = "content")]
+pub enum HistoryEntityDto {
+    /// The state of a planting.
+    Planting(PlantingDto),
+    /// The state of a layer.
+    Layer(LayerDto),
+    /// The state of a base layer image.
+    BaseLayerImage(BaseLayerImageDto),
+    /// The state of a shade area.
+    ShadeArea(ShadeAreaDto),
+    /// The state of a soil zone.
+    SoilZone(SoilZoneDto),
+}
+
+/// A single change of an entity on a map.
+#[typeshare]
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct MapHistoryEntryDto {
+    /// The id of the user who made the change.
+    pub user_id: Uuid,
+    /// The id of the action that caused the change.
+    /// Changes caused by the same action share it.
+    pub action_id: Uuid,
+    /// The kind of the changed entity.
+    pub entity_type: HistoryEntityType,
+    /// The id of the changed layer or of the layer the changed entity is on.
+    pub layer_id: i32,
+    /// The id of the changed planting, base layer image, shade area or soil zone.
+    pub entity_id: Option<Uuid>,
+    /// The entity before the change, `None` if it was created.
+    pub before: Option<HistoryEntityDto>,
+    /// The entity after the change, `None` if it was deleted.
+    pub after: Option<HistoryEntityDto>,
+    /// The date and time (UTC) of the change.
+    #[typeshare(serialized_as = "String")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Query parameters for filtering the history of a map.
+#[typeshare]
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct MapHistorySearchParameters {
+    /// Only changes made by this user.
+    pub user_id: Option<Uuid>,
+    /// Only changes of this kind of entity.
+    pub entity_type: Option<HistoryEntityType>,
+    /// Only changes of this layer or of entities on it.
+    pub layer_id: Option<i32>,
+    /// Only changes of this planting, base layer image, shade area or soil zone.
+    pub entity_id: Option<Uuid>,
+    /// Only changes made on or after this date.
+    pub from: Option<NaiveDate>,
+    /// Only changes made on or before this date.
+    pub to: Option<NaiveDate>,
+}
@@ -0,0 +1,22 @@
+//! Contains the implementation of [`MapHistoryEntryDto`].
+
+use crate::model::entity::MapHistoryEntry;
+
+use super::history::MapHistoryEntryDto;
+
+impl TryFrom<MapHistoryEntry> for MapHistoryEntryDto {
+    type Error = serde_json::Error;
+
+    fn try_from(entry: MapHistoryEntry) -> Result<Self, Self::Error> {
+        Ok(Self {
+            user_id: entry.user_id,
+            action_id: entry.action_id,
+            entity_type: entry.entity_type,
+            layer_id: entry.layer_id,
+            entity_id: entry.entity_id,
+            before: entry.before.map(serde_json::from_value).transpose()?,
+            after: entry.after.map(serde_json::from_value).transpose()?,
+            created_at: entry.created_at,
+        })
+    }
+}
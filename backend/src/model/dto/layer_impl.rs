@@ -12,6 +12,7 @@ impl From<Layer> for LayerDto {
             type_: layer.type_,
             name: layer.name,
             is_alternative: layer.is_alternative,
+            order_index: layer.order_index,
         }
     }
 }
@@ -0,0 +1,16 @@
+//! Contains the implementation of [`LayerPreferencesDto`].
+
+use crate::model::entity::LayerPreferences;
+
+use super::LayerPreferencesDto;
+
+impl From<LayerPreferences> for LayerPreferencesDto {
+    fn from(preferences: LayerPreferences) -> Self {
+        Self {
+            layer_id: preferences.layer_id,
+            is_visible: preferences.is_visible,
+            opacity: preferences.opacity,
+            is_selected: preferences.is_selected,
+        }
+    }
+}
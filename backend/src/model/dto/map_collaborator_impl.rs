@@ -0,0 +1,25 @@
+//! Contains the implementation of [`MapCollaboratorDto`].
+
+use crate::model::entity::MapCollaborator;
+
+use super::{MapCollaboratorDto, NewMapCollaboratorDto};
+
+impl From<MapCollaborator> for MapCollaboratorDto {
+    fn from(collaborator: MapCollaborator) -> Self {
+        Self {
+            map_id: collaborator.map_id,
+            user_id: collaborator.user_id,
+            role: collaborator.role,
+        }
+    }
+}
+
+impl From<(NewMapCollaboratorDto, i32)> for MapCollaborator {
+    fn from((new_collaborator, map_id): (NewMapCollaboratorDto, i32)) -> Self {
+        Self {
+            map_id,
+            user_id: new_collaborator.user_id,
+            role: new_collaborator.role,
+        }
+    }
+}
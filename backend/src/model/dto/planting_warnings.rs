@@ -0,0 +1,39 @@
+//! All DTOs associated with [`PlantingWarningDto`].
+
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+/// What is wrong with the place of a planting.
+#[typeshare]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum PlantingWarningKind {
+    /// The place gets more shade than the plant tolerates.
+    Shade,
+    /// The pH value of the soil doesn't suit the plant.
+    SoilPh,
+    /// The texture of the soil doesn't suit the plant.
+    SoilTexture,
+}
+
+/// A planting placed somewhere its plant won't grow well.
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PlantingWarningDto {
+    /// The id of the planting.
+    pub planting_id: Uuid,
+    /// What is wrong with the place of the planting.
+    pub kind: PlantingWarningKind,
+    /// A description of the problem that can be shown to the user.
+    pub message: String,
+}
+
+/// Query parameters for finding the warnings of the plantings on a layer.
+#[typeshare]
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PlantingWarningsParameters {
+    /// The id of the plant layer the plantings are on.
+    pub layer_id: i32,
+}
@@ -9,7 +9,7 @@ use uuid::Uuid;
 /// Represents plant planted on a map.
 /// E.g. a user drags a plant from the search results and drops it on the map.
 #[typeshare]
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PlantingDto {
     /// The id of the planting.
@@ -38,6 +38,9 @@ pub struct PlantingDto {
     /// The date the planting was removed from the map.
     /// If None, the planting is still on the map.
     pub remove_date: Option<NaiveDate>,
+    /// The version of the planting.
+    /// It is incremented on every update to detect concurrent changes.
+    pub version: i32,
 }
 
 /// Used to create a new planting.
@@ -105,6 +108,9 @@ pub struct TransformPlantingDto {
     pub scale_x: f32,
     /// The y scale of the plant on the map.
     pub scale_y: f32,
+    /// The version of the planting the update is based on.
+    /// The update is rejected if the planting was changed in the meantime.
+    pub version: i32,
     /// Id of the action (for identifying the action in the frontend).
     pub action_id: Uuid,
 }
@@ -118,6 +124,9 @@ pub struct MovePlantingDto {
     pub x: i32,
     /// The y coordinate of the position on the map.
     pub y: i32,
+    /// The version of the planting the update is based on.
+    /// The update is rejected if the planting was changed in the meantime.
+    pub version: i32,
     /// Id of the action (for identifying the action in the frontend).
     pub action_id: Uuid,
 }
@@ -130,6 +139,9 @@ pub struct UpdateAddDatePlantingDto {
     /// The date the planting was added to the map.
     /// If None, the planting always existed.
     pub add_date: Option<NaiveDate>,
+    /// The version of the planting the update is based on.
+    /// The update is rejected if the planting was changed in the meantime.
+    pub version: i32,
     /// Id of the action (for identifying the action in the frontend).
     pub action_id: Uuid,
 }
@@ -142,6 +154,9 @@ pub struct UpdateRemoveDatePlantingDto {
     /// The date the planting was removed from the map.
     /// If None, the planting is still on the map.
     pub remove_date: Option<NaiveDate>,
+    /// The version of the planting the update is based on.
+    /// The update is rejected if the planting was changed in the meantime.
+    pub version: i32,
     /// Id of the action (for identifying the action in the frontend).
     pub action_id: Uuid,
 }
@@ -156,6 +171,62 @@ pub struct DeletePlantingDto {
     pub action_id: Uuid,
 }
 
+/// Used to restore a deleted planting.
+/// The id of the planting is passed in the path.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RestorePlantingDto {
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// Used to create many plantings at once, e.g. when pasting a selection.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchCreatePlantingsDto {
+    /// The plantings to create.
+    /// The `action_id` of the individual plantings is ignored.
+    pub plantings: Vec<NewPlantingDto>,
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// Used to update a single planting as part of a batch.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchUpdatePlantingDto {
+    /// The id of the planting.
+    pub id: Uuid,
+    /// The update of the planting.
+    /// The `action_id` of the individual update is ignored.
+    pub update: UpdatePlantingDto,
+}
+
+/// Used to update many plantings at once, e.g. when moving a selection.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchUpdatePlantingsDto {
+    /// The updates of the plantings.
+    pub updates: Vec<BatchUpdatePlantingDto>,
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// Used to delete many plantings at once.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchDeletePlantingsDto {
+    /// The ids of the plantings to delete.
+    pub ids: Vec<Uuid>,
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
 /// Query parameters for searching plantings.
 #[typeshare]
 #[derive(Debug, Deserialize, IntoParams)]
@@ -1,6 +1,6 @@
 //! Contains the implementations related to [`PlantingDto`].
 
-//use chrono::Utc;
+use chrono::Utc;
 use uuid::Uuid;
 
 use crate::model::entity::plantings::{Planting, UpdatePlanting};
@@ -22,6 +22,7 @@ impl From<Planting> for PlantingDto {
             scale_y: entity.scale_y,
             add_date: entity.add_date,
             remove_date: entity.remove_date,
+            version: entity.version,
         }
     }
 }
@@ -41,8 +42,49 @@ impl From<NewPlantingDto> for Planting {
             scale_y: dto.scale_y,
             add_date: dto.add_date,
             remove_date: None,
-            //create_date: Utc::now().date_naive(),
-            //delete_date: None,
+            create_date: Utc::now().date_naive(),
+            delete_date: None,
+            version: 1,
+            updated_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl From<PlantingDto> for Planting {
+    fn from(dto: PlantingDto) -> Self {
+        Self {
+            id: dto.id,
+            plant_id: dto.plant_id,
+            layer_id: dto.layer_id,
+            x: dto.x,
+            y: dto.y,
+            width: dto.width,
+            height: dto.height,
+            rotation: dto.rotation,
+            scale_x: dto.scale_x,
+            scale_y: dto.scale_y,
+            add_date: dto.add_date,
+            remove_date: dto.remove_date,
+            create_date: Utc::now().date_naive(),
+            delete_date: None,
+            version: dto.version,
+            updated_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl From<PlantingDto> for UpdatePlanting {
+    fn from(dto: PlantingDto) -> Self {
+        Self {
+            x: Some(dto.x),
+            y: Some(dto.y),
+            width: Some(dto.width),
+            height: Some(dto.height),
+            rotation: Some(dto.rotation),
+            scale_x: Some(dto.scale_x),
+            scale_y: Some(dto.scale_y),
+            add_date: Some(dto.add_date),
+            remove_date: Some(dto.remove_date),
         }
     }
 }
@@ -74,3 +116,27 @@ impl From<UpdatePlantingDto> for UpdatePlanting {
         }
     }
 }
+
+impl UpdatePlantingDto {
+    /// Returns the `action_id` of the update.
+    #[must_use]
+    pub const fn action_id(&self) -> Uuid {
+        match self {
+            Self::Transform(dto) => dto.action_id,
+            Self::Move(dto) => dto.action_id,
+            Self::UpdateAddDate(dto) => dto.action_id,
+            Self::UpdateRemoveDate(dto) => dto.action_id,
+        }
+    }
+
+    /// Returns the version of the planting the update is based on.
+    #[must_use]
+    pub const fn version(&self) -> i32 {
+        match self {
+            Self::Transform(dto) => dto.version,
+            Self::Move(dto) => dto.version,
+            Self::UpdateAddDate(dto) => dto.version,
+            Self::UpdateRemoveDate(dto) => dto.version,
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! All DTOs associated with [`ShadeAreaDto`].
+
+use postgis_diesel::types::{Point, Polygon};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::model::r#enum::shade::Shade;
+
+/// An area on a shade layer with the same intensity of shade,
+/// e.g. the shadow of a building or of a tree.
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShadeAreaDto {
+    /// The id of the shade area.
+    pub id: Uuid,
+    /// The shade layer the area is on.
+    pub layer_id: i32,
+    /// How much shade the area gets.
+    pub shade: Shade,
+    /// The outline of the area on the map.
+    ///
+    /// E.g. `{"rings": [[{"x": 0.0,"y": 0.0},{"x": 1000.0,"y": 0.0},{"x": 1000.0,"y": 1000.0},{"x": 0.0,"y": 1000.0},{"x": 0.0,"y": 0.0}]],"srid": 4326}`
+    #[typeshare(serialized_as = "object")]
+    #[schema(value_type = Object)]
+    pub geometry: Polygon<Point>,
+}
+
+/// Used to create a new shade area.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NewShadeAreaDto {
+    /// The id of the shade area.
+    pub id: Option<Uuid>,
+    /// The shade layer the area is on.
+    pub layer_id: i32,
+    /// How much shade the area gets.
+    pub shade: Shade,
+    /// The outline of the area on the map.
+    #[typeshare(serialized_as = "object")]
+    #[schema(value_type = Object)]
+    pub geometry: Polygon<Point>,
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// Used to change the intensity or the outline of a shade area.
+/// Fields that are `None` are not changed.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateShadeAreaDto {
+    /// How much shade the area gets.
+    pub shade: Option<Shade>,
+    /// The outline of the area on the map.
+    #[typeshare(serialized_as = "Option<object>")]
+    #[schema(value_type = Option<Object>)]
+    pub geometry: Option<Polygon<Point>>,
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// Used to delete a shade area.
+/// The id of the shade area is passed in the path.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteShadeAreaDto {
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// Query parameters for finding the shade at a point of the map.
+#[typeshare]
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ShadeAtPointParameters {
+    /// The x coordinate of the point on the map.
+    pub x: i32,
+    /// The y coordinate of the point on the map.
+    pub y: i32,
+}
+
+/// The shade at a point of the map.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShadeAtPointDto {
+    /// How much shade the point gets.
+    /// If it is covered by several areas, the most shade counts.
+    pub shade: Shade,
+    /// The area the shade comes from, `None` if the point is not covered by any area.
+    pub shade_area_id: Option<Uuid>,
+}
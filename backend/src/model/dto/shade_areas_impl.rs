@@ -0,0 +1,61 @@
+//! Contains the implementations related to [`ShadeAreaDto`].
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::model::entity::shade_areas::{ShadeArea, UpdateShadeArea};
+
+use super::shade_areas::{NewShadeAreaDto, ShadeAreaDto, UpdateShadeAreaDto};
+
+impl From<ShadeArea> for ShadeAreaDto {
+    fn from(entity: ShadeArea) -> Self {
+        Self {
+            id: entity.id,
+            layer_id: entity.layer_id,
+            shade: entity.shade,
+            geometry: entity.geometry,
+        }
+    }
+}
+
+impl From<NewShadeAreaDto> for ShadeArea {
+    fn from(dto: NewShadeAreaDto) -> Self {
+        Self {
+            id: dto.id.unwrap_or_else(Uuid::new_v4),
+            layer_id: dto.layer_id,
+            shade: dto.shade,
+            geometry: dto.geometry,
+            updated_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl From<ShadeAreaDto> for ShadeArea {
+    fn from(dto: ShadeAreaDto) -> Self {
+        Self {
+            id: dto.id,
+            layer_id: dto.layer_id,
+            shade: dto.shade,
+            geometry: dto.geometry,
+            updated_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl From<UpdateShadeAreaDto> for UpdateShadeArea {
+    fn from(dto: UpdateShadeAreaDto) -> Self {
+        Self {
+            shade: dto.shade,
+            geometry: dto.geometry,
+        }
+    }
+}
+
+impl From<ShadeArea> for UpdateShadeArea {
+    fn from(entity: ShadeArea) -> Self {
+        Self {
+            shade: Some(entity.shade),
+            geometry: Some(entity.geometry),
+        }
+    }
+}
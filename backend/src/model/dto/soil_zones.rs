@@ -0,0 +1,80 @@
+//! All DTOs associated with [`SoilZoneDto`].
+
+use postgis_diesel::types::{Point, Polygon};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::model::r#enum::{soil_ph::SoilPh, soil_texture::SoilTexture};
+
+/// A zone on a soil layer with the same soil conditions,
+/// e.g. a bed that was measured with a soil test.
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SoilZoneDto {
+    /// The id of the soil zone.
+    pub id: Uuid,
+    /// The soil layer the zone is on.
+    pub layer_id: i32,
+    /// The pH value of the soil in the zone.
+    pub soil_ph: SoilPh,
+    /// The texture of the soil in the zone.
+    pub soil_texture: SoilTexture,
+    /// The outline of the zone on the map.
+    ///
+    /// E.g. `{"rings": [[{"x": 0.0,"y": 0.0},{"x": 1000.0,"y": 0.0},{"x": 1000.0,"y": 1000.0},{"x": 0.0,"y": 1000.0},{"x": 0.0,"y": 0.0}]],"srid": 4326}`
+    #[typeshare(serialized_as = "object")]
+    #[schema(value_type = Object)]
+    pub geometry: Polygon<Point>,
+}
+
+/// Used to create a new soil zone.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NewSoilZoneDto {
+    /// The id of the soil zone.
+    pub id: Option<Uuid>,
+    /// The soil layer the zone is on.
+    pub layer_id: i32,
+    /// The pH value of the soil in the zone.
+    pub soil_ph: SoilPh,
+    /// The texture of the soil in the zone.
+    pub soil_texture: SoilTexture,
+    /// The outline of the zone on the map.
+    #[typeshare(serialized_as = "object")]
+    #[schema(value_type = Object)]
+    pub geometry: Polygon<Point>,
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// Used to change the soil conditions or the outline of a soil zone.
+/// Fields that are `None` are not changed.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateSoilZoneDto {
+    /// The pH value of the soil in the zone.
+    pub soil_ph: Option<SoilPh>,
+    /// The texture of the soil in the zone.
+    pub soil_texture: Option<SoilTexture>,
+    /// The outline of the zone on the map.
+    #[typeshare(serialized_as = "Option<object>")]
+    #[schema(value_type = Option<Object>)]
+    pub geometry: Option<Polygon<Point>>,
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
+
+/// Used to delete a soil zone.
+/// The id of the soil zone is passed in the path.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteSoilZoneDto {
+    /// Id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+}
@@ -0,0 +1,66 @@
+//! Contains the implementations related to [`SoilZoneDto`].
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::model::entity::soil_zones::{SoilZone, UpdateSoilZone};
+
+use super::soil_zones::{NewSoilZoneDto, SoilZoneDto, UpdateSoilZoneDto};
+
+impl From<SoilZone> for SoilZoneDto {
+    fn from(entity: SoilZone) -> Self {
+        Self {
+            id: entity.id,
+            layer_id: entity.layer_id,
+            soil_ph: entity.soil_ph,
+            soil_texture: entity.soil_texture,
+            geometry: entity.geometry,
+        }
+    }
+}
+
+impl From<NewSoilZoneDto> for SoilZone {
+    fn from(dto: NewSoilZoneDto) -> Self {
+        Self {
+            id: dto.id.unwrap_or_else(Uuid::new_v4),
+            layer_id: dto.layer_id,
+            soil_ph: dto.soil_ph,
+            soil_texture: dto.soil_texture,
+            geometry: dto.geometry,
+            updated_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl From<SoilZoneDto> for SoilZone {
+    fn from(dto: SoilZoneDto) -> Self {
+        Self {
+            id: dto.id,
+            layer_id: dto.layer_id,
+            soil_ph: dto.soil_ph,
+            soil_texture: dto.soil_texture,
+            geometry: dto.geometry,
+            updated_at: Utc::now().naive_utc(),
+        }
+    }
+}
+
+impl From<UpdateSoilZoneDto> for UpdateSoilZone {
+    fn from(dto: UpdateSoilZoneDto) -> Self {
+        Self {
+            soil_ph: dto.soil_ph,
+            soil_texture: dto.soil_texture,
+            geometry: dto.geometry,
+        }
+    }
+}
+
+impl From<SoilZone> for UpdateSoilZone {
+    fn from(entity: SoilZone) -> Self {
+        Self {
+            soil_ph: Some(entity.soil_ph),
+            soil_texture: Some(entity.soil_texture),
+            geometry: Some(entity.geometry),
+        }
+    }
+}
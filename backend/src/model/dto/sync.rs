@@ -0,0 +1,114 @@
+//! All DTOs associated with synchronizing changes made while the map was edited offline.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::plantings::{BatchUpdatePlantingDto, NewPlantingDto, PlantingDto};
+use super::{BaseLayerImageDto, UpdateBaseLayerImageDto};
+
+/// Used to apply the operations a user did while editing the map offline.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncDto {
+    /// The operations in the order they were done.
+    pub operations: Vec<SyncOperationDto>,
+    /// Id of the action (for identifying the action in the frontend).
+    /// The `action_id` of the individual operations is ignored.
+    pub action_id: Uuid,
+}
+
+/// A single operation done while editing the map offline.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncOperationDto {
+    /// When the operation was done according to the clock of the client.
+    /// Operations are applied in the order of their timestamps.
+    #[typeshare(serialized_as = "String")]
+    pub timestamp: DateTime<Utc>,
+    /// The operation itself.
+    pub operation: SyncOperation,
+}
+
+/// The kinds of operations that can be done while editing the map offline.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", content = "content")]
+pub enum SyncOperation {
+    /// Create a planting.
+    CreatePlanting(NewPlantingDto),
+    /// Update a planting.
+    /// It is only updated if it is still in the version the update is based on.
+    UpdatePlanting(BatchUpdatePlantingDto),
+    /// Delete a planting.
+    DeletePlanting(SyncDeleteDto),
+    /// Create a base layer image.
+    CreateBaseLayerImage(BaseLayerImageDto),
+    /// Update a base layer image.
+    /// Base layer images are not versioned, the latest update wins.
+    UpdateBaseLayerImage(SyncUpdateBaseLayerImageDto),
+    /// Delete a base layer image.
+    DeleteBaseLayerImage(SyncDeleteDto),
+}
+
+/// Used to delete an entity while editing the map offline.
+#[typeshare]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncDeleteDto {
+    /// The id of the entity.
+    pub id: Uuid,
+}
+
+/// Used to update a base layer image while editing the map offline.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncUpdateBaseLayerImageDto {
+    /// The id of the image.
+    pub id: Uuid,
+    /// The update of the image.
+    pub update: UpdateBaseLayerImageDto,
+}
+
+/// The outcome of synchronizing offline operations.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncResultDto {
+    /// The results of the operations in the same order as in the request.
+    pub results: Vec<SyncOperationResultDto>,
+}
+
+/// The outcome of a single offline operation.
+#[typeshare]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncOperationResultDto {
+    /// If the operation was applied.
+    pub status: SyncStatus,
+    /// Why the operation was not applied.
+    pub reason: Option<String>,
+    /// The planting after the operation was applied,
+    /// or its current state if the operation conflicted with it.
+    pub planting: Option<PlantingDto>,
+    /// The base layer image after the operation was applied,
+    /// or its current state if the operation conflicted with it.
+    pub base_layer_image: Option<BaseLayerImageDto>,
+}
+
+/// Whether an offline operation was applied.
+#[typeshare]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum SyncStatus {
+    /// The operation was applied.
+    Accepted,
+    /// The entity was changed or deleted in the meantime, the operation was discarded.
+    Conflict,
+    /// The operation is invalid, e.g. it targets another map.
+    Rejected,
+}
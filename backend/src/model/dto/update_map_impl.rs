@@ -11,6 +11,7 @@ impl From<UpdateMapDto> for UpdateMap {
             privacy: map_update.privacy,
             description: map_update.description,
             location: map_update.location.map(From::from),
+            geometry: map_update.geometry,
         }
     }
 }
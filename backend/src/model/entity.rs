@@ -4,12 +4,23 @@ pub mod base_layer_images_impl;
 pub mod blossoms_impl;
 pub mod guided_tours_impl;
 pub mod layer_impl;
+pub mod layer_preferences_impl;
+pub mod map_action_log_impl;
+pub mod map_clients_impl;
+pub mod map_collaborator_impl;
+pub mod map_events_impl;
+pub mod map_history_impl;
 pub mod map_impl;
 pub mod plant_layer;
 pub mod plantings;
 pub mod plantings_impl;
 pub mod plants_impl;
 pub mod seed_impl;
+pub mod shade_areas;
+pub mod shade_areas_impl;
+pub mod soil_zones;
+pub mod soil_zones_impl;
+pub mod tombstone_impl;
 pub mod users_impl;
 
 use chrono::NaiveDate;
@@ -23,10 +34,14 @@ use postgis_diesel::types::Polygon;
 use uuid::Uuid;
 
 use crate::schema::{
-    base_layer_images, blossoms, gained_blossoms, guided_tours, layers, maps, plants, seeds, users,
+    base_layer_images, blossoms, gained_blossoms, guided_tours, layer_preferences, layers,
+    map_action_log, map_clients, map_collaborators, map_events, map_history, maps, plants, seeds,
+    tombstones, users,
 };
 
+use super::r#enum::collaborator_role::CollaboratorRole;
 use super::r#enum::experience::Experience;
+use super::r#enum::history_entity_type::HistoryEntityType;
 use super::r#enum::membership::Membership;
 use super::r#enum::privacy_option::PrivacyOption;
 use super::r#enum::salutation::Salutation;
@@ -821,6 +836,8 @@ pub struct UpdateMap {
     pub description: Option<String>,
     /// The location of the map as a latitude/longitude point.
     pub location: Option<Point>,
+    /// The geometry of the map.
+    pub geometry: Option<Polygon<Point>>,
 }
 
 /// The `Layer` entity.
@@ -837,6 +854,12 @@ pub struct Layer {
     pub name: String,
     /// A flag indicating if this layer is an user created alternative.
     pub is_alternative: bool,
+    /// The date and time (UTC) the layer was last changed.
+    pub updated_at: NaiveDateTime,
+    /// The position of the layer in the list of layers of the map, starting at 0.
+    pub order_index: i32,
+    /// The date the layer was marked for deletion.
+    pub deletion_date: Option<NaiveDate>,
 }
 
 /// The `NewLayer` entity.
@@ -853,6 +876,23 @@ pub struct NewLayer {
     pub is_alternative: bool,
 }
 
+/// The `LayerPreferences` entity.
+/// How a user wants to see a layer, independent of the other users of the map.
+#[derive(Identifiable, Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = layer_preferences, primary_key(user_id, layer_id))]
+pub struct LayerPreferences {
+    /// The id of the user.
+    pub user_id: Uuid,
+    /// The id of the layer.
+    pub layer_id: i32,
+    /// Whether the layer is shown.
+    pub is_visible: bool,
+    /// The opacity of the layer from 0 (transparent) to 1 (opaque).
+    pub opacity: f32,
+    /// Whether the layer is the alternative of its type the user works on.
+    pub is_selected: bool,
+}
+
 /// The `BaseLayerImages` entity.
 #[derive(Identifiable, Queryable, Insertable, AsChangeset)]
 #[diesel(table_name = base_layer_images)]
@@ -867,6 +907,9 @@ pub struct BaseLayerImages {
     pub rotation: f32,
     /// The scale of the image on the map.
     pub scale: f32,
+    /// The date and time (UTC) the image was last changed.
+    /// It is maintained by the database, values set by the backend are ignored.
+    pub updated_at: NaiveDateTime,
 }
 
 /// The `Users` entity.
@@ -946,3 +989,172 @@ pub struct GainedBlossoms {
     /// The date on which the user gained this Blossom.
     pub gained_date: NaiveDate,
 }
+
+/// The `MapCollaborator` entity.
+#[derive(Insertable, Identifiable, Queryable)]
+#[diesel(primary_key(map_id, user_id), table_name = map_collaborators)]
+pub struct MapCollaborator {
+    /// The id of the map.
+    pub map_id: i32,
+    /// The id of the collaborating user from Keycloak.
+    pub user_id: Uuid,
+    /// The role of the user on the map.
+    pub role: CollaboratorRole,
+}
+
+/// The `MapActionLogEntry` entity.
+#[derive(Identifiable, Queryable)]
+#[diesel(table_name = map_action_log)]
+pub struct MapActionLogEntry {
+    /// The id of the entry.
+    pub id: i64,
+    /// The id of the map the action was performed on.
+    pub map_id: i32,
+    /// The id of the user who performed the action.
+    pub user_id: Uuid,
+    /// The id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+    /// The serialized [`crate::model::dto::action_log::EntityChange`]s caused by the action.
+    pub changes: serde_json::Value,
+    /// Whether the action is currently undone.
+    pub is_undone: bool,
+    /// The date and time the action was performed.
+    pub created_at: NaiveDateTime,
+}
+
+/// The `NewMapActionLogEntry` entity.
+#[derive(Insertable)]
+#[diesel(table_name = map_action_log)]
+pub struct NewMapActionLogEntry {
+    /// The id of the map the action was performed on.
+    pub map_id: i32,
+    /// The id of the user who performed the action.
+    pub user_id: Uuid,
+    /// The id of the action (for identifying the action in the frontend).
+    pub action_id: Uuid,
+    /// The serialized [`crate::model::dto::action_log::EntityChange`]s caused by the action.
+    pub changes: serde_json::Value,
+}
+
+/// The `MapClient` entity.
+/// A client connected to a map on any backend instance.
+/// Kept after the client disconnected, so it can continue with the same settings when it reconnects.
+#[derive(Identifiable, Queryable)]
+#[diesel(primary_key(map_id, user_id, client_id))]
+#[diesel(table_name = map_clients)]
+pub struct MapClient {
+    /// The id of the map the client is connected to.
+    pub map_id: i32,
+    /// The id of the user the client belongs to.
+    pub user_id: Uuid,
+    /// Id chosen by the client to identify itself.
+    pub client_id: Uuid,
+    /// Id of the current connection of the client.
+    pub connection_id: Uuid,
+    /// The layer the user has selected.
+    pub selected_layer_id: Option<i32>,
+    /// The layers the client subscribed to, `None` for all layers.
+    pub layer_ids: Option<Vec<i32>>,
+    /// The client counts as disconnected after this point in time,
+    /// unless the instance it is connected to extends it.
+    pub expires_at: NaiveDateTime,
+}
+
+/// The `MapEvent` entity.
+/// An action broadcast on a map, kept so clients that reconnect can catch up.
+#[derive(Identifiable, Queryable)]
+#[diesel(table_name = map_events)]
+pub struct MapEvent {
+    /// The id of the event, increasing with every broadcast.
+    pub id: i64,
+    /// The id of the map the action was broadcast on.
+    pub map_id: i32,
+    /// The serialized action.
+    pub data: String,
+    /// The layers changed by the action, `None` if it concerns the whole map.
+    pub layer_ids: Option<Vec<i32>>,
+    /// The date and time the action was broadcast.
+    pub created_at: NaiveDateTime,
+}
+
+/// The `NewMapEvent` entity.
+#[derive(Insertable)]
+#[diesel(table_name = map_events)]
+pub struct NewMapEvent {
+    /// The id of the map the action is broadcast on.
+    pub map_id: i32,
+    /// The serialized action.
+    pub data: String,
+    /// The layers changed by the action, `None` if it concerns the whole map.
+    pub layer_ids: Option<Vec<i32>>,
+}
+
+/// The `MapHistoryEntry` entity.
+/// Records a single change of an entity on a map for the audit trail.
+#[derive(Identifiable, Queryable)]
+#[diesel(table_name = map_history)]
+pub struct MapHistoryEntry {
+    /// The id of the entry.
+    pub id: i64,
+    /// The id of the map the change was made on.
+    pub map_id: i32,
+    /// The id of the user who made the change.
+    pub user_id: Uuid,
+    /// The id of the action that caused the change.
+    pub action_id: Uuid,
+    /// The kind of the changed entity.
+    pub entity_type: HistoryEntityType,
+    /// The id of the changed layer or of the layer the changed entity is on.
+    pub layer_id: i32,
+    /// The id of the changed planting or base layer image.
+    pub entity_id: Option<Uuid>,
+    /// The serialized [`crate::model::dto::history::HistoryEntityDto`] before the change.
+    pub before: Option<serde_json::Value>,
+    /// The serialized [`crate::model::dto::history::HistoryEntityDto`] after the change.
+    pub after: Option<serde_json::Value>,
+    /// The date and time (UTC) of the change.
+    pub created_at: NaiveDateTime,
+}
+
+/// The `NewMapHistoryEntry` entity.
+#[derive(Insertable)]
+#[diesel(table_name = map_history)]
+pub struct NewMapHistoryEntry {
+    /// The id of the map the change was made on.
+    pub map_id: i32,
+    /// The id of the user who made the change.
+    pub user_id: Uuid,
+    /// The id of the action that caused the change.
+    pub action_id: Uuid,
+    /// The kind of the changed entity.
+    pub entity_type: HistoryEntityType,
+    /// The id of the changed layer or of the layer the changed entity is on.
+    pub layer_id: i32,
+    /// The id of the changed planting or base layer image.
+    pub entity_id: Option<Uuid>,
+    /// The serialized [`crate::model::dto::history::HistoryEntityDto`] before the change.
+    pub before: Option<serde_json::Value>,
+    /// The serialized [`crate::model::dto::history::HistoryEntityDto`] after the change.
+    pub after: Option<serde_json::Value>,
+}
+
+/// The `Tombstone` entity.
+/// Remembers a permanently deleted layer, base layer image, shade area or soil zone.
+#[derive(Identifiable, Queryable)]
+#[diesel(table_name = tombstones)]
+pub struct Tombstone {
+    /// The id of the tombstone.
+    pub id: i64,
+    /// The id of the map the entity was on.
+    pub map_id: i32,
+    /// The id of the deleted layer.
+    pub layer_id: Option<i32>,
+    /// The id of the deleted base layer image.
+    pub base_layer_image_id: Option<Uuid>,
+    /// The date and time (UTC) the entity was deleted.
+    pub deleted_at: NaiveDateTime,
+    /// The id of the deleted shade area.
+    pub shade_area_id: Option<Uuid>,
+    /// The id of the deleted soil zone.
+    pub soil_zone_id: Option<Uuid>,
+}
@@ -1,5 +1,6 @@
 //! Contains the implementation of [`BaseLayerImages`].
 
+use chrono::NaiveDateTime;
 use diesel::pg::Pg;
 use diesel::{debug_query, ExpressionMethods, QueryDsl, QueryResult};
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
@@ -7,7 +8,8 @@ use log::debug;
 use uuid::Uuid;
 
 use crate::model::dto::{BaseLayerImageDto, UpdateBaseLayerImageDto};
-use crate::schema::base_layer_images::{self, all_columns, layer_id};
+use crate::schema::base_layer_images::{self, all_columns, layer_id, updated_at};
+use crate::schema::layers;
 
 use super::BaseLayerImages;
 
@@ -33,21 +35,93 @@ impl BaseLayerImages {
             .collect())
     }
 
+    /// Get the `BaseLayerImages` on any layer of the map changed at or after `since`.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_changed_since(
+        map_id: i32,
+        since: NaiveDateTime,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<BaseLayerImageDto>> {
+        let query = base_layer_images::table
+            .inner_join(layers::table)
+            .select(all_columns)
+            .filter(layers::map_id.eq(map_id))
+            .filter(updated_at.ge(since));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        Ok(query
+            .load::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Fetch a `BaseLayerImages` by id from the database.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_by_id(
+        id: Uuid,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<BaseLayerImageDto> {
+        let query = base_layer_images::table.find(id);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.first::<Self>(conn).await.map(Into::into)
+    }
+
     /// Create a new `BaseLayerImages` in the database.
     ///
     /// # Errors
     /// * If the `layer_id` references a layer that is not of type `base`.
     /// * Unknown, diesel doesn't say why it might error.
     pub async fn create(
-        dto: BaseLayerImageDto,
+        image: Self,
         conn: &mut AsyncPgConnection,
     ) -> QueryResult<BaseLayerImageDto> {
-        let insert = Self::from(dto);
-        let query = diesel::insert_into(base_layer_images::table).values(&insert);
+        let query = diesel::insert_into(base_layer_images::table).values(&image);
         debug!("{}", debug_query::<Pg, _>(&query));
         query.get_result::<Self>(conn).await.map(Into::into)
     }
 
+    /// Copy all `BaseLayerImages` of a layer to another layer.
+    /// The copies get new ids.
+    ///
+    /// # Errors
+    /// * If the `to_layer_id` references a layer that is not of type `base`.
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn copy_to_layer(
+        from_layer_id: i32,
+        to_layer_id: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<BaseLayerImageDto>> {
+        let query = base_layer_images::table.filter(layer_id.eq(from_layer_id));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        let copies: Vec<_> = query
+            .load::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(|image| Self {
+                id: Uuid::new_v4(),
+                layer_id: to_layer_id,
+                ..image
+            })
+            .collect();
+        if copies.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let insert_query = diesel::insert_into(base_layer_images::table).values(&copies);
+        debug!("{}", debug_query::<Pg, _>(&insert_query));
+        Ok(insert_query
+            .get_results::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
     /// Update a `BaseLayerImages` in the database.
     ///
     /// # Errors
@@ -63,6 +137,25 @@ impl BaseLayerImages {
         query.get_result::<Self>(conn).await.map(Into::into)
     }
 
+    /// Overwrite the `BaseLayerImages` with `dto` or create it if it doesn't exist.
+    ///
+    /// # Errors
+    /// * If the `layer_id` references a layer that is not of type `base`.
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn replace(
+        dto: BaseLayerImageDto,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<BaseLayerImageDto> {
+        let image = Self::from(dto);
+        let query = diesel::insert_into(base_layer_images::table)
+            .values(&image)
+            .on_conflict(base_layer_images::id)
+            .do_update()
+            .set(&image);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
     /// Delete the `BaseLayerImages` from the database.
     ///
     /// # Errors
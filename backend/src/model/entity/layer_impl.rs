@@ -1,21 +1,29 @@
 //! Contains the implementation of [`Layer`].
 
+use chrono::{Days, NaiveDate, NaiveDateTime, Utc};
+use diesel::dsl::exists;
 use diesel::pg::Pg;
 use diesel::{debug_query, ExpressionMethods, QueryDsl, QueryResult};
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use log::debug;
 
+use crate::db::cronjobs::DELETION_RETENTION_DAYS;
 use crate::model::dto::LayerSearchParameters;
+use crate::model::r#enum::layer_type::LayerType;
 use crate::{
-    model::dto::{LayerDto, NewLayerDto},
-    schema::layers::{self, all_columns, is_alternative, map_id, type_},
+    model::dto::LayerDto,
+    schema::layers::{
+        self, all_columns, deletion_date, is_alternative, map_id, name, order_index, type_,
+        updated_at,
+    },
 };
 
 use super::{Layer, NewLayer};
 
 impl Layer {
-    /// Get a page of layers.
+    /// Get a page of layers ordered by their position.
     /// Can be filtered by its active status if one is provided in `search_parameters`.
+    /// Layers marked for deletion are not returned.
     ///
     /// # Errors
     /// * Unknown, diesel doesn't say why it might error.
@@ -23,7 +31,10 @@ impl Layer {
         search_parameters: LayerSearchParameters,
         conn: &mut AsyncPgConnection,
     ) -> QueryResult<Vec<LayerDto>> {
-        let mut query = layers::table.select(all_columns).into_boxed();
+        let mut query = layers::table
+            .select(all_columns)
+            .filter(deletion_date.is_null())
+            .into_boxed();
 
         if let Some(map_id_search) = search_parameters.map_id {
             query = query.filter(map_id.eq(map_id_search));
@@ -34,6 +45,7 @@ impl Layer {
         if let Some(is_alternative_search) = search_parameters.is_alternative {
             query = query.filter(is_alternative.eq(is_alternative_search));
         }
+        let query = query.order((order_index.asc(), layers::id.asc()));
 
         debug!("{}", debug_query::<Pg, _>(&query));
         Ok(query
@@ -44,37 +56,203 @@ impl Layer {
             .collect())
     }
 
+    /// Get the layers of the map changed at or after `since`.
+    /// Layers marked for deletion are returned as well, as their deletion is a change.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_changed_since(
+        map_id_search: i32,
+        since: NaiveDateTime,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<Self>> {
+        let query = layers::table
+            .select(all_columns)
+            .filter(map_id.eq(map_id_search))
+            .filter(updated_at.ge(since));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.load::<Self>(conn).await
+    }
+
     /// Fetch layer by id from the database.
+    /// Layers marked for deletion are not found.
     ///
     /// # Errors
     /// * Unknown, diesel doesn't say why it might error.
     pub async fn find_by_id(id: i32, conn: &mut AsyncPgConnection) -> QueryResult<LayerDto> {
-        let query = layers::table.find(id);
+        let query = layers::table.find(id).filter(deletion_date.is_null());
         debug!("{}", debug_query::<Pg, _>(&query));
         query.first::<Self>(conn).await.map(Into::into)
     }
 
+    /// Lock the layers of the map until the end of the transaction, ordered by id.
+    /// Can be restricted to layers of one type.
+    /// Layers marked for deletion are locked and returned as well.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn lock_on_map(
+        map_id_search: i32,
+        type_search: Option<LayerType>,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<Self>> {
+        let query = layers::table
+            .select(all_columns)
+            .filter(map_id.eq(map_id_search))
+            .order(layers::id.asc())
+            .for_update();
+        // Locking queries can't be boxed.
+        if let Some(type_search) = type_search {
+            let query = query.filter(type_.eq(type_search));
+            debug!("{}", debug_query::<Pg, _>(&query));
+            query.load::<Self>(conn).await
+        } else {
+            debug!("{}", debug_query::<Pg, _>(&query));
+            query.load::<Self>(conn).await
+        }
+    }
+
+    /// Count how many of the layers with the given ids are part of the map.
+    /// Layers marked for deletion are not counted.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn count_on_map(
+        ids: &[i32],
+        map_id_search: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<i64> {
+        let query = layers::table
+            .filter(layers::id.eq_any(ids))
+            .filter(map_id.eq(map_id_search))
+            .filter(deletion_date.is_null())
+            .count();
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<i64>(conn).await
+    }
+
+    /// Check if a layer of the map other than `except_id` already has the name.
+    /// Layers marked for deletion are checked as well, as they might be restored.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn name_exists(
+        map_id_search: i32,
+        name_search: &str,
+        except_id: Option<i32>,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<bool> {
+        let mut query = layers::table
+            .filter(map_id.eq(map_id_search))
+            .filter(name.eq(name_search))
+            .into_boxed();
+        if let Some(except_id) = except_id {
+            query = query.filter(layers::id.ne(except_id));
+        }
+        let query = diesel::select(exists(query));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<bool>(conn).await
+    }
+
     /// Create a new layer in the database.
+    /// It is placed behind the other layers of the map.
     ///
     /// # Errors
     /// * Unknown, diesel doesn't say why it might error.
     pub async fn create(
-        new_layer: NewLayerDto,
+        new_layer: NewLayer,
         conn: &mut AsyncPgConnection,
     ) -> QueryResult<LayerDto> {
-        let new_layer = NewLayer::from(new_layer);
-        let query = diesel::insert_into(layers::table).values(&new_layer);
+        let last_index = layers::table
+            .select(diesel::dsl::max(order_index))
+            .filter(map_id.eq(new_layer.map_id));
+        debug!("{}", debug_query::<Pg, _>(&last_index));
+        let next_index = last_index
+            .first::<Option<i32>>(conn)
+            .await?
+            .map_or(0, |last| last + 1);
+
+        let query =
+            diesel::insert_into(layers::table).values((&new_layer, order_index.eq(next_index)));
         debug!("{}", debug_query::<Pg, _>(&query));
         query.get_result::<Self>(conn).await.map(Into::into)
     }
 
-    /// Delete the layer from the database.
+    /// Rename the layer.
     ///
     /// # Errors
     /// * Unknown, diesel doesn't say why it might error.
-    pub async fn delete_by_id(id: i32, conn: &mut AsyncPgConnection) -> QueryResult<usize> {
-        let query = diesel::delete(layers::table.find(id));
+    pub async fn rename(
+        id_layer: i32,
+        new_name: String,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<LayerDto> {
+        let query = diesel::update(layers::table.find(id_layer)).set(name.eq(new_name));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Move the layer to another position in the list of layers of the map.
+    /// The positions of the other layers are not changed.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn set_order_index(
+        id_layer: i32,
+        index: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<LayerDto> {
+        let query = diesel::update(layers::table.find(id_layer)).set(order_index.eq(index));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Mark the layer as an alternative or as the main layer of its type.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn set_alternative(
+        id: i32,
+        alternative: bool,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<LayerDto> {
+        let query = diesel::update(layers::table.find(id)).set(is_alternative.eq(alternative));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Mark the layer for deletion.
+    /// Its content is kept, so it can be restored for [`DELETION_RETENTION_DAYS`] days.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn mark_for_deletion(id: i32, conn: &mut AsyncPgConnection) -> QueryResult<usize> {
+        let query = diesel::update(layers::table.find(id))
+            .filter(deletion_date.is_null())
+            .set(deletion_date.eq(Some(Utc::now().date_naive())));
         debug!("{}", debug_query::<Pg, _>(&query));
         query.execute(conn).await
     }
+
+    /// Restore a layer of the map marked for deletion within the last [`DELETION_RETENTION_DAYS`] days.
+    ///
+    /// # Errors
+    /// * If the layer is not marked for deletion or can no longer be restored.
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn restore(
+        id: i32,
+        map_id_search: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<LayerDto> {
+        let oldest_restorable = Utc::now()
+            .date_naive()
+            .checked_sub_days(Days::new(DELETION_RETENTION_DAYS.into()))
+            .unwrap_or(NaiveDate::MIN);
+        let query = diesel::update(layers::table.find(id))
+            .filter(map_id.eq(map_id_search))
+            .filter(deletion_date.ge(oldest_restorable))
+            .set(deletion_date.eq(None::<NaiveDate>));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
 }
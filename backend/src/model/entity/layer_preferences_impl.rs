@@ -0,0 +1,84 @@
+//! Contains the implementation of [`LayerPreferences`].
+
+use diesel::pg::Pg;
+use diesel::{debug_query, ExpressionMethods, QueryDsl, QueryResult};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use log::debug;
+use uuid::Uuid;
+
+use crate::model::dto::LayerPreferencesDto;
+use crate::model::r#enum::layer_type::LayerType;
+use crate::schema::layer_preferences::{self, all_columns, is_selected, layer_id, user_id};
+use crate::schema::layers;
+
+use super::LayerPreferences;
+
+impl LayerPreferences {
+    /// Get the preferences of the user for the layers of the map.
+    /// Layers the user has no preferences for or that are marked for deletion are not returned.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find(
+        user_id_search: Uuid,
+        map_id: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<LayerPreferencesDto>> {
+        let query = layer_preferences::table
+            .inner_join(layers::table)
+            .select(all_columns)
+            .filter(user_id.eq(user_id_search))
+            .filter(layers::map_id.eq(map_id))
+            .filter(layers::deletion_date.is_null());
+        debug!("{}", debug_query::<Pg, _>(&query));
+        Ok(query
+            .load::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Create or replace the preferences of the user for the layer.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn upsert(
+        preferences: Self,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<LayerPreferencesDto> {
+        let query = diesel::insert_into(layer_preferences::table)
+            .values(&preferences)
+            .on_conflict((user_id, layer_id))
+            .do_update()
+            .set(&preferences);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Deselect all layers of the type on the map for the user, except the layer with id `except`.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn deselect_others(
+        user_id_search: Uuid,
+        map_id: i32,
+        type_: LayerType,
+        except: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<usize> {
+        let same_type = layers::table
+            .select(layers::id)
+            .filter(layers::map_id.eq(map_id))
+            .filter(layers::type_.eq(type_))
+            .filter(layers::id.ne(except));
+        let query = diesel::update(
+            layer_preferences::table
+                .filter(user_id.eq(user_id_search))
+                .filter(layer_id.eq_any(same_type)),
+        )
+        .set(is_selected.eq(false));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.execute(conn).await
+    }
+}
@@ -0,0 +1,135 @@
+//! Contains the implementation of [`MapActionLogEntry`].
+
+use diesel::{debug_query, pg::Pg, ExpressionMethods, OptionalExtension, QueryDsl, QueryResult};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use log::debug;
+use uuid::Uuid;
+
+use crate::schema::map_action_log::{self, id, is_undone, map_id, user_id};
+
+use super::{MapActionLogEntry, NewMapActionLogEntry};
+
+impl MapActionLogEntry {
+    /// Get the latest action of the user on the map that is not undone.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_last_done(
+        map_id_search: i32,
+        user_id_search: Uuid,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Option<Self>> {
+        let query = map_action_log::table
+            .filter(map_id.eq(map_id_search))
+            .filter(user_id.eq(user_id_search))
+            .filter(is_undone.eq(false))
+            .order(id.desc());
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.first::<Self>(conn).await.optional()
+    }
+
+    /// Get the earliest action of the user on the map that is undone.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_first_undone(
+        map_id_search: i32,
+        user_id_search: Uuid,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Option<Self>> {
+        let query = map_action_log::table
+            .filter(map_id.eq(map_id_search))
+            .filter(user_id.eq(user_id_search))
+            .filter(is_undone.eq(true))
+            .order(id.asc());
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.first::<Self>(conn).await.optional()
+    }
+
+    /// Add an action to the log.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn create(
+        new_entry: NewMapActionLogEntry,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Self> {
+        let query = diesel::insert_into(map_action_log::table).values(&new_entry);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await
+    }
+
+    /// Mark an action as undone or done again.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn set_undone(
+        entry_id: i64,
+        undone: bool,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<usize> {
+        let query = diesel::update(map_action_log::table.find(entry_id)).set(is_undone.eq(undone));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.execute(conn).await
+    }
+
+    /// Delete an action from the log.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn delete_by_id(entry_id: i64, conn: &mut AsyncPgConnection) -> QueryResult<usize> {
+        let query = diesel::delete(map_action_log::table.find(entry_id));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.execute(conn).await
+    }
+
+    /// Delete all undone actions of the user on the map, so they can no longer be redone.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn delete_undone(
+        map_id_search: i32,
+        user_id_search: Uuid,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<usize> {
+        let query = diesel::delete(
+            map_action_log::table
+                .filter(map_id.eq(map_id_search))
+                .filter(user_id.eq(user_id_search))
+                .filter(is_undone.eq(true)),
+        );
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.execute(conn).await
+    }
+
+    /// Delete all but the latest `keep` actions of the user on the map.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn truncate(
+        map_id_search: i32,
+        user_id_search: Uuid,
+        keep: i64,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<usize> {
+        let oldest_kept = map_action_log::table
+            .select(id)
+            .filter(map_id.eq(map_id_search))
+            .filter(user_id.eq(user_id_search))
+            .order(id.desc())
+            .offset(keep - 1);
+        debug!("{}", debug_query::<Pg, _>(&oldest_kept));
+        let Some(oldest_kept) = oldest_kept.first::<i64>(conn).await.optional()? else {
+            return Ok(0);
+        };
+
+        let query = diesel::delete(
+            map_action_log::table
+                .filter(map_id.eq(map_id_search))
+                .filter(user_id.eq(user_id_search))
+                .filter(id.lt(oldest_kept)),
+        );
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.execute(conn).await
+    }
+}
@@ -0,0 +1,174 @@
+//! Contains the implementation of [`MapClient`].
+
+use diesel::dsl::{exists, now, IntervalDsl};
+use diesel::upsert::excluded;
+use diesel::{debug_query, pg::Pg, ExpressionMethods, OptionalExtension, QueryDsl, QueryResult};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use log::debug;
+use uuid::Uuid;
+
+use crate::db::function::timezone;
+use crate::schema::map_clients::{
+    self, client_id, connection_id, expires_at, layer_ids, map_id, selected_layer_id, user_id,
+};
+
+use super::MapClient;
+
+impl MapClient {
+    /// Fetch the client of the user by id from the database.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_by_id(
+        map_id_search: i32,
+        user_id_search: Uuid,
+        client_id_search: Uuid,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Self> {
+        let query = map_clients::table.find((map_id_search, user_id_search, client_id_search));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.first::<Self>(conn).await
+    }
+
+    /// Register the new connection of the client.
+    /// A client that was connected before keeps its selected layer and subscription.
+    ///
+    /// The client counts as connected for `timeout_seconds`, unless it is extended.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn connect(
+        map_id_connect: i32,
+        user_id_connect: Uuid,
+        client_id_connect: Uuid,
+        connection_id_connect: Uuid,
+        timeout_seconds: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Self> {
+        let query = diesel::insert_into(map_clients::table)
+            .values((
+                map_id.eq(map_id_connect),
+                user_id.eq(user_id_connect),
+                client_id.eq(client_id_connect),
+                connection_id.eq(connection_id_connect),
+                expires_at.eq(timezone("UTC", now) + timeout_seconds.seconds()),
+            ))
+            .on_conflict((map_id, user_id, client_id))
+            .do_update()
+            .set((
+                connection_id.eq(excluded(connection_id)),
+                expires_at.eq(excluded(expires_at)),
+            ));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await
+    }
+
+    /// Extend the time the connections count as connected by `timeout_seconds`.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn extend(
+        connection_ids: &[Uuid],
+        timeout_seconds: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<usize> {
+        let query = diesel::update(map_clients::table)
+            .filter(connection_id.eq_any(connection_ids))
+            .set(expires_at.eq(timezone("UTC", now) + timeout_seconds.seconds()));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.execute(conn).await
+    }
+
+    /// Mark the connection as disconnected.
+    /// Does nothing if the client reconnected in the meantime.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn disconnect(
+        connection_id_disconnect: Uuid,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<usize> {
+        let query = diesel::update(map_clients::table)
+            .filter(connection_id.eq(connection_id_disconnect))
+            .set(expires_at.eq(timezone("UTC", now)));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.execute(conn).await
+    }
+
+    /// Check if the user has any client connected to the map.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn is_connected(
+        map_id_search: i32,
+        user_id_search: Uuid,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<bool> {
+        let query = diesel::select(exists(
+            map_clients::table
+                .filter(map_id.eq(map_id_search))
+                .filter(user_id.eq(user_id_search))
+                .filter(expires_at.gt(timezone("UTC", now))),
+        ));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<bool>(conn).await
+    }
+
+    /// Get all clients connected to the map, ordered by user.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_connected(
+        map_id_search: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<Self>> {
+        let query = map_clients::table
+            .filter(map_id.eq(map_id_search))
+            .filter(expires_at.gt(timezone("UTC", now)))
+            .order((user_id, client_id));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.load::<Self>(conn).await
+    }
+
+    /// Set the layer the user has selected on all of their clients connected to the map.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn select_layer(
+        map_id_select: i32,
+        user_id_select: Uuid,
+        layer_id: Option<i32>,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<usize> {
+        let query = diesel::update(map_clients::table)
+            .filter(map_id.eq(map_id_select))
+            .filter(user_id.eq(user_id_select))
+            .filter(expires_at.gt(timezone("UTC", now)))
+            .set(selected_layer_id.eq(layer_id));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.execute(conn).await
+    }
+
+    /// Set the layers the connected client receives actions for.
+    ///
+    /// Returns `None` if the client is not connected to the map.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn subscribe(
+        map_id_subscribe: i32,
+        user_id_subscribe: Uuid,
+        client_id_subscribe: Uuid,
+        layer_ids_subscribe: Option<Vec<i32>>,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Option<Self>> {
+        let query = diesel::update(map_clients::table)
+            .filter(map_id.eq(map_id_subscribe))
+            .filter(user_id.eq(user_id_subscribe))
+            .filter(client_id.eq(client_id_subscribe))
+            .filter(expires_at.gt(timezone("UTC", now)))
+            .set(layer_ids.eq(layer_ids_subscribe));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.optional()
+    }
+}
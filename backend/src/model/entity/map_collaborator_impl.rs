@@ -0,0 +1,100 @@
+//! Contains the implementation of [`MapCollaborator`].
+
+use diesel::{debug_query, pg::Pg, ExpressionMethods, OptionalExtension, QueryDsl, QueryResult};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use log::debug;
+use uuid::Uuid;
+
+use crate::{
+    model::{
+        dto::{MapCollaboratorDto, NewMapCollaboratorDto},
+        r#enum::collaborator_role::CollaboratorRole,
+    },
+    schema::map_collaborators::{self, map_id, role, user_id},
+};
+
+use super::MapCollaborator;
+
+impl MapCollaborator {
+    /// Get all collaborators of a map.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_by_map_id(
+        map_id_search: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<MapCollaboratorDto>> {
+        let query = map_collaborators::table
+            .filter(map_id.eq(map_id_search))
+            .order(user_id);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        Ok(query
+            .load::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Get the role of a user on a map.
+    /// Returns `None` if the user is not a collaborator of the map.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_role(
+        map_id_search: i32,
+        user_id_search: Uuid,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Option<CollaboratorRole>> {
+        let query = map_collaborators::table
+            .find((map_id_search, user_id_search))
+            .select(role);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.first::<CollaboratorRole>(conn).await.optional()
+    }
+
+    /// Add a collaborator to a map.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn create(
+        new_collaborator: NewMapCollaboratorDto,
+        map_id_new: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<MapCollaboratorDto> {
+        let new_collaborator = Self::from((new_collaborator, map_id_new));
+        let query = diesel::insert_into(map_collaborators::table).values(new_collaborator);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Change the role of a collaborator.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn update_role(
+        map_id_update: i32,
+        user_id_update: Uuid,
+        new_role: CollaboratorRole,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<MapCollaboratorDto> {
+        let query = diesel::update(map_collaborators::table.find((map_id_update, user_id_update)))
+            .set(role.eq(new_role));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Remove a collaborator from a map.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn delete(
+        map_id_delete: i32,
+        user_id_delete: Uuid,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<usize> {
+        let query = diesel::delete(map_collaborators::table.find((map_id_delete, user_id_delete)));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.execute(conn).await
+    }
+}
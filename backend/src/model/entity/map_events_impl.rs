@@ -0,0 +1,50 @@
+//! Contains the implementation of [`MapEvent`].
+
+use diesel::{debug_query, pg::Pg, ExpressionMethods, QueryDsl, QueryResult};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use log::debug;
+
+use crate::schema::map_events::{self, id, map_id};
+
+use super::{MapEvent, NewMapEvent};
+
+impl MapEvent {
+    /// Fetch the event by id from the database.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_by_id(event_id: i64, conn: &mut AsyncPgConnection) -> QueryResult<Self> {
+        let query = map_events::table.find(event_id);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.first::<Self>(conn).await
+    }
+
+    /// Get at most `limit` events of the map starting with the event with id `first_id`, oldest first.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_starting_at(
+        map_id_search: i32,
+        first_id: i64,
+        limit: i64,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<Self>> {
+        let query = map_events::table
+            .filter(map_id.eq(map_id_search))
+            .filter(id.ge(first_id))
+            .order(id.asc())
+            .limit(limit);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.load::<Self>(conn).await
+    }
+
+    /// Store a new event in the database.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn create(event: NewMapEvent, conn: &mut AsyncPgConnection) -> QueryResult<Self> {
+        let query = diesel::insert_into(map_events::table).values(&event);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await
+    }
+}
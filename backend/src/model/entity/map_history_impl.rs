@@ -0,0 +1,72 @@
+//! Contains the implementation of [`MapHistoryEntry`].
+
+use chrono::NaiveTime;
+use diesel::pg::Pg;
+use diesel::{debug_query, ExpressionMethods, QueryDsl, QueryResult};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use log::debug;
+
+use crate::db::pagination::Paginate;
+use crate::model::dto::history::MapHistorySearchParameters;
+use crate::model::dto::{Page, PageParameters};
+use crate::schema::map_history::{
+    self, created_at, entity_id, entity_type, id, layer_id, map_id, user_id,
+};
+
+use super::{MapHistoryEntry, NewMapHistoryEntry};
+
+impl MapHistoryEntry {
+    /// Get a page of the changes made to the map, the latest first.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find(
+        map_id_search: i32,
+        search_parameters: MapHistorySearchParameters,
+        page_parameters: PageParameters,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Page<Self>> {
+        let mut query = map_history::table
+            .filter(map_id.eq(map_id_search))
+            .into_boxed();
+
+        if let Some(user_id_search) = search_parameters.user_id {
+            query = query.filter(user_id.eq(user_id_search));
+        }
+        if let Some(entity_type_search) = search_parameters.entity_type {
+            query = query.filter(entity_type.eq(entity_type_search));
+        }
+        if let Some(layer_id_search) = search_parameters.layer_id {
+            query = query.filter(layer_id.eq(layer_id_search));
+        }
+        if let Some(entity_id_search) = search_parameters.entity_id {
+            query = query.filter(entity_id.eq(entity_id_search));
+        }
+        if let Some(from) = search_parameters.from {
+            query = query.filter(created_at.ge(from.and_time(NaiveTime::MIN)));
+        }
+        if let Some(to) = search_parameters.to.and_then(|to| to.succ_opt()) {
+            query = query.filter(created_at.lt(to.and_time(NaiveTime::MIN)));
+        }
+
+        let query = query
+            .order(id.desc())
+            .paginate(page_parameters.page)
+            .per_page(page_parameters.per_page);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.load_page::<Self>(conn).await
+    }
+
+    /// Add changes to the history.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn create_batch(
+        new_entries: Vec<NewMapHistoryEntry>,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<usize> {
+        let query = diesel::insert_into(map_history::table).values(&new_entries);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.execute(conn).await
+    }
+}
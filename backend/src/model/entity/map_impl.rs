@@ -1,11 +1,12 @@
 //! Contains the implementation of [`Map`].
 
+use chrono::{NaiveDate, Utc};
 use diesel::dsl::sql;
 use diesel::pg::Pg;
-use diesel::sql_types::Float;
+use diesel::sql_types::{Bool, Float};
 use diesel::{
-    debug_query, BoolExpressionMethods, ExpressionMethods, PgTextExpressionMethods, QueryDsl,
-    QueryResult,
+    debug_query, BoolExpressionMethods, BoxableExpression, ExpressionMethods, OptionalExtension,
+    PgTextExpressionMethods, QueryDsl, QueryResult,
 };
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use log::debug;
@@ -15,10 +16,14 @@ use crate::db::function::{similarity, PgTrgmExpressionMethods};
 use crate::db::pagination::Paginate;
 use crate::model::dto::{MapSearchParameters, Page, PageParameters, UpdateMapDto};
 use crate::model::entity::UpdateMap;
+use crate::model::r#enum::collaborator_role::CollaboratorRole;
+use crate::model::r#enum::privacy_option::PrivacyOption;
+use crate::schema::map_collaborators;
 use crate::schema::maps::name;
+use crate::schema::users;
 use crate::{
     model::dto::{MapDto, NewMapDto},
-    schema::maps::{self, all_columns, is_inactive, owner_id, privacy},
+    schema::maps::{self, all_columns, deletion_date, is_inactive, owner_id, privacy},
 };
 
 use super::{Map, NewMap};
@@ -28,6 +33,10 @@ impl Map {
     ///
     /// Can be filtered by `is_inactive` and `owner_id` if provided in `search_parameters`.
     /// This will be done with equals and is additional functionality for maps (when compared to plant search).
+    /// Filtering by `owner_id` also returns the maps the user collaborates on.
+    /// Deleted maps are hidden unless `is_deleted` is set, in which case only the deleted maps
+    /// owned by the user are returned.
+    /// Only maps visible to the user with id `user_id` are returned (see [`Map::visible_to`]).
     ///
     /// Uses `pg_trgm` to find matches in `name`.
     /// Ranks using the `pg_trgm` function `similarity()`.
@@ -37,13 +46,16 @@ impl Map {
     pub async fn find(
         search_parameters: MapSearchParameters,
         page_parameters: PageParameters,
+        user_id: Uuid,
         conn: &mut AsyncPgConnection,
     ) -> QueryResult<Page<MapDto>> {
+        let visible = Self::visible_to(user_id, conn).await?;
         let mut query = maps::table
             .select((
                 similarity(name, search_parameters.name.clone().unwrap_or_default()),
                 all_columns,
             ))
+            .filter(visible)
             .into_boxed();
 
         if let Some(search_query) = &search_parameters.name {
@@ -54,6 +66,17 @@ impl Map {
                 );
             }
         }
+        if search_parameters.is_deleted.unwrap_or(false) {
+            let owned_maps = map_collaborators::table
+                .filter(map_collaborators::user_id.eq(user_id))
+                .filter(map_collaborators::role.eq(CollaboratorRole::Owner))
+                .select(map_collaborators::map_id);
+            query = query
+                .filter(deletion_date.is_not_null())
+                .filter(owner_id.eq(user_id).or(maps::id.eq_any(owned_maps)));
+        } else {
+            query = query.filter(deletion_date.is_null());
+        }
         if let Some(is_inactive_search) = search_parameters.is_inactive {
             query = query.filter(is_inactive.eq(is_inactive_search));
         }
@@ -61,7 +84,14 @@ impl Map {
             query = query.filter(privacy.eq(privacy_search));
         }
         if let Some(owner_id_search) = search_parameters.owner_id {
-            query = query.filter(owner_id.eq(owner_id_search));
+            let collaborated_maps = map_collaborators::table
+                .filter(map_collaborators::user_id.eq(owner_id_search))
+                .select(map_collaborators::map_id);
+            query = query.filter(
+                owner_id
+                    .eq(owner_id_search)
+                    .or(maps::id.eq_any(collaborated_maps)),
+            );
         }
 
         let query = query
@@ -85,6 +115,63 @@ impl Map {
         query.first::<Self>(conn).await.map(Into::into)
     }
 
+    /// Fetch map by id from the database if it is visible to the user with id `user_id`.
+    /// Maps the user is not allowed to see and maps marked for deletion are reported as not found.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_visible_by_id(
+        id: i32,
+        user_id: Uuid,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<MapDto> {
+        let visible = Self::visible_to(user_id, conn).await?;
+        let query = maps::table
+            .find(id)
+            .filter(deletion_date.is_null())
+            .filter(visible);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.first::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Build a filter matching all maps the user with id `user_id` is allowed to see.
+    ///
+    /// * `public` maps are visible to everyone.
+    /// * `protected` maps are visible to members of `PermaplanT`.
+    /// * `private` maps are only visible to their owner and collaborators.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    async fn visible_to(
+        user_id: Uuid,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Box<dyn BoxableExpression<maps::table, Pg, SqlType = Bool>>> {
+        let query = users::table
+            .find(user_id)
+            .select(users::membership.is_not_null());
+        debug!("{}", debug_query::<Pg, _>(&query));
+        let is_member = query.first::<bool>(conn).await.optional()?.unwrap_or(false);
+
+        let collaborated_maps = map_collaborators::table
+            .filter(map_collaborators::user_id.eq(user_id))
+            .select(map_collaborators::map_id);
+        let is_owner_or_collaborator = owner_id.eq(user_id).or(maps::id.eq_any(collaborated_maps));
+
+        if is_member {
+            Ok(Box::new(
+                privacy
+                    .ne(PrivacyOption::Private)
+                    .or(is_owner_or_collaborator),
+            ))
+        } else {
+            Ok(Box::new(
+                privacy
+                    .eq(PrivacyOption::Public)
+                    .or(is_owner_or_collaborator),
+            ))
+        }
+    }
+
     /// Create a new map in the database.
     ///
     /// # Errors
@@ -114,4 +201,26 @@ impl Map {
         debug!("{}", debug_query::<Pg, _>(&query));
         query.get_result::<Self>(conn).await.map(Into::into)
     }
+
+    /// Mark a map for deletion by setting its `deletion_date` to today.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn mark_for_deletion(id: i32, conn: &mut AsyncPgConnection) -> QueryResult<MapDto> {
+        let query = diesel::update(maps::table.find(id))
+            .set(deletion_date.eq(Some(Utc::now().date_naive())));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Restore a map that was marked for deletion.
+    /// Only the `deletion_date` is reset, `is_inactive` keeps the value chosen by the owner.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn restore(id: i32, conn: &mut AsyncPgConnection) -> QueryResult<MapDto> {
+        let query = diesel::update(maps::table.find(id)).set(deletion_date.eq(None::<NaiveDate>));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
 }
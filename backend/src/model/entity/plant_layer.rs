@@ -3,18 +3,21 @@
 use diesel::{
     debug_query,
     pg::Pg,
-    sql_types::{Float, Integer},
+    sql_types::{Array, Float, Integer},
     CombineDsl, ExpressionMethods, QueryDsl, QueryResult, QueryableByName,
 };
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use log::debug;
+use uuid::Uuid;
 
 use crate::{
     model::{
         dto::{RelationDto, RelationSearchParameters, RelationsDto},
-        r#enum::relation_type::RelationType,
+        r#enum::{
+            relation_type::RelationType, shade::Shade, soil_ph::SoilPh, soil_texture::SoilTexture,
+        },
     },
-    schema::relations,
+    schema::{relations, sql_types},
 };
 
 /// The resolution of the generated heatmap in cm.
@@ -37,6 +40,88 @@ struct BoundingBox {
     y_max: i32,
 }
 
+/// The scores of the heatmap of a map.
+#[derive(Debug, Clone)]
+pub struct HeatMap {
+    /// The lowest x value in the geometry of the map, the coordinate the first column starts at.
+    pub x_min: i32,
+    /// The lowest y value in the geometry of the map, the coordinate the first row starts at.
+    pub y_min: i32,
+    /// The scores with values 0-1, one row per [`GRANULARITY`] cm.
+    pub scores: Vec<Vec<f32>>,
+}
+
+impl HeatMap {
+    /// Get the score of the cell in column `x` and row `y`.
+    pub fn score_mut(&mut self, x: i32, y: i32) -> Option<&mut f32> {
+        let row = self.scores.get_mut(usize::try_from(y).ok()?)?;
+        row.get_mut(usize::try_from(x).ok()?)
+    }
+
+    /// Get the number of columns and rows of the heatmap.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)] // ok, because the matrix is never that large
+    fn size(&self) -> (i32, i32) {
+        let num_cols = <[Vec<f32>]>::first(&self.scores).map_or(0, Vec::len);
+        (num_cols as i32, self.scores.len() as i32)
+    }
+}
+
+/// The most shade a cell of the heatmap gets.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct ShadedCell {
+    /// The column of the cell.
+    #[diesel(sql_type = Integer)]
+    pub x: i32,
+    /// The row of the cell.
+    #[diesel(sql_type = Integer)]
+    pub y: i32,
+    /// The most shade of the areas covering the center of the cell.
+    #[diesel(sql_type = sql_types::Shade)]
+    pub shade: Shade,
+}
+
+/// The soil of a zone covering a cell of the heatmap.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct SoilCell {
+    /// The column of the cell.
+    #[diesel(sql_type = Integer)]
+    pub x: i32,
+    /// The row of the cell.
+    #[diesel(sql_type = Integer)]
+    pub y: i32,
+    /// The pH value of the soil in the zone.
+    #[diesel(sql_type = sql_types::SoilPh)]
+    pub soil_ph: SoilPh,
+    /// The texture of the soil in the zone.
+    #[diesel(sql_type = sql_types::SoilTexture)]
+    pub soil_texture: SoilTexture,
+}
+
+/// The most shade a planting gets.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct PlantingShade {
+    /// The id of the planting.
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub planting_id: Uuid,
+    /// The most shade of the areas covering the planting.
+    #[diesel(sql_type = sql_types::Shade)]
+    pub shade: Shade,
+}
+
+/// The soil of a zone covering a planting.
+#[derive(Debug, Clone, QueryableByName)]
+pub struct PlantingSoil {
+    /// The id of the planting.
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub planting_id: Uuid,
+    /// The pH value of the soil in the zone.
+    #[diesel(sql_type = sql_types::SoilPh)]
+    pub soil_ph: SoilPh,
+    /// The texture of the soil in the zone.
+    #[diesel(sql_type = sql_types::SoilTexture)]
+    pub soil_texture: SoilTexture,
+}
+
 /// Stores the score of a x,y coordinate on the heatmap.
 #[derive(Debug, Clone, QueryableByName)]
 struct HeatMapElement {
@@ -67,7 +152,7 @@ pub async fn heatmap(
     layer_id: i32,
     plant_id: i32,
     conn: &mut AsyncPgConnection,
-) -> QueryResult<Vec<Vec<f32>>> {
+) -> QueryResult<HeatMap> {
     // Fetch the bounding box x and y values of the maps coordinates
     let bounding_box_query =
         diesel::sql_query("SELECT * FROM calculate_bbox($1)").bind::<Integer, _>(map_id);
@@ -93,11 +178,157 @@ pub async fn heatmap(
         (f64::from(bounding_box.x_max - bounding_box.x_min) / f64::from(GRANULARITY)).ceil();
     let num_rows =
         (f64::from(bounding_box.y_max - bounding_box.y_min) / f64::from(GRANULARITY)).ceil();
-    let mut heatmap = vec![vec![0.0; num_cols as usize]; num_rows as usize];
+    let mut scores = vec![vec![0.0; num_cols as usize]; num_rows as usize];
     for HeatMapElement { score, x, y } in result {
-        heatmap[y as usize][x as usize] = score;
+        scores[y as usize][x as usize] = score;
     }
-    Ok(heatmap)
+    Ok(HeatMap {
+        x_min: bounding_box.x_min,
+        y_min: bounding_box.y_min,
+        scores,
+    })
+}
+
+/// Get the cells of the heatmap whose center is covered by shade areas
+/// of the main shade layer of the map, together with the most shade they get.
+///
+/// # Errors
+/// * Unknown, diesel doesn't say why it might error.
+pub async fn find_shaded_cells(
+    map_id: i32,
+    heatmap: &HeatMap,
+    conn: &mut AsyncPgConnection,
+) -> QueryResult<Vec<ShadedCell>> {
+    let (num_cols, num_rows) = heatmap.size();
+    let query = diesel::sql_query(format!(
+        "SELECT cells_x.x, cells_y.y, max(shade_areas.shade) AS shade
+        {}
+        GROUP BY cells_x.x, cells_y.y",
+        covered_cells("shade_areas")
+    ))
+    .bind::<Integer, _>(map_id)
+    .bind::<Integer, _>(heatmap.x_min)
+    .bind::<Integer, _>(heatmap.y_min)
+    .bind::<Integer, _>(GRANULARITY)
+    .bind::<Integer, _>(num_cols)
+    .bind::<Integer, _>(num_rows);
+    debug!("{}", debug_query::<Pg, _>(&query));
+    query.load::<ShadedCell>(conn).await
+}
+
+/// Get the cells of the heatmap whose center is covered by soil zones
+/// of the main soil layer of the map, once for every zone covering them.
+///
+/// # Errors
+/// * Unknown, diesel doesn't say why it might error.
+pub async fn find_soil_cells(
+    map_id: i32,
+    heatmap: &HeatMap,
+    conn: &mut AsyncPgConnection,
+) -> QueryResult<Vec<SoilCell>> {
+    let (num_cols, num_rows) = heatmap.size();
+    let query = diesel::sql_query(format!(
+        "SELECT cells_x.x, cells_y.y, soil_zones.soil_ph, soil_zones.soil_texture
+        {}",
+        covered_cells("soil_zones")
+    ))
+    .bind::<Integer, _>(map_id)
+    .bind::<Integer, _>(heatmap.x_min)
+    .bind::<Integer, _>(heatmap.y_min)
+    .bind::<Integer, _>(GRANULARITY)
+    .bind::<Integer, _>(num_cols)
+    .bind::<Integer, _>(num_rows);
+    debug!("{}", debug_query::<Pg, _>(&query));
+    query.load::<SoilCell>(conn).await
+}
+
+/// The `FROM` and `WHERE` clauses joining the polygons in `table` on main layers of the map
+/// with the cells of the heatmap whose center they cover.
+///
+/// Only the cells within the bounding box of each polygon are checked.
+/// Binds the map id, `x_min`, `y_min`, the granularity and the number of columns and rows of the heatmap.
+fn covered_cells(table: &str) -> String {
+    format!(
+        "FROM {table}
+        INNER JOIN layers ON layers.id = {table}.layer_id
+        CROSS JOIN LATERAL generate_series(
+            greatest(floor((ST_XMin({table}.geometry) - $2) / $4)::integer, 0),
+            least(ceil((ST_XMax({table}.geometry) - $2) / $4)::integer, $5 - 1)
+        ) AS cells_x(x)
+        CROSS JOIN LATERAL generate_series(
+            greatest(floor((ST_YMin({table}.geometry) - $3) / $4)::integer, 0),
+            least(ceil((ST_YMax({table}.geometry) - $3) / $4)::integer, $6 - 1)
+        ) AS cells_y(y)
+        WHERE layers.map_id = $1
+            AND NOT layers.is_alternative
+            AND layers.deletion_date IS NULL
+            AND ST_Covers(
+                {table}.geometry,
+                ST_SetSRID(ST_MakePoint($2 + $4 * (cells_x.x + 0.5), $3 + $4 * (cells_y.y + 0.5)), 4326)
+            )"
+    )
+}
+
+/// Get the most shade the plantings get according to the main shade layer of the map.
+/// Plantings not covered by any shade area are not returned.
+///
+/// # Errors
+/// * Unknown, diesel doesn't say why it might error.
+pub async fn find_planting_shades(
+    map_id: i32,
+    planting_ids: &[Uuid],
+    conn: &mut AsyncPgConnection,
+) -> QueryResult<Vec<PlantingShade>> {
+    let query = diesel::sql_query(format!(
+        "SELECT plantings.id AS planting_id, max(shade_areas.shade) AS shade
+        {}
+        GROUP BY plantings.id",
+        covered_plantings("shade_areas")
+    ))
+    .bind::<Integer, _>(map_id)
+    .bind::<Array<diesel::sql_types::Uuid>, _>(planting_ids);
+    debug!("{}", debug_query::<Pg, _>(&query));
+    query.load::<PlantingShade>(conn).await
+}
+
+/// Get the soil at the plantings according to the main soil layer of the map,
+/// once for every zone covering them.
+///
+/// # Errors
+/// * Unknown, diesel doesn't say why it might error.
+pub async fn find_planting_soils(
+    map_id: i32,
+    planting_ids: &[Uuid],
+    conn: &mut AsyncPgConnection,
+) -> QueryResult<Vec<PlantingSoil>> {
+    let query = diesel::sql_query(format!(
+        "SELECT plantings.id AS planting_id, soil_zones.soil_ph, soil_zones.soil_texture
+        {}",
+        covered_plantings("soil_zones")
+    ))
+    .bind::<Integer, _>(map_id)
+    .bind::<Array<diesel::sql_types::Uuid>, _>(planting_ids);
+    debug!("{}", debug_query::<Pg, _>(&query));
+    query.load::<PlantingSoil>(conn).await
+}
+
+/// The `FROM` and `WHERE` clauses joining the polygons in `table` on main layers of the map
+/// with the plantings they cover.
+///
+/// Binds the map id and the ids of the plantings.
+fn covered_plantings(table: &str) -> String {
+    format!(
+        "FROM plantings
+        INNER JOIN {table} ON ST_Covers(
+            {table}.geometry,
+            ST_SetSRID(ST_MakePoint(plantings.x, plantings.y), 4326)
+        )
+        INNER JOIN layers ON layers.id = {table}.layer_id
+        WHERE layers.map_id = $1
+            AND NOT layers.is_alternative
+            AND layers.deletion_date IS NULL
+            AND plantings.id = ANY($2)"
+    )
 }
 
 /// Get all relations of a certain plant.
@@ -1,6 +1,6 @@
 //! All entities associated with [`Planting`].
 
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
 use diesel::{AsChangeset, Identifiable, Insertable, Queryable};
 use uuid::Uuid;
 
@@ -36,14 +36,17 @@ pub struct Planting {
     /// The date the planting was removed from the map.
     /// If None, the planting is still on the map.
     pub remove_date: Option<NaiveDate>,
-    /*
     /// The date the planting was created.
-    //pub create_date: NaiveDate,
-
+    pub create_date: NaiveDate,
     /// The date the planting was 'soft' deleted
     /// and is still able to be restored.
-    //pub delete_date: Option<NaiveDate>,
-    */
+    pub delete_date: Option<NaiveDate>,
+    /// The version of the planting.
+    /// It is incremented on every update to detect concurrent changes.
+    pub version: i32,
+    /// The date and time (UTC) the planting was last changed.
+    /// It is maintained by the database, values set by the backend are ignored.
+    pub updated_at: NaiveDateTime,
 }
 
 /// The `UpdatePlanting` entity.
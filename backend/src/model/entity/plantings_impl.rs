@@ -1,18 +1,28 @@
 //! Contains the implementation of [`Planting`].
 
-use chrono::NaiveDate;
+use chrono::{Days, NaiveDate, NaiveDateTime, Utc};
+use diesel::dsl::sql;
 use diesel::pg::Pg;
-use diesel::{debug_query, BoolExpressionMethods, ExpressionMethods, QueryDsl, QueryResult};
+use diesel::sql_types::Bool;
+use diesel::{
+    debug_query, BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl, QueryResult,
+};
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use log::debug;
 use uuid::Uuid;
 
+use crate::db::cronjobs::DELETION_RETENTION_DAYS;
 use crate::model::dto::plantings::{NewPlantingDto, PlantingDto, UpdatePlantingDto};
 use crate::model::entity::plantings::{Planting, UpdatePlanting};
-use crate::schema::plantings::{self, all_columns, layer_id, plant_id};
+use crate::schema::plantings::{
+    self, all_columns, delete_date, layer_id, plant_id, updated_at, version,
+};
+use crate::schema::{layers, maps};
 
 /// Arguments for the database layer find plantings function.
 pub struct FindPlantingsParameters {
+    /// The id of the map to find plantings on.
+    pub map_id: i32,
     /// The id of the plant to find plantings for.
     pub plant_id: Option<i32>,
     /// The id of the layer to find plantings for.
@@ -25,6 +35,7 @@ pub struct FindPlantingsParameters {
 
 impl Planting {
     /// Get all plantings associated with the query.
+    /// Deleted plantings are not returned.
     ///
     /// # Errors
     /// * Unknown, diesel doesn't say why it might error.
@@ -32,7 +43,12 @@ impl Planting {
         search_parameters: FindPlantingsParameters,
         conn: &mut AsyncPgConnection,
     ) -> QueryResult<Vec<PlantingDto>> {
-        let mut query = plantings::table.select(all_columns).into_boxed();
+        let mut query = plantings::table
+            .inner_join(layers::table)
+            .select(all_columns)
+            .filter(layers::map_id.eq(search_parameters.map_id))
+            .filter(delete_date.is_null())
+            .into_boxed();
 
         if let Some(id) = search_parameters.plant_id {
             query = query.filter(plant_id.eq(id));
@@ -62,6 +78,102 @@ impl Planting {
             .collect())
     }
 
+    /// Get all plantings that are not deleted on any layer of the map not marked for deletion
+    /// and are placed outside of the geometry of the map.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_outside_map_geometry(
+        map_id: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<PlantingDto>> {
+        let query = plantings::table
+            .inner_join(layers::table.inner_join(maps::table))
+            .select(all_columns)
+            .filter(layers::map_id.eq(map_id))
+            .filter(layers::deletion_date.is_null())
+            .filter(delete_date.is_null())
+            .filter(sql::<Bool>(
+                "NOT ST_Covers(maps.geometry, ST_SetSRID(ST_MakePoint(plantings.x, plantings.y), 4326))",
+            ));
+        debug!("{}", debug_query::<Pg, _>(&query));
+
+        Ok(query
+            .load::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Get the plantings on any layer of the map changed at or after `since`.
+    /// Deleted plantings are returned as well, as their deletion is a change.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_changed_since(
+        map_id: i32,
+        since: NaiveDateTime,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<Self>> {
+        let query = plantings::table
+            .inner_join(layers::table)
+            .select(all_columns)
+            .filter(layers::map_id.eq(map_id))
+            .filter(updated_at.ge(since));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.load::<Self>(conn).await
+    }
+
+    /// Count how many of the plantings with the given ids are placed on a layer of the map
+    /// that is not marked for deletion.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn count_on_map(
+        ids: &[Uuid],
+        map_id: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<i64> {
+        let query = plantings::table
+            .inner_join(layers::table)
+            .filter(plantings::id.eq_any(ids))
+            .filter(layers::map_id.eq(map_id))
+            .filter(layers::deletion_date.is_null())
+            .count();
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<i64>(conn).await
+    }
+
+    /// Fetch planting by id from the database.
+    /// Deleted plantings are returned as well.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_by_id(id: Uuid, conn: &mut AsyncPgConnection) -> QueryResult<PlantingDto> {
+        let query = plantings::table.find(id);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.first::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Fetch planting by id from the database.
+    /// Returns `None` if the planting doesn't exist or is deleted.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_existing_by_id(
+        id: Uuid,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Option<PlantingDto>> {
+        let query = plantings::table.find(id).filter(delete_date.is_null());
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query
+            .first::<Self>(conn)
+            .await
+            .optional()
+            .map(|planting| planting.map(Into::into))
+    }
+
     /// Create a new planting in the database.
     ///
     /// # Errors
@@ -77,7 +189,51 @@ impl Planting {
         query.get_result::<Self>(conn).await.map(Into::into)
     }
 
-    /// Partially update a planting in the database.
+    /// Copy all plantings of a layer that are not deleted to another layer.
+    /// The copies get new ids and start with the first version.
+    ///
+    /// # Errors
+    /// * If the `to_layer_id` references a layer that is not of type `plant`.
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn copy_to_layer(
+        from_layer_id: i32,
+        to_layer_id: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<PlantingDto>> {
+        let query = plantings::table
+            .filter(layer_id.eq(from_layer_id))
+            .filter(delete_date.is_null());
+        debug!("{}", debug_query::<Pg, _>(&query));
+        let copies: Vec<_> = query
+            .load::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(|planting| Self {
+                id: Uuid::new_v4(),
+                layer_id: to_layer_id,
+                create_date: Utc::now().date_naive(),
+                version: 1,
+                ..planting
+            })
+            .collect();
+        if copies.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let insert_query = diesel::insert_into(plantings::table).values(&copies);
+        debug!("{}", debug_query::<Pg, _>(&insert_query));
+        Ok(insert_query
+            .get_results::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Partially update a planting in the database and increment its version.
+    /// Deleted plantings can't be updated.
+    ///
+    /// Returns `None` if the planting is deleted or not in the version the update is based on.
     ///
     /// # Errors
     /// * Unknown, diesel doesn't say why it might error.
@@ -85,20 +241,73 @@ impl Planting {
         planting_id: Uuid,
         dto: UpdatePlantingDto,
         conn: &mut AsyncPgConnection,
-    ) -> QueryResult<PlantingDto> {
+    ) -> QueryResult<Option<PlantingDto>> {
+        let expected_version = dto.version();
         let planting = UpdatePlanting::from(dto);
-        let query = diesel::update(plantings::table.find(planting_id)).set(&planting);
+        let query = diesel::update(plantings::table.find(planting_id))
+            .filter(delete_date.is_null())
+            .filter(version.eq(expected_version))
+            .set((&planting, version.eq(version + 1)));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query
+            .get_result::<Self>(conn)
+            .await
+            .optional()
+            .map(|updated| updated.map(Into::into))
+    }
+
+    /// Overwrite the planting with `dto`.
+    /// The planting is created if it doesn't exist and restored if it is deleted.
+    /// The version of an existing planting is incremented.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn replace(
+        dto: PlantingDto,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<PlantingDto> {
+        let planting = Self::from(dto);
+        let update = UpdatePlanting::from(dto);
+        let query = diesel::insert_into(plantings::table)
+            .values(&planting)
+            .on_conflict(plantings::id)
+            .do_update()
+            .set((
+                &update,
+                delete_date.eq(None::<NaiveDate>),
+                version.eq(version + 1),
+            ));
         debug!("{}", debug_query::<Pg, _>(&query));
         query.get_result::<Self>(conn).await.map(Into::into)
     }
 
-    /// Delete the planting from the database.
+    /// Mark the planting as deleted.
+    /// It can be restored for [`DELETION_RETENTION_DAYS`] days.
     ///
     /// # Errors
     /// * Unknown, diesel doesn't say why it might error.
     pub async fn delete_by_id(id: Uuid, conn: &mut AsyncPgConnection) -> QueryResult<usize> {
-        let query = diesel::delete(plantings::table.find(id));
+        let query = diesel::update(plantings::table.find(id))
+            .filter(delete_date.is_null())
+            .set(delete_date.eq(Some(Utc::now().date_naive())));
         debug!("{}", debug_query::<Pg, _>(&query));
         query.execute(conn).await
     }
+
+    /// Restore a planting deleted within the last [`DELETION_RETENTION_DAYS`] days.
+    ///
+    /// # Errors
+    /// * If the planting is not deleted or can no longer be restored.
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn restore(id: Uuid, conn: &mut AsyncPgConnection) -> QueryResult<PlantingDto> {
+        let oldest_restorable = Utc::now()
+            .date_naive()
+            .checked_sub_days(Days::new(DELETION_RETENTION_DAYS.into()))
+            .unwrap_or(NaiveDate::MIN);
+        let query = diesel::update(plantings::table.find(id))
+            .filter(delete_date.ge(oldest_restorable))
+            .set(delete_date.eq(None::<NaiveDate>));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
 }
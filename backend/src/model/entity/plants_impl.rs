@@ -101,6 +101,16 @@ impl Plants {
         query.first::<Self>(conn).await.map(Into::into)
     }
 
+    /// Fetch all plants with one of the ids from the database.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_by_ids(ids: &[i32], conn: &mut AsyncPgConnection) -> QueryResult<Vec<Self>> {
+        let query = plants::table.filter(plants::id.eq_any(ids));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.load::<Self>(conn).await
+    }
+
     /// Fetch available and seasonal plants.
     /// - A plant is available if for the given `user_id` there is a `Seed` with `quantity` not `Nothing`.
     /// - A plant is seasonal if the given `half_of_month` is included in a `Plant`'s `sowing_outdoors`
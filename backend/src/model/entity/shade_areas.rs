@@ -0,0 +1,35 @@
+//! All entities associated with [`ShadeArea`].
+
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable};
+use postgis_diesel::types::{Point, Polygon};
+use uuid::Uuid;
+
+use crate::model::r#enum::shade::Shade;
+use crate::schema::shade_areas;
+
+/// The `ShadeArea` entity.
+#[derive(Debug, Clone, Identifiable, Queryable, Insertable)]
+#[diesel(table_name = shade_areas)]
+pub struct ShadeArea {
+    /// The id of the shade area.
+    pub id: Uuid,
+    /// The shade layer the area is on.
+    pub layer_id: i32,
+    /// How much shade the area gets.
+    pub shade: Shade,
+    /// The outline of the area on the map.
+    pub geometry: Polygon<Point>,
+    /// The date and time (UTC) the area was last changed.
+    pub updated_at: NaiveDateTime,
+}
+
+/// The `UpdateShadeArea` entity.
+#[derive(Debug, Clone, Default, AsChangeset)]
+#[diesel(table_name = shade_areas)]
+pub struct UpdateShadeArea {
+    /// How much shade the area gets.
+    pub shade: Option<Shade>,
+    /// The outline of the area on the map.
+    pub geometry: Option<Polygon<Point>>,
+}
@@ -0,0 +1,186 @@
+//! Contains the implementation of [`ShadeArea`].
+
+use chrono::NaiveDateTime;
+use diesel::pg::Pg;
+use diesel::{debug_query, ExpressionMethods, OptionalExtension, QueryDsl, QueryResult};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use log::debug;
+use postgis_diesel::functions::st_covers;
+use postgis_diesel::types::Point;
+use uuid::Uuid;
+
+use crate::model::dto::shade_areas::{NewShadeAreaDto, ShadeAreaDto, UpdateShadeAreaDto};
+use crate::model::entity::shade_areas::{ShadeArea, UpdateShadeArea};
+use crate::schema::layers;
+use crate::schema::shade_areas::{self, all_columns, geometry, layer_id, shade, updated_at};
+
+impl ShadeArea {
+    /// Get all shade areas of the layer.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find(
+        layer_id_search: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<ShadeAreaDto>> {
+        let query = shade_areas::table
+            .select(all_columns)
+            .filter(layer_id.eq(layer_id_search));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        Ok(query
+            .load::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Get the shade areas on any layer of the map changed at or after `since`.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_changed_since(
+        map_id: i32,
+        since: NaiveDateTime,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<ShadeAreaDto>> {
+        let query = shade_areas::table
+            .inner_join(layers::table)
+            .select(all_columns)
+            .filter(layers::map_id.eq(map_id))
+            .filter(updated_at.ge(since));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        Ok(query
+            .load::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Fetch a shade area by id from the database.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_by_id(id: Uuid, conn: &mut AsyncPgConnection) -> QueryResult<ShadeAreaDto> {
+        let query = shade_areas::table.find(id);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.first::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Get the area of the layer with the most shade that covers the point.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_covering(
+        layer_id_search: i32,
+        x: f64,
+        y: f64,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Option<ShadeAreaDto>> {
+        let query = shade_areas::table
+            .select(all_columns)
+            .filter(layer_id.eq(layer_id_search))
+            .filter(st_covers(geometry, Point::new(x, y, Some(4326))))
+            .order(shade.desc());
+        debug!("{}", debug_query::<Pg, _>(&query));
+        Ok(query.first::<Self>(conn).await.optional()?.map(Into::into))
+    }
+
+    /// Create a new shade area in the database.
+    ///
+    /// # Errors
+    /// * If the `layer_id` references a layer that is not of type `shade`.
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn create(
+        dto: NewShadeAreaDto,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<ShadeAreaDto> {
+        let area = Self::from(dto);
+        let query = diesel::insert_into(shade_areas::table).values(&area);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Copy all shade areas of a layer to another layer.
+    /// The copies get new ids.
+    ///
+    /// # Errors
+    /// * If the `to_layer_id` references a layer that is not of type `shade`.
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn copy_to_layer(
+        from_layer_id: i32,
+        to_layer_id: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<ShadeAreaDto>> {
+        let query = shade_areas::table.filter(layer_id.eq(from_layer_id));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        let copies: Vec<_> = query
+            .load::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(|area| Self {
+                id: Uuid::new_v4(),
+                layer_id: to_layer_id,
+                ..area
+            })
+            .collect();
+        if copies.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let insert_query = diesel::insert_into(shade_areas::table).values(&copies);
+        debug!("{}", debug_query::<Pg, _>(&insert_query));
+        Ok(insert_query
+            .get_results::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Partially update a shade area in the database.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn update(
+        id: Uuid,
+        dto: UpdateShadeAreaDto,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<ShadeAreaDto> {
+        let update = UpdateShadeArea::from(dto);
+        let query = diesel::update(shade_areas::table.find(id)).set(&update);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Overwrite the shade area with `dto` or create it if it doesn't exist.
+    ///
+    /// # Errors
+    /// * If the `layer_id` references a layer that is not of type `shade`.
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn replace(
+        dto: ShadeAreaDto,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<ShadeAreaDto> {
+        let area = Self::from(dto);
+        let update = UpdateShadeArea::from(area.clone());
+        let query = diesel::insert_into(shade_areas::table)
+            .values(&area)
+            .on_conflict(shade_areas::id)
+            .do_update()
+            .set((&update, layer_id.eq(area.layer_id)));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Delete the shade area from the database.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn delete_by_id(id: Uuid, conn: &mut AsyncPgConnection) -> QueryResult<usize> {
+        let query = diesel::delete(shade_areas::table.find(id));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.execute(conn).await
+    }
+}
@@ -0,0 +1,39 @@
+//! All entities associated with [`SoilZone`].
+
+use chrono::NaiveDateTime;
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable};
+use postgis_diesel::types::{Point, Polygon};
+use uuid::Uuid;
+
+use crate::model::r#enum::{soil_ph::SoilPh, soil_texture::SoilTexture};
+use crate::schema::soil_zones;
+
+/// The `SoilZone` entity.
+#[derive(Debug, Clone, Identifiable, Queryable, Insertable)]
+#[diesel(table_name = soil_zones)]
+pub struct SoilZone {
+    /// The id of the soil zone.
+    pub id: Uuid,
+    /// The soil layer the zone is on.
+    pub layer_id: i32,
+    /// The pH value of the soil in the zone.
+    pub soil_ph: SoilPh,
+    /// The texture of the soil in the zone.
+    pub soil_texture: SoilTexture,
+    /// The outline of the zone on the map.
+    pub geometry: Polygon<Point>,
+    /// The date and time (UTC) the zone was last changed.
+    pub updated_at: NaiveDateTime,
+}
+
+/// The `UpdateSoilZone` entity.
+#[derive(Debug, Clone, Default, AsChangeset)]
+#[diesel(table_name = soil_zones)]
+pub struct UpdateSoilZone {
+    /// The pH value of the soil in the zone.
+    pub soil_ph: Option<SoilPh>,
+    /// The texture of the soil in the zone.
+    pub soil_texture: Option<SoilTexture>,
+    /// The outline of the zone on the map.
+    pub geometry: Option<Polygon<Point>>,
+}
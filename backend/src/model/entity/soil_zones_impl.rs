@@ -0,0 +1,165 @@
+//! Contains the implementation of [`SoilZone`].
+
+use chrono::NaiveDateTime;
+use diesel::pg::Pg;
+use diesel::{debug_query, ExpressionMethods, QueryDsl, QueryResult};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use log::debug;
+use uuid::Uuid;
+
+use crate::model::dto::soil_zones::{NewSoilZoneDto, SoilZoneDto, UpdateSoilZoneDto};
+use crate::model::entity::soil_zones::{SoilZone, UpdateSoilZone};
+use crate::schema::layers;
+use crate::schema::soil_zones::{self, all_columns, layer_id, updated_at};
+
+impl SoilZone {
+    /// Get all soil zones of the layer.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find(
+        layer_id_search: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<SoilZoneDto>> {
+        let query = soil_zones::table
+            .select(all_columns)
+            .filter(layer_id.eq(layer_id_search));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        Ok(query
+            .load::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Get the soil zones on any layer of the map changed at or after `since`.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_changed_since(
+        map_id: i32,
+        since: NaiveDateTime,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<SoilZoneDto>> {
+        let query = soil_zones::table
+            .inner_join(layers::table)
+            .select(all_columns)
+            .filter(layers::map_id.eq(map_id))
+            .filter(updated_at.ge(since));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        Ok(query
+            .load::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Fetch a soil zone by id from the database.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_by_id(id: Uuid, conn: &mut AsyncPgConnection) -> QueryResult<SoilZoneDto> {
+        let query = soil_zones::table.find(id);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.first::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Create a new soil zone in the database.
+    ///
+    /// # Errors
+    /// * If the `layer_id` references a layer that is not of type `soil`.
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn create(
+        dto: NewSoilZoneDto,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<SoilZoneDto> {
+        let zone = Self::from(dto);
+        let query = diesel::insert_into(soil_zones::table).values(&zone);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Copy all soil zones of a layer to another layer.
+    /// The copies get new ids.
+    ///
+    /// # Errors
+    /// * If the `to_layer_id` references a layer that is not of type `soil`.
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn copy_to_layer(
+        from_layer_id: i32,
+        to_layer_id: i32,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<SoilZoneDto>> {
+        let query = soil_zones::table.filter(layer_id.eq(from_layer_id));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        let copies: Vec<_> = query
+            .load::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(|zone| Self {
+                id: Uuid::new_v4(),
+                layer_id: to_layer_id,
+                ..zone
+            })
+            .collect();
+        if copies.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let insert_query = diesel::insert_into(soil_zones::table).values(&copies);
+        debug!("{}", debug_query::<Pg, _>(&insert_query));
+        Ok(insert_query
+            .get_results::<Self>(conn)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect())
+    }
+
+    /// Partially update a soil zone in the database.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn update(
+        id: Uuid,
+        dto: UpdateSoilZoneDto,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<SoilZoneDto> {
+        let update = UpdateSoilZone::from(dto);
+        let query = diesel::update(soil_zones::table.find(id)).set(&update);
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Overwrite the soil zone with `dto` or create it if it doesn't exist.
+    ///
+    /// # Errors
+    /// * If the `layer_id` references a layer that is not of type `soil`.
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn replace(
+        dto: SoilZoneDto,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<SoilZoneDto> {
+        let zone = Self::from(dto);
+        let update = UpdateSoilZone::from(zone.clone());
+        let query = diesel::insert_into(soil_zones::table)
+            .values(&zone)
+            .on_conflict(soil_zones::id)
+            .do_update()
+            .set((&update, layer_id.eq(zone.layer_id)));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result::<Self>(conn).await.map(Into::into)
+    }
+
+    /// Delete the soil zone from the database.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn delete_by_id(id: Uuid, conn: &mut AsyncPgConnection) -> QueryResult<usize> {
+        let query = diesel::delete(soil_zones::table.find(id));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.execute(conn).await
+    }
+}
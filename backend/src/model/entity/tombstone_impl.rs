@@ -0,0 +1,55 @@
+//! Contains the implementation of [`Tombstone`].
+
+use chrono::NaiveDateTime;
+use diesel::dsl::sql;
+use diesel::pg::Pg;
+use diesel::sql_types::Timestamptz;
+use diesel::{debug_query, ExpressionMethods, QueryDsl, QueryResult};
+use diesel_async::{AsyncPgConnection, RunQueryDsl};
+use log::debug;
+
+use crate::db::function::timezone;
+use crate::schema::tombstones::{self, deleted_at, map_id};
+
+use super::Tombstone;
+
+impl Tombstone {
+    /// Get the entities of the map deleted at or after `since`.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn find_since(
+        map_id_search: i32,
+        since: NaiveDateTime,
+        conn: &mut AsyncPgConnection,
+    ) -> QueryResult<Vec<Self>> {
+        let query = tombstones::table
+            .filter(map_id.eq(map_id_search))
+            .filter(deleted_at.ge(since));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.load::<Self>(conn).await
+    }
+
+    /// Get the point in time (UTC) up to which all changes of the database are visible.
+    /// It uses the same clock as `deleted_at` and all `updated_at` columns.
+    ///
+    /// Changes get the start time of their transaction as timestamp,
+    /// so a transaction still running might commit changes with an earlier timestamp than the current time.
+    /// Therefore the start of the oldest transaction still running is returned, if there is one.
+    /// Call it before reading the changes, so transactions committing in between are visible to the read.
+    ///
+    /// # Errors
+    /// * Unknown, diesel doesn't say why it might error.
+    pub async fn watermark(conn: &mut AsyncPgConnection) -> QueryResult<NaiveDateTime> {
+        // `least` ignores the start of the oldest transaction if there is none.
+        let query = diesel::select(timezone(
+            "UTC",
+            sql::<Timestamptz>(
+                "least(now(), (SELECT min(xact_start) FROM pg_stat_activity \
+                 WHERE datname = current_database() AND pid <> pg_backend_pid()))",
+            ),
+        ));
+        debug!("{}", debug_query::<Pg, _>(&query));
+        query.get_result(conn).await
+    }
+}
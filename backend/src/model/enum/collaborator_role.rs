@@ -0,0 +1,25 @@
+//! [`CollaboratorRole`] enum.
+
+use diesel_derive_enum::DbEnum;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+use utoipa::ToSchema;
+
+/// Enum for the roles a user can have on a map.
+#[typeshare]
+#[derive(Serialize, Deserialize, DbEnum, Debug, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::CollaboratorRole"]
+pub enum CollaboratorRole {
+    /// The user can edit the map, its settings and manage its collaborators.
+    #[serde(rename = "owner")]
+    #[db_rename = "owner"]
+    Owner,
+    /// The user can edit the content of the map.
+    #[serde(rename = "editor")]
+    #[db_rename = "editor"]
+    Editor,
+    /// The user can only view the map.
+    #[serde(rename = "viewer")]
+    #[db_rename = "viewer"]
+    Viewer,
+}
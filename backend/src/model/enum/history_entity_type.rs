@@ -0,0 +1,33 @@
+//! [`HistoryEntityType`] enum.
+
+use diesel_derive_enum::DbEnum;
+use serde::{Deserialize, Serialize};
+use typeshare::typeshare;
+use utoipa::ToSchema;
+
+/// Enum for the kinds of entities whose changes are recorded in the history of a map.
+#[typeshare]
+#[derive(Serialize, Deserialize, DbEnum, Debug, ToSchema, Clone, Copy, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::HistoryEntityType"]
+pub enum HistoryEntityType {
+    /// A planting on a plant layer.
+    #[serde(rename = "planting")]
+    #[db_rename = "planting"]
+    Planting,
+    /// A layer of the map.
+    #[serde(rename = "layer")]
+    #[db_rename = "layer"]
+    Layer,
+    /// An image on a base layer.
+    #[serde(rename = "base_layer_image")]
+    #[db_rename = "base_layer_image"]
+    BaseLayerImage,
+    /// An area on a shade layer.
+    #[serde(rename = "shade_area")]
+    #[db_rename = "shade_area"]
+    ShadeArea,
+    /// A zone on a soil layer.
+    #[serde(rename = "soil_zone")]
+    #[db_rename = "soil_zone"]
+    SoilZone,
+}
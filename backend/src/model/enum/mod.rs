@@ -1,5 +1,6 @@
 //! Enums used in the database.
 
+pub mod collaborator_role;
 pub mod deciduous_or_evergreen;
 pub mod experience;
 pub mod external_source;
@@ -7,6 +8,7 @@ pub mod fertility;
 //pub mod flower_type;
 pub mod growth_rate;
 pub mod herbaceous_or_woody;
+pub mod history_entity_type;
 pub mod layer_type;
 pub mod life_cycle;
 pub mod light_requirement;
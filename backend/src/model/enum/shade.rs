@@ -1,13 +1,18 @@
 //! [`Shade`] enum.
 
+use core::fmt;
+
 use diesel_derive_enum::DbEnum;
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
 use utoipa::ToSchema;
 
+/// How much shade a place gets, ordered from the least to the most shade.
 #[allow(clippy::missing_docs_in_private_items)] // TODO: See #97.
 #[typeshare]
-#[derive(Serialize, Deserialize, DbEnum, Debug, ToSchema)]
+#[derive(
+    Serialize, Deserialize, DbEnum, Debug, ToSchema, Clone, Copy, PartialEq, Eq, PartialOrd, Ord,
+)]
 #[ExistingTypePath = "crate::schema::sql_types::Shade"]
 pub enum Shade {
     #[serde(rename = "no shade")]
@@ -30,3 +35,15 @@ pub enum Shade {
     #[db_rename = "permanent deep shade"]
     PermanentDeepShade,
 }
+
+impl fmt::Display for Shade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoShade => write!(f, "no shade"),
+            Self::LightShade => write!(f, "light shade"),
+            Self::PartialShade => write!(f, "partial shade"),
+            Self::PermanentShade => write!(f, "permanent shade"),
+            Self::PermanentDeepShade => write!(f, "permanent deep shade"),
+        }
+    }
+}
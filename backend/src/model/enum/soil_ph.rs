@@ -1,5 +1,7 @@
 //! [`SoilPh`] enum.
 
+use core::fmt;
+
 use diesel_derive_enum::DbEnum;
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
@@ -7,7 +9,7 @@ use utoipa::ToSchema;
 
 #[allow(clippy::missing_docs_in_private_items)] // TODO: See #97.
 #[typeshare]
-#[derive(Serialize, Deserialize, DbEnum, Debug, ToSchema)]
+#[derive(Serialize, Deserialize, DbEnum, Debug, ToSchema, Clone, Copy, PartialEq, Eq)]
 #[ExistingTypePath = "crate::schema::sql_types::SoilPh"]
 pub enum SoilPh {
     #[serde(rename = "very acid")]
@@ -30,3 +32,15 @@ pub enum SoilPh {
     #[db_rename = "very alkaline"]
     VeryAlkaline,
 }
+
+impl fmt::Display for SoilPh {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::VeryAcidic => write!(f, "very acid"),
+            Self::Acidic => write!(f, "acid"),
+            Self::Neutral => write!(f, "neutral"),
+            Self::Alkaline => write!(f, "alkaline"),
+            Self::VeryAlkaline => write!(f, "very alkaline"),
+        }
+    }
+}
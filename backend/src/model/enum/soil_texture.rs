@@ -1,5 +1,7 @@
 //! [`SoilTexture`] enum.
 
+use core::fmt;
+
 use diesel_derive_enum::DbEnum;
 use serde::{Deserialize, Serialize};
 use typeshare::typeshare;
@@ -7,7 +9,7 @@ use utoipa::ToSchema;
 
 #[allow(clippy::missing_docs_in_private_items)] // TODO: See #97.
 #[typeshare]
-#[derive(Serialize, Deserialize, DbEnum, Debug, ToSchema)]
+#[derive(Serialize, Deserialize, DbEnum, Debug, ToSchema, Clone, Copy, PartialEq, Eq)]
 #[ExistingTypePath = "crate::schema::sql_types::SoilTexture"]
 pub enum SoilTexture {
     #[serde(rename = "sandy")]
@@ -26,3 +28,14 @@ pub enum SoilTexture {
     #[db_rename = "heavy clay"]
     HeavyClay,
 }
+
+impl fmt::Display for SoilTexture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sandy => write!(f, "sandy"),
+            Self::Loamy => write!(f, "loamy"),
+            Self::Clay => write!(f, "clay"),
+            Self::HeavyClay => write!(f, "heavy clay"),
+        }
+    }
+}
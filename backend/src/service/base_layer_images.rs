@@ -1,59 +1,122 @@
 //! Service layer for images on the base layer.
 
 use actix_web::web::Data;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::AsyncConnection;
 use uuid::Uuid;
 
 use crate::config::data::AppDataInner;
 use crate::error::ServiceError;
+use crate::model::dto::action_log::EntityChange;
 use crate::model::dto::{BaseLayerImageDto, UpdateBaseLayerImageDto};
 use crate::model::entity::BaseLayerImages;
+use crate::service::map_access_control::{
+    check_base_layer_image_permissions, check_layer_permissions, check_layer_visibility,
+};
+use crate::service::map_action_log;
 
 /// Fetch all base layer images for the layer from the database.
+/// Checks if the requesting user is allowed to see the map.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
+/// If the layer is not part of the map or the requesting user is not allowed to see the map.
 pub async fn find(
     app_data: &Data<AppDataInner>,
+    map_id: i32,
     layer_id: i32,
+    user_id: Uuid,
 ) -> Result<Vec<BaseLayerImageDto>, ServiceError> {
     let mut conn = app_data.pool.get().await?;
+    check_layer_visibility(map_id, layer_id, user_id, &mut conn).await?;
     let result = BaseLayerImages::find(&mut conn, layer_id).await?;
     Ok(result)
 }
 
 /// Create a base layer image in the database.
+/// Checks if the requesting user is allowed to edit the map.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
 pub async fn create(
     dto: BaseLayerImageDto,
+    map_id: i32,
+    user_id: Uuid,
     app_data: &Data<AppDataInner>,
 ) -> Result<BaseLayerImageDto, ServiceError> {
     let mut conn = app_data.pool.get().await?;
-    let result = BaseLayerImages::create(dto, &mut conn).await?;
-    Ok(result)
+    conn.transaction(|conn| {
+        async move {
+            check_layer_permissions(map_id, dto.layer_id, user_id, conn).await?;
+            let action_id = dto.action_id;
+            let result = BaseLayerImages::create(dto.into(), conn).await?;
+            let change = EntityChange::new(result.id, None, Some(result.clone()));
+            map_action_log::record(map_id, user_id, action_id, vec![change], conn).await?;
+            Ok(result)
+        }
+        .scope_boxed()
+    })
+    .await
 }
 
 /// Update the base layer image in the database.
+/// Checks if the requesting user is allowed to edit the map.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
 pub async fn update(
     id: Uuid,
     dto: UpdateBaseLayerImageDto,
+    map_id: i32,
+    user_id: Uuid,
     app_data: &Data<AppDataInner>,
 ) -> Result<BaseLayerImageDto, ServiceError> {
     let mut conn = app_data.pool.get().await?;
-    let result = BaseLayerImages::update(id, dto, &mut conn).await?;
-    Ok(result)
+    conn.transaction(|conn| {
+        async move {
+            check_base_layer_image_permissions(map_id, id, user_id, conn).await?;
+            // The image may be moved to another layer, which has to be on the same map.
+            check_layer_permissions(map_id, dto.layer_id, user_id, conn).await?;
+            let action_id = dto.action_id;
+            let before = BaseLayerImages::find_by_id(id, conn).await?;
+            let result = BaseLayerImages::update(id, dto, conn).await?;
+            let change = EntityChange::new(id, Some(before), Some(result.clone()));
+            map_action_log::record(map_id, user_id, action_id, vec![change], conn).await?;
+            Ok(result)
+        }
+        .scope_boxed()
+    })
+    .await
 }
 
 /// Delete the base layer image from the database.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// Returns the deleted base layer image.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
-pub async fn delete_by_id(id: Uuid, app_data: &Data<AppDataInner>) -> Result<(), ServiceError> {
+/// If the requesting user is not allowed to edit the map.
+pub async fn delete_by_id(
+    id: Uuid,
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<BaseLayerImageDto, ServiceError> {
     let mut conn = app_data.pool.get().await?;
-    let _ = BaseLayerImages::delete_by_id(id, &mut conn).await?;
-    Ok(())
+    conn.transaction(|conn| {
+        async move {
+            check_base_layer_image_permissions(map_id, id, user_id, conn).await?;
+            let before = BaseLayerImages::find_by_id(id, conn).await?;
+            let _ = BaseLayerImages::delete_by_id(id, conn).await?;
+            let change = EntityChange::new(id, Some(before.clone()), None);
+            map_action_log::record(map_id, user_id, action_id, vec![change], conn).await?;
+            Ok(before)
+        }
+        .scope_boxed()
+    })
+    .await
 }
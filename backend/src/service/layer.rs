@@ -1,9 +1,31 @@
 //! Service layer for layers.
 
+use actix_http::StatusCode;
 use actix_web::web::Data;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection};
+use uuid::Uuid;
 
 use crate::config::data::AppDataInner;
-use crate::model::dto::LayerSearchParameters;
+use crate::model::dto::action_log::EntityChange;
+use crate::model::dto::actions::{
+    Action, CreateBaseLayerImageActionPayload, CreatePlantActionPayload,
+    CreateShadeAreaActionPayload, CreateSoilZoneActionPayload,
+};
+use crate::model::dto::{
+    DuplicateLayerDto, LayerPreferencesDto, LayerSearchParameters, UpdateLayerDto,
+    UpdateLayerPreferencesDto,
+};
+use crate::model::entity::plantings::Planting;
+use crate::model::entity::shade_areas::ShadeArea;
+use crate::model::entity::soil_zones::SoilZone;
+use crate::model::entity::{BaseLayerImages, LayerPreferences, Map, NewLayer};
+use crate::model::r#enum::layer_type::LayerType;
+use crate::service::map::LAYER_TYPES;
+use crate::service::map_access_control::{
+    check_layer_permissions, check_layer_visibility, check_permissions, check_visibility,
+};
+use crate::service::map_history;
 use crate::{
     error::ServiceError,
     model::{
@@ -12,48 +34,562 @@ use crate::{
     },
 };
 
-/// Search layers from the database.
+/// Maximum number of characters in the name of a layer.
+const MAX_LAYER_NAME_LENGTH: usize = 100;
+
+/// Search layers of the map from the database.
+/// Checks if the requesting user is allowed to see the map.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
+/// If the requesting user is not allowed to see the map.
 pub async fn find(
-    search_parameters: LayerSearchParameters,
+    map_id: i32,
+    mut search_parameters: LayerSearchParameters,
+    user_id: Uuid,
     app_data: &Data<AppDataInner>,
 ) -> Result<Vec<LayerDto>, ServiceError> {
     let mut conn = app_data.pool.get().await?;
+    check_visibility(map_id, user_id, &mut conn).await?;
+    search_parameters.map_id = Some(map_id);
     let result = Layer::find(search_parameters, &mut conn).await?;
     Ok(result)
 }
 
-/// Find a layer by id in the database.
+/// Find a layer of the map by id in the database.
+/// Checks if the requesting user is allowed to see the map.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
-pub async fn find_by_id(id: i32, app_data: &Data<AppDataInner>) -> Result<LayerDto, ServiceError> {
+/// If the layer is not part of the map or the requesting user is not allowed to see the map.
+pub async fn find_by_id(
+    id: i32,
+    map_id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<LayerDto, ServiceError> {
     let mut conn = app_data.pool.get().await?;
+    check_layer_visibility(map_id, id, user_id, &mut conn).await?;
     let result = Layer::find_by_id(id, &mut conn).await?;
     Ok(result)
 }
 
 /// Create a new layer in the database.
+/// Checks if the requesting user is allowed to edit the map.
+/// The creation is recorded in the history of the map.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the name is empty, too long or already used by another layer of the map.
 pub async fn create(
     new_layer: NewLayerDto,
+    user_id: Uuid,
     app_data: &Data<AppDataInner>,
 ) -> Result<LayerDto, ServiceError> {
     let mut conn = app_data.pool.get().await?;
-    let result = Layer::create(new_layer, &mut conn).await?;
+    check_permissions(new_layer.map_id, user_id, &mut conn).await?;
+    conn.transaction(|conn| {
+        async move {
+            let name = validate_name(new_layer.map_id, &new_layer.name, None, conn).await?;
+            let action_id = new_layer.action_id;
+            let result = Layer::create(
+                NewLayer {
+                    name,
+                    ..new_layer.into()
+                },
+                conn,
+            )
+            .await?;
+            map_history::record_layer(user_id, action_id, None, Some(result.clone()), conn).await?;
+            Ok(result)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Mark the layer for deletion.
+///
+/// Alternative layers and layers of types that are not required can always be deleted.
+/// The last main layer of a type every map needs (see [`LAYER_TYPES`]) can't be deleted,
+/// an alternative has to be promoted first.
+/// The content of the layer is kept, so restoring the layer restores its content as well.
+/// It is removed permanently together with the layer after
+/// [`DELETION_RETENTION_DAYS`](crate::db::cronjobs::DELETION_RETENTION_DAYS) days.
+///
+/// Checks if the requesting user is allowed to edit the map.
+/// The deletion is recorded in the history of the map.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the layer is the last main layer of a required type.
+pub async fn delete_by_id(
+    id: i32,
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<(), ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    check_layer_permissions(map_id, id, user_id, &mut conn).await?;
+    conn.transaction(|conn| {
+        async move {
+            let before = Layer::find_by_id(id, conn).await?;
+            if LAYER_TYPES.contains(&before.type_) {
+                // Locked so concurrent deletions can't remove all main layers of the type.
+                let layers = Layer::lock_on_map(before.map_id, Some(before.type_), conn).await?;
+                let is_last_main_layer = layers
+                    .iter()
+                    .filter(|layer| layer.deletion_date.is_none() && !layer.is_alternative)
+                    .all(|layer| layer.id == id);
+                let is_main_layer = layers
+                    .iter()
+                    .any(|layer| layer.id == id && !layer.is_alternative);
+                if is_main_layer && is_last_main_layer {
+                    return Err(ServiceError::new(
+                        StatusCode::CONFLICT,
+                        "The last main layer of this type can't be deleted".to_owned(),
+                    ));
+                }
+            }
+            let _ = Layer::mark_for_deletion(id, conn).await?;
+            map_history::record_layer(user_id, action_id, Some(before), None, conn).await
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Restore a layer of the map that was marked for deletion together with its content.
+///
+/// If the type of the layer has a main layer again, the layer is restored as an alternative.
+/// Checks if the requesting user is allowed to edit the map.
+/// The restoration is recorded in the history of the map.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the layer is not marked for deletion or can no longer be restored.
+pub async fn restore(
+    id: i32,
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<LayerDto, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    check_permissions(map_id, user_id, &mut conn).await?;
+    conn.transaction(|conn| {
+        async move {
+            // Locked so concurrent restorations can't bring back several main layers of a type.
+            let layers = Layer::lock_on_map(map_id, None, conn).await?;
+            let mut restored = Layer::restore(id, map_id, conn).await?;
+            let has_main_layer = layers.iter().any(|layer| {
+                layer.id != id
+                    && layer.type_ == restored.type_
+                    && layer.deletion_date.is_none()
+                    && !layer.is_alternative
+            });
+            if has_main_layer && !restored.is_alternative {
+                restored = Layer::set_alternative(id, true, conn).await?;
+            }
+            map_history::record_layer(user_id, action_id, None, Some(restored.clone()), conn)
+                .await?;
+            Ok(restored)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Duplicate the layer together with its content as a new alternative layer.
+///
+/// The plantings, base layer images, shade areas or soil zones of the layer are copied with new ids.
+/// Checks if the requesting user is allowed to edit the map.
+/// The creation of the layer and its content is recorded in the history of the map.
+///
+/// Returns the new layer and the actions that create its content.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the name is empty, too long or already used by another layer of the map.
+pub async fn duplicate(
+    id: i32,
+    map_id: i32,
+    dto: DuplicateLayerDto,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<(LayerDto, Vec<Action>), ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    check_layer_permissions(map_id, id, user_id, &mut conn).await?;
+    conn.transaction(|conn| {
+        async move {
+            let layer = Layer::find_by_id(id, conn).await?;
+            let name = validate_name(layer.map_id, &dto.name, None, conn).await?;
+            let action_id = dto.action_id;
+            let new_layer = NewLayer {
+                map_id: layer.map_id,
+                type_: layer.type_,
+                name,
+                is_alternative: true,
+            };
+            let copy = Layer::create(new_layer, conn).await?;
+            let changes = copy_content(&layer, copy.id, conn).await?;
+            map_history::record_layer(user_id, action_id, None, Some(copy.clone()), conn).await?;
+            map_history::record(map_id, user_id, action_id, &changes, conn).await?;
+            let actions = create_actions(&changes, user_id, action_id);
+            Ok((copy, actions))
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Make the alternative layer the main layer of its type.
+///
+/// The previous main layers of the type become alternatives.
+/// Checks if the requesting user is allowed to edit the map.
+/// The changes are recorded in the history of the map.
+///
+/// Returns all changed layers, the promoted layer first.
+/// Nothing is changed if the layer already is a main layer.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+pub async fn promote(
+    id: i32,
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<Vec<LayerDto>, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    check_layer_permissions(map_id, id, user_id, &mut conn).await?;
+    conn.transaction(|conn| {
+        async move {
+            let layer = Layer::find_by_id(id, conn).await?;
+            if !layer.is_alternative {
+                return Ok(Vec::new());
+            }
+            let main_layers = Layer::find(
+                LayerSearchParameters {
+                    map_id: Some(layer.map_id),
+                    type_: Some(layer.type_),
+                    is_alternative: Some(false),
+                },
+                conn,
+            )
+            .await?;
+
+            let promoted = Layer::set_alternative(id, false, conn).await?;
+            map_history::record_layer(
+                user_id,
+                action_id,
+                Some(layer),
+                Some(promoted.clone()),
+                conn,
+            )
+            .await?;
+            let mut changed = vec![promoted];
+            for main_layer in main_layers {
+                let demoted = Layer::set_alternative(main_layer.id, true, conn).await?;
+                map_history::record_layer(
+                    user_id,
+                    action_id,
+                    Some(main_layer),
+                    Some(demoted.clone()),
+                    conn,
+                )
+                .await?;
+                changed.push(demoted);
+            }
+            Ok(changed)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Copy the content of the layer to the layer with id `to_layer_id`.
+/// Returns the changes for the history of the map.
+async fn copy_content(
+    layer: &LayerDto,
+    to_layer_id: i32,
+    conn: &mut AsyncPgConnection,
+) -> Result<Vec<EntityChange>, ServiceError> {
+    let changes = match layer.type_ {
+        LayerType::Plants => Planting::copy_to_layer(layer.id, to_layer_id, conn)
+            .await?
+            .into_iter()
+            .map(|planting| EntityChange::Planting {
+                id: planting.id,
+                before: None,
+                after: Some(planting),
+            })
+            .collect(),
+        LayerType::Base => BaseLayerImages::copy_to_layer(layer.id, to_layer_id, conn)
+            .await?
+            .into_iter()
+            .map(|image| EntityChange::BaseLayerImage {
+                id: image.id,
+                before: None,
+                after: Some(image),
+            })
+            .collect(),
+        LayerType::Shade => ShadeArea::copy_to_layer(layer.id, to_layer_id, conn)
+            .await?
+            .into_iter()
+            .map(|area| EntityChange::ShadeArea {
+                id: area.id,
+                before: None,
+                after: Some(area),
+            })
+            .collect(),
+        LayerType::Soil => SoilZone::copy_to_layer(layer.id, to_layer_id, conn)
+            .await?
+            .into_iter()
+            .map(|zone| EntityChange::SoilZone {
+                id: zone.id,
+                before: None,
+                after: Some(zone),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    Ok(changes)
+}
+
+/// Get the actions that create the copied content in the frontend.
+fn create_actions(changes: &[EntityChange], user_id: Uuid, action_id: Uuid) -> Vec<Action> {
+    changes
+        .iter()
+        .filter_map(|change| match change {
+            EntityChange::Planting {
+                after: Some(planting),
+                ..
+            } => Some(Action::CreatePlanting(CreatePlantActionPayload::new(
+                *planting, user_id, action_id,
+            ))),
+            EntityChange::BaseLayerImage {
+                after: Some(image), ..
+            } => Some(Action::CreateBaseLayerImage(
+                CreateBaseLayerImageActionPayload::new(image.clone(), user_id, action_id),
+            )),
+            EntityChange::ShadeArea {
+                after: Some(area), ..
+            } => Some(Action::CreateShadeArea(CreateShadeAreaActionPayload::new(
+                area.clone(),
+                user_id,
+                action_id,
+            ))),
+            EntityChange::SoilZone {
+                after: Some(zone), ..
+            } => Some(Action::CreateSoilZone(CreateSoilZoneActionPayload::new(
+                zone.clone(),
+                user_id,
+                action_id,
+            ))),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Rename the layer or move it to another position in the list of layers of the map.
+/// Checks if the requesting user is allowed to edit the map.
+/// The changes are recorded in the history of the map.
+///
+/// Returns all changed layers, the updated layer first.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the name is empty, too long or already used by another layer of the map.
+pub async fn update(
+    id: i32,
+    map_id: i32,
+    dto: UpdateLayerDto,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<Vec<LayerDto>, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    check_layer_permissions(map_id, id, user_id, &mut conn).await?;
+    conn.transaction(|conn| {
+        async move {
+            let action_id = dto.action_id;
+            let mut changes = Vec::new();
+            if let Some(new_name) = dto.name {
+                changes.push(rename(id, map_id, &new_name, conn).await?);
+            }
+            if let Some(index) = dto.order_index {
+                changes.append(&mut reorder(id, map_id, index, conn).await?);
+            }
+
+            let mut updated = vec![Layer::find_by_id(id, conn).await?];
+            for (before, after) in changes {
+                map_history::record_layer(
+                    user_id,
+                    action_id,
+                    Some(before),
+                    Some(after.clone()),
+                    conn,
+                )
+                .await?;
+                if after.id != id {
+                    updated.push(after);
+                }
+            }
+            Ok(updated)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Rename the layer.
+/// Returns the layer before and after the change.
+async fn rename(
+    id: i32,
+    map_id: i32,
+    new_name: &str,
+    conn: &mut AsyncPgConnection,
+) -> Result<(LayerDto, LayerDto), ServiceError> {
+    let new_name = validate_name(map_id, new_name, Some(id), conn).await?;
+
+    let before = Layer::find_by_id(id, conn).await?;
+    let after = Layer::rename(id, new_name, conn).await?;
+    Ok((before, after))
+}
+
+/// Check that the name is between 1 and [`MAX_LAYER_NAME_LENGTH`] characters long
+/// and not used by another layer of the map, including layers marked for deletion.
+/// `id` is the layer that gets the name, if it already exists.
+///
+/// Returns the name without surrounding whitespace.
+async fn validate_name(
+    map_id: i32,
+    name: &str,
+    id: Option<i32>,
+    conn: &mut AsyncPgConnection,
+) -> Result<String, ServiceError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_LAYER_NAME_LENGTH {
+        return Err(ServiceError::new(
+            StatusCode::BAD_REQUEST,
+            format!("The name of a layer has to be between 1 and {MAX_LAYER_NAME_LENGTH} characters long"),
+        ));
+    }
+    if Layer::name_exists(map_id, name, id, conn).await? {
+        return Err(ServiceError::new(
+            StatusCode::CONFLICT,
+            "Another layer of the map already has this name".to_owned(),
+        ));
+    }
+    Ok(name.to_owned())
+}
+
+/// Move the layer to the position `index` and close the gaps between the layers of the map.
+/// Returns the layers whose position changed before and after the change.
+async fn reorder(
+    id: i32,
+    map_id: i32,
+    index: i32,
+    conn: &mut AsyncPgConnection,
+) -> Result<Vec<(LayerDto, LayerDto)>, ServiceError> {
+    let mut layers = Layer::find(map_layers(map_id), conn).await?;
+    let Some(position) = layers.iter().position(|layer| layer.id == id) else {
+        return Ok(Vec::new());
+    };
+    let moved = layers.remove(position);
+    let index = usize::try_from(index).unwrap_or(0).min(layers.len());
+    layers.insert(index, moved);
+
+    let mut changes = Vec::new();
+    for (new_index, layer) in (0..).zip(layers) {
+        if layer.order_index != new_index {
+            let after = Layer::set_order_index(layer.id, new_index, conn).await?;
+            changes.push((layer, after));
+        }
+    }
+    Ok(changes)
+}
+
+/// The search parameters matching all layers of the map.
+const fn map_layers(map_id: i32) -> LayerSearchParameters {
+    LayerSearchParameters {
+        map_id: Some(map_id),
+        type_: None,
+        is_alternative: None,
+    }
+}
+
+/// Get the preferences of the requesting user for the layers of the map.
+/// Layers the user has no preferences for are not returned.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the map does not exist or is not visible to the requesting user.
+pub async fn find_preferences(
+    map_id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<Vec<LayerPreferencesDto>, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    Map::find_visible_by_id(map_id, user_id, &mut conn).await?;
+    let result = LayerPreferences::find(user_id, map_id, &mut conn).await?;
     Ok(result)
 }
 
-/// Delete the layer in the database.
+/// Set the preferences of the requesting user for the layer.
+/// Selecting a layer deselects the other layers of its type for the user.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
-pub async fn delete_by_id(id: i32, app_data: &Data<AppDataInner>) -> Result<(), ServiceError> {
+/// If the map does not exist or is not visible to the requesting user.
+/// If the layer is not part of the map.
+/// If the opacity is not between 0 and 1.
+pub async fn update_preferences(
+    id: i32,
+    map_id: i32,
+    dto: UpdateLayerPreferencesDto,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<LayerPreferencesDto, ServiceError> {
+    if !(0.0..=1.0).contains(&dto.opacity) {
+        return Err(ServiceError::new(
+            StatusCode::BAD_REQUEST,
+            "The opacity has to be between 0 and 1".to_owned(),
+        ));
+    }
     let mut conn = app_data.pool.get().await?;
-    let _ = Layer::delete_by_id(id, &mut conn).await?;
-    Ok(())
+    Map::find_visible_by_id(map_id, user_id, &mut conn).await?;
+    let layer = Layer::find_by_id(id, &mut conn).await?;
+    if layer.map_id != map_id {
+        return Err(ServiceError::new(
+            StatusCode::FORBIDDEN,
+            "Layer is not part of this map".to_owned(),
+        ));
+    }
+
+    conn.transaction(|conn| {
+        async move {
+            if dto.is_selected {
+                let _ = LayerPreferences::deselect_others(user_id, map_id, layer.type_, id, conn)
+                    .await?;
+            }
+            let preferences = LayerPreferences {
+                user_id,
+                layer_id: id,
+                is_visible: dto.is_visible,
+                opacity: dto.opacity,
+                is_selected: dto.is_selected,
+            };
+            Ok(LayerPreferences::upsert(preferences, conn).await?)
+        }
+        .scope_boxed()
+    })
+    .await
 }
@@ -2,13 +2,24 @@
 
 use actix_http::StatusCode;
 use actix_web::web::Data;
+use chrono::{Days, NaiveDateTime, Utc};
+use diesel::QueryResult;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::AsyncPgConnection;
 use uuid::Uuid;
 
 use crate::config::data::AppDataInner;
-use crate::model::dto::{BaseLayerImageDto, MapSearchParameters, Page, UpdateMapDto};
-use crate::model::dto::{NewLayerDto, PageParameters};
-use crate::model::entity::{BaseLayerImages, Layer};
+use crate::db::cronjobs::DELETION_RETENTION_DAYS;
+use crate::model::dto::plantings::PlantingDto;
+use crate::model::dto::{LayerDto, PageParameters};
+use crate::model::dto::{MapChangesDto, MapSearchParameters, Page, UpdateMapDto, UpdatedMapDto};
+use crate::model::entity::plantings::Planting;
+use crate::model::entity::shade_areas::ShadeArea;
+use crate::model::entity::soil_zones::SoilZone;
+use crate::model::entity::{BaseLayerImages, Layer, NewLayer, Tombstone};
 use crate::model::r#enum::layer_type::LayerType;
+use crate::service::map_access_control::check_owner_permissions;
+use crate::service::util::validate_geometry;
 use crate::{
     error::ServiceError,
     model::{
@@ -18,29 +29,36 @@ use crate::{
 };
 
 /// Defines which layers should be created when a new map is created.
-const LAYER_TYPES: [LayerType; 2] = [LayerType::Base, LayerType::Plants];
+/// Every map needs a main layer of each of these types, so the last one can't be deleted.
+pub const LAYER_TYPES: [LayerType; 2] = [LayerType::Base, LayerType::Plants];
 
-/// Search maps from the database.
+/// Search maps visible to the requesting user from the database.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
 pub async fn find(
     search_parameters: MapSearchParameters,
     page_parameters: PageParameters,
+    user_id: Uuid,
     app_data: &Data<AppDataInner>,
 ) -> Result<Page<MapDto>, ServiceError> {
     let mut conn = app_data.pool.get().await?;
-    let result = Map::find(search_parameters, page_parameters, &mut conn).await?;
+    let result = Map::find(search_parameters, page_parameters, user_id, &mut conn).await?;
     Ok(result)
 }
 
-/// Find a map by id in the database.
+/// Find a map visible to the requesting user by id in the database.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
-pub async fn find_by_id(id: i32, app_data: &Data<AppDataInner>) -> Result<MapDto, ServiceError> {
+/// If the map does not exist or is not visible to the requesting user.
+pub async fn find_by_id(
+    id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<MapDto, ServiceError> {
     let mut conn = app_data.pool.get().await?;
-    let result = Map::find_by_id(id, &mut conn).await?;
+    let result = Map::find_visible_by_id(id, user_id, &mut conn).await?;
     Ok(result)
 }
 
@@ -56,7 +74,7 @@ pub async fn create(
     let mut conn = app_data.pool.get().await?;
     let result = Map::create(new_map, user_id, &mut conn).await?;
     for layer_type in &LAYER_TYPES {
-        let new_layer = NewLayerDto {
+        let new_layer = NewLayer {
             map_id: result.id,
             type_: *layer_type,
             name: format!("{layer_type} Layer"),
@@ -69,13 +87,13 @@ pub async fn create(
         // anyway.
         if layer.type_ == LayerType::Base {
             BaseLayerImages::create(
-                BaseLayerImageDto {
+                BaseLayerImages {
                     id: Uuid::new_v4(),
                     layer_id: layer.id,
                     path: String::new(),
                     rotation: 0.0,
                     scale: 100.0,
-                    action_id: Uuid::nil(),
+                    updated_at: Utc::now().naive_utc(),
                 },
                 &mut conn,
             )
@@ -87,25 +105,174 @@ pub async fn create(
 }
 
 /// Update a map in the database.
-/// Checks if the map is owned by the requesting user.
+/// Checks if the requesting user is an owner of the map.
+///
+/// If the geometry is changed, all plantings outside of the new geometry are reported.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
-/// If the requesting user is not the owner of the map.
+/// If the requesting user is not an owner of the map.
+/// If the new geometry is not a valid polygon.
 pub async fn update(
     map_update: UpdateMapDto,
     id: i32,
     user_id: Uuid,
     app_data: &Data<AppDataInner>,
+) -> Result<UpdatedMapDto, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    check_owner_permissions(id, user_id, &mut conn).await?;
+    if let Some(geometry) = &map_update.geometry {
+        validate_geometry(geometry)?;
+    }
+    let geometry_changed = map_update.geometry.is_some();
+
+    let map = Map::update(map_update, id, &mut conn).await?;
+    let plantings_outside_geometry = if geometry_changed {
+        Planting::find_outside_map_geometry(id, &mut conn).await?
+    } else {
+        Vec::new()
+    };
+
+    Ok(UpdatedMapDto {
+        map,
+        plantings_outside_geometry,
+    })
+}
+
+/// Mark a map for deletion.
+/// The map can be restored within [`DELETION_RETENTION_DAYS`] days.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not an owner of the map.
+/// If the map already is marked for deletion.
+pub async fn delete_by_id(
+    id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
 ) -> Result<MapDto, ServiceError> {
     let mut conn = app_data.pool.get().await?;
+    check_owner_permissions(id, user_id, &mut conn).await?;
     let map = Map::find_by_id(id, &mut conn).await?;
-    if map.owner_id != user_id {
-        return Err(ServiceError {
-            status_code: StatusCode::FORBIDDEN,
-            reason: "No permission to update data".to_owned(),
-        });
+    if map.deletion_date.is_some() {
+        return Err(ServiceError::new(
+            StatusCode::CONFLICT,
+            "Map is already deleted".to_owned(),
+        ));
     }
-    let result = Map::update(map_update, id, &mut conn).await?;
+    let result = Map::mark_for_deletion(id, &mut conn).await?;
     Ok(result)
 }
+
+/// Restore a map that was marked for deletion.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not an owner of the map.
+/// If the map is not marked for deletion.
+/// If the map was deleted more than [`DELETION_RETENTION_DAYS`] days ago.
+pub async fn restore(
+    id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<MapDto, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    check_owner_permissions(id, user_id, &mut conn).await?;
+    let map = Map::find_by_id(id, &mut conn).await?;
+    let Some(deletion_date) = map.deletion_date else {
+        return Err(ServiceError::new(
+            StatusCode::CONFLICT,
+            "Map is not deleted".to_owned(),
+        ));
+    };
+    let oldest_restorable = Utc::now()
+        .date_naive()
+        .checked_sub_days(Days::new(DELETION_RETENTION_DAYS.into()));
+    if oldest_restorable.is_some_and(|date| deletion_date < date) {
+        return Err(ServiceError::new(
+            StatusCode::GONE,
+            "Map can no longer be restored".to_owned(),
+        ));
+    }
+    let result = Map::restore(id, &mut conn).await?;
+    Ok(result)
+}
+
+/// Get the changes of the content of a map visible to the requesting user since `since`.
+/// All changes are read from the same snapshot of the database.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the map does not exist or is not visible to the requesting user.
+/// If `since` is more than [`DELETION_RETENTION_DAYS`] days ago,
+/// as deletions are not tracked for longer.
+pub async fn find_changes(
+    map_id: i32,
+    user_id: Uuid,
+    since: NaiveDateTime,
+    app_data: &Data<AppDataInner>,
+) -> Result<MapChangesDto, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    Map::find_visible_by_id(map_id, user_id, &mut conn).await?;
+
+    // Determined before the snapshot is taken, see `Tombstone::watermark`.
+    let timestamp = Tombstone::watermark(&mut conn).await?;
+    let oldest_trackable = timestamp.checked_sub_days(Days::new(DELETION_RETENTION_DAYS.into()));
+    if oldest_trackable.is_some_and(|oldest| since < oldest) {
+        return Err(ServiceError::new(
+            StatusCode::GONE,
+            "Changes are no longer available, reload the map".to_owned(),
+        ));
+    }
+
+    let changes = conn
+        .build_transaction()
+        .repeatable_read()
+        .read_only()
+        .run(|conn| read_changes(map_id, since, timestamp, conn).scope_boxed())
+        .await?;
+    Ok(changes)
+}
+
+/// Read the changes of the content of the map since `since`.
+///
+/// # Errors
+/// * Unknown, diesel doesn't say why it might error.
+async fn read_changes(
+    map_id: i32,
+    since: NaiveDateTime,
+    timestamp: NaiveDateTime,
+    conn: &mut AsyncPgConnection,
+) -> QueryResult<MapChangesDto> {
+    let (deleted, changed): (Vec<_>, Vec<_>) = Planting::find_changed_since(map_id, since, conn)
+        .await?
+        .into_iter()
+        .partition(|planting| planting.delete_date.is_some());
+    let (deleted_layers, changed_layers): (Vec<_>, Vec<_>) =
+        Layer::find_changed_since(map_id, since, conn)
+            .await?
+            .into_iter()
+            .partition(|layer| layer.deletion_date.is_some());
+    let tombstones = Tombstone::find_since(map_id, since, conn).await?;
+
+    Ok(MapChangesDto {
+        timestamp,
+        plantings: changed.into_iter().map(PlantingDto::from).collect(),
+        deleted_plantings: deleted.into_iter().map(|planting| planting.id).collect(),
+        layers: changed_layers.into_iter().map(LayerDto::from).collect(),
+        deleted_layers: deleted_layers
+            .iter()
+            .map(|layer| layer.id)
+            .chain(tombstones.iter().filter_map(|t| t.layer_id))
+            .collect(),
+        base_layer_images: BaseLayerImages::find_changed_since(map_id, since, conn).await?,
+        deleted_base_layer_images: tombstones
+            .iter()
+            .filter_map(|t| t.base_layer_image_id)
+            .collect(),
+        shade_areas: ShadeArea::find_changed_since(map_id, since, conn).await?,
+        deleted_shade_areas: tombstones.iter().filter_map(|t| t.shade_area_id).collect(),
+        soil_zones: SoilZone::find_changed_since(map_id, since, conn).await?,
+        deleted_soil_zones: tombstones.iter().filter_map(|t| t.soil_zone_id).collect(),
+    })
+}
@@ -0,0 +1,281 @@
+//! Access control for maps and the data placed on them.
+//!
+//! Every endpoint that modifies a map or its content has to go through one of these checks.
+//! The checks resolve the given resource to the map it is placed on and make sure
+//! the requesting user is allowed to edit that map.
+//! Endpoints that only read the content of a map check that the map is visible to the user instead.
+//!
+//! The creator of a map always has the [`CollaboratorRole::Owner`] role.
+//! Other users get their role by being invited as a collaborator.
+
+use actix_http::StatusCode;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+use crate::{
+    error::ServiceError,
+    model::{
+        entity::{
+            plantings::Planting, shade_areas::ShadeArea, soil_zones::SoilZone, BaseLayerImages,
+            Layer, Map, MapCollaborator,
+        },
+        r#enum::collaborator_role::CollaboratorRole,
+    },
+};
+
+/// Find the role of the user on the map.
+/// Returns `None` if the user neither created the map nor collaborates on it.
+///
+/// # Errors
+/// * If no map with id `map_id` exists.
+pub async fn find_role(
+    map_id: i32,
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<Option<CollaboratorRole>, ServiceError> {
+    let map = Map::find_by_id(map_id, conn).await?;
+    if map.owner_id == user_id {
+        return Ok(Some(CollaboratorRole::Owner));
+    }
+
+    Ok(MapCollaborator::find_role(map_id, user_id, conn).await?)
+}
+
+/// Check if the user is allowed to edit the map.
+///
+/// # Errors
+/// * If no map with id `map_id` exists.
+/// * If the user is not allowed to edit the map.
+pub async fn check_permissions(
+    map_id: i32,
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    match find_role(map_id, user_id, conn).await? {
+        Some(CollaboratorRole::Owner | CollaboratorRole::Editor) => Ok(()),
+        _ => Err(ServiceError::new(
+            StatusCode::FORBIDDEN,
+            "No permission to edit this map".to_owned(),
+        )),
+    }
+}
+
+/// Check if the user is allowed to manage the map, i.e. change its settings and collaborators.
+///
+/// # Errors
+/// * If no map with id `map_id` exists.
+/// * If the user is not an owner of the map.
+pub async fn check_owner_permissions(
+    map_id: i32,
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    match find_role(map_id, user_id, conn).await? {
+        Some(CollaboratorRole::Owner) => Ok(()),
+        _ => Err(ServiceError::new(
+            StatusCode::FORBIDDEN,
+            "No permission to manage this map".to_owned(),
+        )),
+    }
+}
+
+/// Check if the user is a member of the map, i.e. its creator or one of its collaborators.
+///
+/// # Errors
+/// * If no map with id `map_id` exists.
+/// * If the user is not a member of the map.
+pub async fn check_member_permissions(
+    map_id: i32,
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    match find_role(map_id, user_id, conn).await? {
+        Some(_) => Ok(()),
+        None => Err(ServiceError::new(
+            StatusCode::FORBIDDEN,
+            "Not a member of this map".to_owned(),
+        )),
+    }
+}
+
+/// Check if the user is allowed to see the map and its content.
+/// Maps the user is not allowed to see are reported as not found.
+///
+/// # Errors
+/// * If no map with id `map_id` exists or the user is not allowed to see it.
+pub async fn check_visibility(
+    map_id: i32,
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    Map::find_visible_by_id(map_id, user_id, conn).await?;
+    Ok(())
+}
+
+/// Check if the user is allowed to see the layer and its content.
+///
+/// # Errors
+/// * If no layer with id `layer_id` exists.
+/// * If the layer is not part of the map with id `map_id`.
+/// * If the user is not allowed to see the map.
+pub async fn check_layer_visibility(
+    map_id: i32,
+    layer_id: i32,
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    check_layer_on_map(map_id, layer_id, conn).await?;
+    check_visibility(map_id, user_id, conn).await
+}
+
+/// Check if the user is allowed to edit the layer.
+///
+/// # Errors
+/// * If no layer with id `layer_id` exists.
+/// * If the layer is not part of the map with id `map_id`.
+/// * If the user is not allowed to edit the map.
+pub async fn check_layer_permissions(
+    map_id: i32,
+    layer_id: i32,
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    check_layer_on_map(map_id, layer_id, conn).await?;
+    check_permissions(map_id, user_id, conn).await
+}
+
+/// Check that the layer is part of the map.
+///
+/// # Errors
+/// * If no layer with id `layer_id` exists.
+/// * If the layer is not part of the map with id `map_id`.
+async fn check_layer_on_map(
+    map_id: i32,
+    layer_id: i32,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    let layer = Layer::find_by_id(layer_id, conn).await?;
+    if layer.map_id != map_id {
+        return Err(ServiceError::new(
+            StatusCode::FORBIDDEN,
+            "Layer is not part of this map".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// Check if the user is allowed to edit all of the layers.
+/// The permissions on the map are only checked once.
+///
+/// # Errors
+/// * If any of the layers doesn't exist or is not part of the map with id `map_id`.
+/// * If the user is not allowed to edit the map.
+pub async fn check_layers_permissions(
+    map_id: i32,
+    layer_ids: &[i32],
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    let mut layer_ids = layer_ids.to_vec();
+    layer_ids.sort_unstable();
+    layer_ids.dedup();
+    let on_map = Layer::count_on_map(&layer_ids, map_id, conn).await?;
+    if usize::try_from(on_map) != Ok(layer_ids.len()) {
+        return Err(ServiceError::new(
+            StatusCode::FORBIDDEN,
+            "Not all layers are part of this map".to_owned(),
+        ));
+    }
+
+    check_permissions(map_id, user_id, conn).await
+}
+
+/// Check if the user is allowed to edit all of the plantings.
+/// The permissions on the map are only checked once.
+///
+/// # Errors
+/// * If any of the plantings doesn't exist or is not placed on the map with id `map_id`.
+/// * If the user is not allowed to edit the map.
+pub async fn check_plantings_permissions(
+    map_id: i32,
+    planting_ids: &[Uuid],
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    let mut planting_ids = planting_ids.to_vec();
+    planting_ids.sort_unstable();
+    planting_ids.dedup();
+    let on_map = Planting::count_on_map(&planting_ids, map_id, conn).await?;
+    if usize::try_from(on_map) != Ok(planting_ids.len()) {
+        return Err(ServiceError::new(
+            StatusCode::FORBIDDEN,
+            "Not all plantings are placed on this map".to_owned(),
+        ));
+    }
+
+    check_permissions(map_id, user_id, conn).await
+}
+
+/// Check if the user is allowed to edit the planting.
+///
+/// # Errors
+/// * If no planting with id `planting_id` exists.
+/// * If the planting is not placed on the map with id `map_id`.
+/// * If the user is not allowed to edit the map.
+pub async fn check_planting_permissions(
+    map_id: i32,
+    planting_id: Uuid,
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    let planting = Planting::find_by_id(planting_id, conn).await?;
+    check_layer_permissions(map_id, planting.layer_id, user_id, conn).await
+}
+
+/// Check if the user is allowed to edit the base layer image.
+///
+/// # Errors
+/// * If no base layer image with id `image_id` exists.
+/// * If the image is not placed on the map with id `map_id`.
+/// * If the user is not allowed to edit the map.
+pub async fn check_base_layer_image_permissions(
+    map_id: i32,
+    image_id: Uuid,
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    let image = BaseLayerImages::find_by_id(image_id, conn).await?;
+    check_layer_permissions(map_id, image.layer_id, user_id, conn).await
+}
+
+/// Check if the user is allowed to edit the shade area.
+///
+/// # Errors
+/// * If no shade area with id `shade_area_id` exists.
+/// * If the area is not placed on the map with id `map_id`.
+/// * If the user is not allowed to edit the map.
+pub async fn check_shade_area_permissions(
+    map_id: i32,
+    shade_area_id: Uuid,
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    let area = ShadeArea::find_by_id(shade_area_id, conn).await?;
+    check_layer_permissions(map_id, area.layer_id, user_id, conn).await
+}
+
+/// Check if the user is allowed to edit the soil zone.
+///
+/// # Errors
+/// * If no soil zone with id `soil_zone_id` exists.
+/// * If the zone is not placed on the map with id `map_id`.
+/// * If the user is not allowed to edit the map.
+pub async fn check_soil_zone_permissions(
+    map_id: i32,
+    soil_zone_id: Uuid,
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    let zone = SoilZone::find_by_id(soil_zone_id, conn).await?;
+    check_layer_permissions(map_id, zone.layer_id, user_id, conn).await
+}
@@ -0,0 +1,348 @@
+//! Service layer for the action log used to undo and redo actions on a map.
+//!
+//! Every action that changes the content of a map is recorded together with the state
+//! of the changed entities before and after the action.
+//! Undoing an action restores the state before it, redoing restores the state after it.
+//! Each user can only undo and redo their own actions.
+
+use actix_http::StatusCode;
+use actix_web::web::Data;
+use diesel::OptionalExtension;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection};
+use uuid::Uuid;
+
+use crate::config::data::AppDataInner;
+use crate::error::ServiceError;
+use crate::model::dto::action_log::EntityChange;
+use crate::model::dto::actions::{
+    Action, BatchActionPayload, CreateBaseLayerImageActionPayload, CreatePlantActionPayload,
+    CreateShadeAreaActionPayload, CreateSoilZoneActionPayload, DeleteBaseLayerImageActionPayload,
+    DeletePlantActionPayload, DeleteShadeAreaActionPayload, DeleteSoilZoneActionPayload,
+    TransformPlantActionPayload, UpdateBaseLayerImageActionPayload,
+    UpdatePlantingAddDateActionPayload, UpdatePlantingRemoveDateActionPayload,
+    UpdateShadeAreaActionPayload, UpdateSoilZoneActionPayload,
+};
+use crate::model::dto::plantings::PlantingDto;
+use crate::model::entity::plantings::Planting;
+use crate::model::entity::shade_areas::ShadeArea;
+use crate::model::entity::soil_zones::SoilZone;
+use crate::model::entity::{BaseLayerImages, MapActionLogEntry, NewMapActionLogEntry};
+use crate::service::map_access_control::check_permissions;
+use crate::service::map_history;
+
+/// Number of actions per user and map that are kept for undoing.
+pub const ACTION_LOG_SIZE: i64 = 100;
+
+/// Record the changes caused by an action of the user.
+/// Actions undone by the user can no longer be redone afterwards.
+/// The changes are added to the history of the map as well.
+///
+/// Should be called in the same transaction as the changes.
+///
+/// # Errors
+/// If the changes could not be serialized.
+/// If the action could not be saved.
+pub async fn record(
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    changes: Vec<EntityChange>,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    map_history::record(map_id, user_id, action_id, &changes, conn).await?;
+    let changes = serde_json::to_value(changes)
+        .map_err(|e| ServiceError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let _ = MapActionLogEntry::delete_undone(map_id, user_id, conn).await?;
+    let _ = MapActionLogEntry::create(
+        NewMapActionLogEntry {
+            map_id,
+            user_id,
+            action_id,
+            changes,
+        },
+        conn,
+    )
+    .await?;
+    let _ = MapActionLogEntry::truncate(map_id, user_id, ACTION_LOG_SIZE, conn).await?;
+    Ok(())
+}
+
+/// Undo the latest action of the user on the map.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// Returns the action that has to be broadcast to apply the undo in the frontend.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the user has no action that can be undone.
+/// If the changed entities were changed again in the meantime, in which case the action is discarded.
+pub async fn undo(
+    map_id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<Action, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    check_permissions(map_id, user_id, &mut conn).await?;
+
+    conn.transaction(|conn| {
+        async move {
+            let entry = MapActionLogEntry::find_last_done(map_id, user_id, conn)
+                .await?
+                .ok_or_else(|| {
+                    ServiceError::new(StatusCode::NOT_FOUND, "Nothing to undo".to_owned())
+                })?;
+            let changes = deserialize_changes(&entry)?
+                .into_iter()
+                .rev()
+                .map(EntityChange::inverse)
+                .collect();
+            replay(entry, changes, true, user_id, conn).await
+        }
+        .scope_boxed()
+    })
+    .await?
+}
+
+/// Redo the latest action of the user on the map that was undone.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// Returns the action that has to be broadcast to apply the redo in the frontend.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the user has no action that can be redone.
+/// If the changed entities were changed again in the meantime, in which case the action is discarded.
+pub async fn redo(
+    map_id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<Action, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    check_permissions(map_id, user_id, &mut conn).await?;
+
+    conn.transaction(|conn| {
+        async move {
+            let entry = MapActionLogEntry::find_first_undone(map_id, user_id, conn)
+                .await?
+                .ok_or_else(|| {
+                    ServiceError::new(StatusCode::NOT_FOUND, "Nothing to redo".to_owned())
+                })?;
+            let changes = deserialize_changes(&entry)?;
+            replay(entry, changes, false, user_id, conn).await
+        }
+        .scope_boxed()
+    })
+    .await?
+}
+
+/// Apply `changes` and mark the log entry accordingly.
+///
+/// The outer result is the result of the transaction.
+/// A conflict is returned in the inner result, so the discarded entry is still committed.
+async fn replay(
+    entry: MapActionLogEntry,
+    changes: Vec<EntityChange>,
+    undone: bool,
+    user_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<Result<Action, ServiceError>, ServiceError> {
+    for change in &changes {
+        if !is_current(change, conn).await? {
+            let _ = MapActionLogEntry::delete_by_id(entry.id, conn).await?;
+            return Ok(Err(ServiceError::new(
+                StatusCode::CONFLICT,
+                "The map was changed in the meantime, the action was discarded".to_owned(),
+            )));
+        }
+    }
+
+    let action_id = Uuid::new_v4();
+    map_history::record(entry.map_id, user_id, action_id, &changes, conn).await?;
+    let mut actions = Vec::new();
+    for change in changes {
+        actions.append(&mut apply(change, user_id, action_id, conn).await?);
+    }
+    let _ = MapActionLogEntry::set_undone(entry.id, undone, conn).await?;
+
+    Ok(Ok(Action::Batch(BatchActionPayload::new(
+        actions, user_id, action_id,
+    ))))
+}
+
+/// Deserialize the changes saved in the log entry.
+fn deserialize_changes(entry: &MapActionLogEntry) -> Result<Vec<EntityChange>, ServiceError> {
+    serde_json::from_value(entry.changes.clone())
+        .map_err(|e| ServiceError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Check if the entity is still in the state before the change.
+///
+/// The version of plantings is not compared,
+/// as undoing and redoing increments it without changing the planting.
+async fn is_current(
+    change: &EntityChange,
+    conn: &mut AsyncPgConnection,
+) -> Result<bool, ServiceError> {
+    Ok(match change {
+        EntityChange::Planting { id, before, .. } => {
+            let current = Planting::find_existing_by_id(*id, conn).await?;
+            current.map(without_version) == before.map(without_version)
+        }
+        EntityChange::BaseLayerImage { id, before, .. } => {
+            BaseLayerImages::find_by_id(*id, conn).await.optional()? == *before
+        }
+        EntityChange::ShadeArea { id, before, .. } => {
+            ShadeArea::find_by_id(*id, conn).await.optional()? == *before
+        }
+        EntityChange::SoilZone { id, before, .. } => {
+            SoilZone::find_by_id(*id, conn).await.optional()? == *before
+        }
+    })
+}
+
+/// Reset the version of the planting, so only its content is compared.
+const fn without_version(planting: PlantingDto) -> PlantingDto {
+    PlantingDto {
+        version: 0,
+        ..planting
+    }
+}
+
+/// Bring the entity into the state after the change.
+/// Returns the actions necessary to apply the change in the frontend.
+async fn apply(
+    change: EntityChange,
+    user_id: Uuid,
+    action_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<Vec<Action>, ServiceError> {
+    Ok(match change {
+        EntityChange::Planting { id, before, after } => match (before, after) {
+            (None, None) => vec![],
+            (Some(before), None) => {
+                let _ = Planting::delete_by_id(id, conn).await?;
+                vec![Action::DeletePlanting(DeletePlantActionPayload::new(
+                    id,
+                    before.layer_id,
+                    user_id,
+                    action_id,
+                ))]
+            }
+            (None, Some(after)) => {
+                let planting = Planting::replace(after, conn).await?;
+                vec![Action::CreatePlanting(CreatePlantActionPayload::new(
+                    planting, user_id, action_id,
+                ))]
+            }
+            (Some(before), Some(after)) => {
+                let planting = Planting::replace(after, conn).await?;
+                planting_update_actions(&before, planting, user_id, action_id)
+            }
+        },
+        EntityChange::BaseLayerImage { id, before, after } => match (before, after) {
+            (None, None) => vec![],
+            (Some(before), None) => {
+                let _ = BaseLayerImages::delete_by_id(id, conn).await?;
+                vec![Action::DeleteBaseLayerImage(
+                    DeleteBaseLayerImageActionPayload::new(id, before.layer_id, user_id, action_id),
+                )]
+            }
+            (None, Some(after)) => {
+                let image = BaseLayerImages::replace(after, conn).await?;
+                vec![Action::CreateBaseLayerImage(
+                    CreateBaseLayerImageActionPayload::new(image, user_id, action_id),
+                )]
+            }
+            (Some(_), Some(after)) => {
+                let image = BaseLayerImages::replace(after, conn).await?;
+                vec![Action::UpdateBaseLayerImage(
+                    UpdateBaseLayerImageActionPayload::new(image, user_id, action_id),
+                )]
+            }
+        },
+        EntityChange::ShadeArea { id, before, after } => match (before, after) {
+            (None, None) => vec![],
+            (Some(before), None) => {
+                let _ = ShadeArea::delete_by_id(id, conn).await?;
+                vec![Action::DeleteShadeArea(DeleteShadeAreaActionPayload::new(
+                    id,
+                    before.layer_id,
+                    user_id,
+                    action_id,
+                ))]
+            }
+            (None, Some(after)) => {
+                let area = ShadeArea::replace(after, conn).await?;
+                vec![Action::CreateShadeArea(CreateShadeAreaActionPayload::new(
+                    area, user_id, action_id,
+                ))]
+            }
+            (Some(_), Some(after)) => {
+                let area = ShadeArea::replace(after, conn).await?;
+                vec![Action::UpdateShadeArea(UpdateShadeAreaActionPayload::new(
+                    area, user_id, action_id,
+                ))]
+            }
+        },
+        EntityChange::SoilZone { id, before, after } => match (before, after) {
+            (None, None) => vec![],
+            (Some(before), None) => {
+                let _ = SoilZone::delete_by_id(id, conn).await?;
+                vec![Action::DeleteSoilZone(DeleteSoilZoneActionPayload::new(
+                    id,
+                    before.layer_id,
+                    user_id,
+                    action_id,
+                ))]
+            }
+            (None, Some(after)) => {
+                let zone = SoilZone::replace(after, conn).await?;
+                vec![Action::CreateSoilZone(CreateSoilZoneActionPayload::new(
+                    zone, user_id, action_id,
+                ))]
+            }
+            (Some(_), Some(after)) => {
+                let zone = SoilZone::replace(after, conn).await?;
+                vec![Action::UpdateSoilZone(UpdateSoilZoneActionPayload::new(
+                    zone, user_id, action_id,
+                ))]
+            }
+        },
+    })
+}
+
+/// Get the actions that update the planting in the frontend from `before` to `after`.
+fn planting_update_actions(
+    before: &PlantingDto,
+    after: PlantingDto,
+    user_id: Uuid,
+    action_id: Uuid,
+) -> Vec<Action> {
+    let mut actions = Vec::new();
+    #[allow(clippy::float_cmp)] // The values are copied, not calculated.
+    if before.x != after.x
+        || before.y != after.y
+        || before.rotation != after.rotation
+        || before.scale_x != after.scale_x
+        || before.scale_y != after.scale_y
+    {
+        actions.push(Action::TransformPlanting(TransformPlantActionPayload::new(
+            after, user_id, action_id,
+        )));
+    }
+    if before.add_date != after.add_date {
+        actions.push(Action::UpdatePlantingAddDate(
+            UpdatePlantingAddDateActionPayload::new(after, user_id, action_id),
+        ));
+    }
+    if before.remove_date != after.remove_date {
+        actions.push(Action::UpdatePlantingRemoveDate(
+            UpdatePlantingRemoveDateActionPayload::new(after, user_id, action_id),
+        ));
+    }
+    actions
+}
@@ -0,0 +1,106 @@
+//! Service layer for map collaborators.
+
+use actix_http::StatusCode;
+use actix_web::web::Data;
+use uuid::Uuid;
+
+use crate::config::data::AppDataInner;
+use crate::error::ServiceError;
+use crate::model::dto::{MapCollaboratorDto, NewMapCollaboratorDto, UpdateMapCollaboratorDto};
+use crate::model::entity::MapCollaborator;
+use crate::service::map_access_control::{
+    check_member_permissions, check_owner_permissions, find_role,
+};
+
+/// Get all collaborators of a map.
+/// Checks if the requesting user is a member of the map.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not a member of the map.
+pub async fn find(
+    map_id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<Vec<MapCollaboratorDto>, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    check_member_permissions(map_id, user_id, &mut conn).await?;
+    let result = MapCollaborator::find_by_map_id(map_id, &mut conn).await?;
+    Ok(result)
+}
+
+/// Invite a user to collaborate on a map.
+/// Checks if the requesting user is an owner of the map.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not an owner of the map.
+/// If the invited user already is a member of the map.
+pub async fn create(
+    new_collaborator: NewMapCollaboratorDto,
+    map_id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<MapCollaboratorDto, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    check_owner_permissions(map_id, user_id, &mut conn).await?;
+    if find_role(map_id, new_collaborator.user_id, &mut conn)
+        .await?
+        .is_some()
+    {
+        return Err(ServiceError::new(
+            StatusCode::CONFLICT,
+            "User already is a member of this map".to_owned(),
+        ));
+    }
+    let result = MapCollaborator::create(new_collaborator, map_id, &mut conn).await?;
+    Ok(result)
+}
+
+/// Change the role of a collaborator.
+/// Checks if the requesting user is an owner of the map.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not an owner of the map.
+/// If the user is not a collaborator of the map.
+pub async fn update(
+    update: UpdateMapCollaboratorDto,
+    map_id: i32,
+    collaborator_id: Uuid,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<MapCollaboratorDto, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    check_owner_permissions(map_id, user_id, &mut conn).await?;
+    let result =
+        MapCollaborator::update_role(map_id, collaborator_id, update.role, &mut conn).await?;
+    Ok(result)
+}
+
+/// Remove a collaborator from a map.
+/// Owners can remove any collaborator, all other collaborators can only remove themselves.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to remove the collaborator.
+/// If the user is not a collaborator of the map.
+pub async fn delete(
+    map_id: i32,
+    collaborator_id: Uuid,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<(), ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    if collaborator_id != user_id {
+        check_owner_permissions(map_id, user_id, &mut conn).await?;
+    }
+    let deleted = MapCollaborator::delete(map_id, collaborator_id, &mut conn).await?;
+    if deleted == 0 {
+        return Err(ServiceError::new(
+            StatusCode::NOT_FOUND,
+            "User is not a collaborator of this map".to_owned(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! Service layer for the history of the changes made to a map.
+//!
+//! Every change of a planting, layer or base layer image is recorded together with
+//! the user who made it and the state of the entity before and after the change.
+//! Unlike the action log used for undo and redo, the history is never truncated.
+
+use actix_http::StatusCode;
+use actix_web::web::Data;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+use crate::config::data::AppDataInner;
+use crate::error::ServiceError;
+use crate::model::dto::action_log::EntityChange;
+use crate::model::dto::history::{
+    HistoryEntityDto, MapHistoryEntryDto, MapHistorySearchParameters,
+};
+use crate::model::dto::{LayerDto, Page, PageParameters};
+use crate::model::entity::{MapHistoryEntry, NewMapHistoryEntry};
+use crate::model::r#enum::history_entity_type::HistoryEntityType;
+use crate::service::map_access_control::check_owner_permissions;
+
+/// Search the history of a map, the latest changes first.
+/// Checks if the requesting user is an owner of the map.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not an owner of the map.
+pub async fn find(
+    map_id: i32,
+    user_id: Uuid,
+    search_parameters: MapHistorySearchParameters,
+    page_parameters: PageParameters,
+    app_data: &Data<AppDataInner>,
+) -> Result<Page<MapHistoryEntryDto>, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    check_owner_permissions(map_id, user_id, &mut conn).await?;
+    let page = MapHistoryEntry::find(map_id, search_parameters, page_parameters, &mut conn).await?;
+    let results = page
+        .results
+        .into_iter()
+        .map(MapHistoryEntryDto::try_from)
+        .collect::<Result<_, _>>()
+        .map_err(internal_error)?;
+    Ok(Page {
+        results,
+        page: page.page,
+        per_page: page.per_page,
+        total_pages: page.total_pages,
+    })
+}
+
+/// Record the changes of plantings, base layer images, shade areas and soil zones caused by an action of the user.
+///
+/// Should be called in the same transaction as the changes.
+///
+/// # Errors
+/// If the changes could not be serialized.
+/// If the changes could not be saved.
+pub async fn record(
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    changes: &[EntityChange],
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    let mut entries = Vec::with_capacity(changes.len());
+    for change in changes {
+        let entry = match change {
+            EntityChange::Planting { id, before, after } => {
+                let Some(layer_id) = before.or(*after).map(|planting| planting.layer_id) else {
+                    continue;
+                };
+                new_entry(
+                    (map_id, user_id, action_id),
+                    HistoryEntityType::Planting,
+                    layer_id,
+                    Some(*id),
+                    before.map(HistoryEntityDto::Planting),
+                    after.map(HistoryEntityDto::Planting),
+                )?
+            }
+            EntityChange::BaseLayerImage { id, before, after } => {
+                let Some(layer_id) = before
+                    .as_ref()
+                    .or(after.as_ref())
+                    .map(|image| image.layer_id)
+                else {
+                    continue;
+                };
+                new_entry(
+                    (map_id, user_id, action_id),
+                    HistoryEntityType::BaseLayerImage,
+                    layer_id,
+                    Some(*id),
+                    before.clone().map(HistoryEntityDto::BaseLayerImage),
+                    after.clone().map(HistoryEntityDto::BaseLayerImage),
+                )?
+            }
+            EntityChange::ShadeArea { id, before, after } => {
+                let Some(layer_id) = before.as_ref().or(after.as_ref()).map(|area| area.layer_id)
+                else {
+                    continue;
+                };
+                new_entry(
+                    (map_id, user_id, action_id),
+                    HistoryEntityType::ShadeArea,
+                    layer_id,
+                    Some(*id),
+                    before.clone().map(HistoryEntityDto::ShadeArea),
+                    after.clone().map(HistoryEntityDto::ShadeArea),
+                )?
+            }
+            EntityChange::SoilZone { id, before, after } => {
+                let Some(layer_id) = before.as_ref().or(after.as_ref()).map(|zone| zone.layer_id)
+                else {
+                    continue;
+                };
+                new_entry(
+                    (map_id, user_id, action_id),
+                    HistoryEntityType::SoilZone,
+                    layer_id,
+                    Some(*id),
+                    before.clone().map(HistoryEntityDto::SoilZone),
+                    after.clone().map(HistoryEntityDto::SoilZone),
+                )?
+            }
+        };
+        entries.push(entry);
+    }
+
+    if !entries.is_empty() {
+        let _ = MapHistoryEntry::create_batch(entries, conn).await?;
+    }
+    Ok(())
+}
+
+/// Record the creation, change or deletion of a layer by the user.
+///
+/// # Errors
+/// If the layer could not be serialized.
+/// If the change could not be saved.
+pub async fn record_layer(
+    user_id: Uuid,
+    action_id: Uuid,
+    before: Option<LayerDto>,
+    after: Option<LayerDto>,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    let Some((map_id, layer_id)) = before
+        .as_ref()
+        .or(after.as_ref())
+        .map(|layer| (layer.map_id, layer.id))
+    else {
+        return Ok(());
+    };
+    let entry = new_entry(
+        (map_id, user_id, action_id),
+        HistoryEntityType::Layer,
+        layer_id,
+        None,
+        before.map(HistoryEntityDto::Layer),
+        after.map(HistoryEntityDto::Layer),
+    )?;
+    let _ = MapHistoryEntry::create_batch(vec![entry], conn).await?;
+    Ok(())
+}
+
+/// Build a history entry for the change of an entity.
+/// `origin` contains the ids of the map, the user and the action.
+fn new_entry(
+    origin: (i32, Uuid, Uuid),
+    entity_type: HistoryEntityType,
+    layer_id: i32,
+    entity_id: Option<Uuid>,
+    before: Option<HistoryEntityDto>,
+    after: Option<HistoryEntityDto>,
+) -> Result<NewMapHistoryEntry, ServiceError> {
+    let (map_id, user_id, action_id) = origin;
+    Ok(NewMapHistoryEntry {
+        map_id,
+        user_id,
+        action_id,
+        entity_type,
+        layer_id,
+        entity_id,
+        before: before
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(internal_error)?,
+        after: after
+            .map(serde_json::to_value)
+            .transpose()
+            .map_err(internal_error)?,
+    })
+}
+
+/// Report a failed (de)serialization of a history entry.
+#[allow(clippy::needless_pass_by_value)] // Used in `map_err`.
+fn internal_error(e: serde_json::Error) -> ServiceError {
+    ServiceError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
@@ -5,9 +5,17 @@ pub mod blossoms;
 pub mod guided_tours;
 pub mod layer;
 pub mod map;
+pub mod map_access_control;
+pub mod map_action_log;
+pub mod map_collaborators;
+pub mod map_history;
 pub mod plant_layer;
 pub mod plantings;
 pub mod plants;
+pub mod presence;
 pub mod seed;
+pub mod shade_areas;
+pub mod soil_zones;
+pub mod sync;
 pub mod users;
 pub mod util;
@@ -1,21 +1,50 @@
 //! Service layer for plant layer.
 
-use std::io::Cursor;
+use std::{collections::HashSet, io::Cursor};
 
 use actix_http::StatusCode;
 use actix_web::web::Data;
+use chrono::{Days, Utc};
+use diesel::QueryResult;
+use diesel_async::AsyncPgConnection;
 use image::{ImageBuffer, Rgb};
+use uuid::Uuid;
 
 use crate::{
     config::data::AppDataInner,
     error::ServiceError,
     model::{
-        dto::{HeatMapQueryParams, RelationSearchParameters, RelationsDto},
-        entity::plant_layer,
+        dto::{
+            planting_warnings::{
+                PlantingWarningDto, PlantingWarningKind, PlantingWarningsParameters,
+            },
+            HeatMapQueryParams, RelationSearchParameters, RelationsDto,
+        },
+        entity::{
+            plant_layer::{self, HeatMap},
+            plantings::Planting,
+            plantings_impl::FindPlantingsParameters,
+            Layer, Plants,
+        },
+        r#enum::{
+            layer_type::LayerType, light_requirement::LightRequirement, shade::Shade,
+            soil_ph::SoilPh, soil_texture::SoilTexture,
+        },
     },
 };
 
+/// How much the heatmap score of a place is reduced
+/// for every level of shade it gets more than the plant tolerates.
+const SHADE_PENALTY_PER_LEVEL: f32 = 0.25;
+
+/// How much the heatmap score of a place is reduced
+/// for the pH value and for the texture of the soil if it doesn't suit the plant.
+const SOIL_PENALTY: f32 = 0.5;
+
 /// Generates a heatmap signaling ideal locations for planting the plant.
+///
+/// Places with more shade than the plant tolerates according to the shade layer
+/// or with soil that doesn't suit the plant according to the soil layer get a lower score.
 /// The return values are raw bytes of an PNG image.
 ///
 /// # Errors
@@ -30,7 +59,7 @@ pub async fn heatmap(
     app_data: &Data<AppDataInner>,
 ) -> Result<Vec<u8>, ServiceError> {
     let mut conn = app_data.pool.get().await?;
-    let result = plant_layer::heatmap(
+    let mut result = plant_layer::heatmap(
         map_id,
         query_params.layer_id,
         query_params.plant_id,
@@ -38,11 +67,75 @@ pub async fn heatmap(
     )
     .await?;
 
-    let buffer = matrix_to_image(&result)?;
+    let plant = Plants::find_by_ids(&[query_params.plant_id], &mut conn)
+        .await?
+        .into_iter()
+        .next();
+    if let Some(tolerated) = plant.as_ref().and_then(tolerated_shade) {
+        apply_shade_penalty(&mut result, map_id, tolerated, &mut conn).await?;
+    }
+    if let Some(requirements) = plant.as_ref().map(SoilRequirements::from) {
+        if !requirements.is_empty() {
+            apply_soil_penalty(&mut result, map_id, &requirements, &mut conn).await?;
+        }
+    }
+
+    let buffer = matrix_to_image(&result.scores)?;
 
     Ok(buffer)
 }
 
+/// Lower the scores of the places that get more shade than `tolerated`
+/// according to the main shade layer of the map.
+///
+/// # Errors
+/// * If the SQL query failed.
+pub async fn apply_shade_penalty(
+    heatmap: &mut HeatMap,
+    map_id: i32,
+    tolerated: Shade,
+    conn: &mut AsyncPgConnection,
+) -> QueryResult<()> {
+    for cell in plant_layer::find_shaded_cells(map_id, heatmap, conn).await? {
+        let excess = shade_excess(cell.shade, tolerated);
+        if let (true, Some(score)) = (excess > 0, heatmap.score_mut(cell.x, cell.y)) {
+            *score *= f32::from(excess)
+                .mul_add(-SHADE_PENALTY_PER_LEVEL, 1.0)
+                .max(0.0);
+        }
+    }
+    Ok(())
+}
+
+/// Lower the scores of the places with soil that doesn't suit the plant
+/// according to the main soil layer of the map.
+///
+/// # Errors
+/// * If the SQL query failed.
+async fn apply_soil_penalty(
+    heatmap: &mut HeatMap,
+    map_id: i32,
+    requirements: &SoilRequirements,
+    conn: &mut AsyncPgConnection,
+) -> QueryResult<()> {
+    let mut unsuited_ph = HashSet::new();
+    let mut unsuited_texture = HashSet::new();
+    for cell in plant_layer::find_soil_cells(map_id, heatmap, conn).await? {
+        if !requirements.suits_ph(cell.soil_ph) {
+            let _ = unsuited_ph.insert((cell.x, cell.y));
+        }
+        if !requirements.suits_texture(cell.soil_texture) {
+            let _ = unsuited_texture.insert((cell.x, cell.y));
+        }
+    }
+    for (x, y) in unsuited_ph.into_iter().chain(unsuited_texture) {
+        if let Some(score) = heatmap.score_mut(x, y) {
+            *score *= SOIL_PENALTY;
+        }
+    }
+    Ok(())
+}
+
 /// Parses the matrix of scores with values 0-1 to raw bytes of a PNG image.
 #[allow(
     clippy::cast_possible_truncation,   // ok, because size of matrix shouldn't ever be larger than u32 and casting to u8 in image should remove floating point values
@@ -84,3 +177,183 @@ pub async fn find_relations(
     let result = plant_layer::find_relations(search_query, &mut conn).await?;
     Ok(result)
 }
+
+/// Find the plantings on the layer that are placed somewhere their plant won't grow well.
+///
+/// A planting is placed badly if it gets more shade than the plant tolerates according to the shade layer
+/// or if the pH value or texture of the soil don't suit the plant according to the soil layer.
+///
+/// Only plantings that are on the map today are checked.
+///
+/// # Errors
+/// * If the connection to the database could not be established.
+/// * If the layer is not a plant layer of the map.
+pub async fn find_warnings(
+    map_id: i32,
+    search_parameters: PlantingWarningsParameters,
+    app_data: &Data<AppDataInner>,
+) -> Result<Vec<PlantingWarningDto>, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    let layer = Layer::find_by_id(search_parameters.layer_id, &mut conn).await?;
+    if layer.map_id != map_id || layer.type_ != LayerType::Plants {
+        return Err(ServiceError::new(
+            StatusCode::BAD_REQUEST,
+            "Layer is not a plant layer of this map".to_owned(),
+        ));
+    }
+
+    let today = Utc::now().date_naive();
+    let plantings = Planting::find(
+        FindPlantingsParameters {
+            map_id,
+            plant_id: None,
+            layer_id: Some(layer.id),
+            from: today,
+            to: today.checked_add_days(Days::new(1)).unwrap_or(today),
+        },
+        &mut conn,
+    )
+    .await?;
+    if plantings.is_empty() {
+        return Ok(Vec::new());
+    }
+    let plant_ids: Vec<i32> = plantings.iter().map(|planting| planting.plant_id).collect();
+    let plants = Plants::find_by_ids(&plant_ids, &mut conn).await?;
+    let planting_ids: Vec<Uuid> = plantings.iter().map(|planting| planting.id).collect();
+    let shades = plant_layer::find_planting_shades(map_id, &planting_ids, &mut conn).await?;
+    let soils = plant_layer::find_planting_soils(map_id, &planting_ids, &mut conn).await?;
+
+    let mut warnings = Vec::new();
+    for planting in plantings {
+        let Some(plant) = plants.iter().find(|plant| plant.id == planting.plant_id) else {
+            continue;
+        };
+        if let Some(tolerated) = tolerated_shade(plant) {
+            let shade = shades
+                .iter()
+                .find(|shade| shade.planting_id == planting.id)
+                .map_or(Shade::NoShade, |shade| shade.shade);
+            if shade_excess(shade, tolerated) > 0 {
+                warnings.push(PlantingWarningDto {
+                    planting_id: planting.id,
+                    kind: PlantingWarningKind::Shade,
+                    message: format!(
+                        "The plant tolerates at most {tolerated}, but the place gets {shade}"
+                    ),
+                });
+            }
+        }
+
+        let requirements = SoilRequirements::from(plant);
+        let soils_here: Vec<_> = soils
+            .iter()
+            .filter(|soil| soil.planting_id == planting.id)
+            .collect();
+        let soil_ph = soils_here
+            .iter()
+            .map(|soil| soil.soil_ph)
+            .find(|soil_ph| !requirements.suits_ph(*soil_ph));
+        let soil_texture = soils_here
+            .iter()
+            .map(|soil| soil.soil_texture)
+            .find(|soil_texture| !requirements.suits_texture(*soil_texture));
+        if let Some(soil_ph) = soil_ph {
+            warnings.push(PlantingWarningDto {
+                planting_id: planting.id,
+                kind: PlantingWarningKind::SoilPh,
+                message: format!(
+                    "The plant needs {} soil, but the soil here is {soil_ph}",
+                    join(&requirements.soil_ph)
+                ),
+            });
+        }
+        if let Some(soil_texture) = soil_texture {
+            warnings.push(PlantingWarningDto {
+                planting_id: planting.id,
+                kind: PlantingWarningKind::SoilTexture,
+                message: format!(
+                    "The plant needs {} soil, but the soil here is {soil_texture}",
+                    join(&requirements.soil_texture)
+                ),
+            });
+        }
+    }
+    Ok(warnings)
+}
+
+/// Get the most shade the plant tolerates.
+///
+/// Combines `shade` and `light_requirement` of the plant, whichever tolerates more shade counts.
+/// Returns `None` if neither is known.
+#[must_use]
+pub fn tolerated_shade(plant: &Plants) -> Option<Shade> {
+    let by_light_requirement = plant
+        .light_requirement
+        .iter()
+        .flatten()
+        .flatten()
+        .map(|light_requirement| match light_requirement {
+            LightRequirement::Full => Shade::NoShade,
+            LightRequirement::Partial => Shade::PartialShade,
+            LightRequirement::FullShade => Shade::PermanentDeepShade,
+        })
+        .max();
+    plant.shade.max(by_light_requirement)
+}
+
+/// The soil conditions that suit a plant.
+/// Empty if they are not known.
+struct SoilRequirements {
+    /// The pH values of the soil that suit the plant.
+    soil_ph: Vec<SoilPh>,
+    /// The textures of the soil that suit the plant.
+    soil_texture: Vec<SoilTexture>,
+}
+
+impl From<&Plants> for SoilRequirements {
+    fn from(plant: &Plants) -> Self {
+        Self {
+            soil_ph: plant.soil_ph.iter().flatten().flatten().copied().collect(),
+            soil_texture: plant
+                .soil_texture
+                .iter()
+                .flatten()
+                .flatten()
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+impl SoilRequirements {
+    /// Whether nothing is known about the soil that suits the plant.
+    const fn is_empty(&self) -> bool {
+        self.soil_ph.is_empty() && self.soil_texture.is_empty()
+    }
+
+    /// Whether soil with the pH value suits the plant.
+    /// Unknown requirements are never violated.
+    fn suits_ph(&self, soil_ph: SoilPh) -> bool {
+        self.soil_ph.is_empty() || self.soil_ph.contains(&soil_ph)
+    }
+
+    /// Whether soil with the texture suits the plant.
+    /// Unknown requirements are never violated.
+    fn suits_texture(&self, soil_texture: SoilTexture) -> bool {
+        self.soil_texture.is_empty() || self.soil_texture.contains(&soil_texture)
+    }
+}
+
+/// List the values for a message, e.g. `acid or neutral`.
+fn join<T: ToString>(values: &[T]) -> String {
+    values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+/// Get by how many levels `shade` is darker than `tolerated`.
+const fn shade_excess(shade: Shade, tolerated: Shade) -> u8 {
+    (shade as u8).saturating_sub(tolerated as u8)
+}
@@ -3,29 +3,44 @@
 use actix_http::StatusCode;
 use actix_web::web::Data;
 use chrono::Days;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection};
 use uuid::Uuid;
 
 use crate::config::data::AppDataInner;
 use crate::error::ServiceError;
+use crate::model::dto::action_log::EntityChange;
 use crate::model::dto::plantings::{
-    NewPlantingDto, PlantingDto, PlantingSearchParameters, UpdatePlantingDto,
+    BatchUpdatePlantingDto, NewPlantingDto, PlantingDto, PlantingSearchParameters,
+    UpdatePlantingDto,
 };
 use crate::model::dto::TimelinePage;
 use crate::model::entity::plantings::Planting;
 use crate::model::entity::plantings_impl::FindPlantingsParameters;
+use crate::service::map_access_control::{
+    check_layer_permissions, check_layers_permissions, check_planting_permissions,
+    check_plantings_permissions, check_visibility,
+};
+use crate::service::map_action_log;
+use crate::service::util::check_batch_size;
 
 /// Time offset in days for loading plantings in the timeline.
 pub const TIME_LINE_LOADING_OFFSET_DAYS: u64 = 356;
 
-/// Search plantings from the database.
+/// Search plantings of the map from the database.
+/// Checks if the requesting user is allowed to see the map.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
+/// If the requesting user is not allowed to see the map.
 pub async fn find(
+    map_id: i32,
     search_parameters: PlantingSearchParameters,
+    user_id: Uuid,
     app_data: &Data<AppDataInner>,
 ) -> Result<TimelinePage<PlantingDto>, ServiceError> {
     let mut conn = app_data.pool.get().await?;
+    check_visibility(map_id, user_id, &mut conn).await?;
 
     let from = search_parameters
         .relative_to_date
@@ -48,6 +63,7 @@ pub async fn find(
         })?;
 
     let search_parameters = FindPlantingsParameters {
+        map_id,
         layer_id: search_parameters.layer_id,
         plant_id: search_parameters.plant_id,
         from,
@@ -63,38 +79,272 @@ pub async fn find(
 }
 
 /// Create a new planting in the database.
+/// Checks if the requesting user is allowed to edit the map.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
 pub async fn create(
     dto: NewPlantingDto,
+    map_id: i32,
+    user_id: Uuid,
     app_data: &Data<AppDataInner>,
 ) -> Result<PlantingDto, ServiceError> {
     let mut conn = app_data.pool.get().await?;
-    let result = Planting::create(dto, &mut conn).await?;
-    Ok(result)
+    conn.transaction(|conn| {
+        async move {
+            check_layer_permissions(map_id, dto.layer_id, user_id, conn).await?;
+            let action_id = dto.action_id;
+            let result = Planting::create(dto, conn).await?;
+            let change = EntityChange::new(result.id, None, Some(result));
+            map_action_log::record(map_id, user_id, action_id, vec![change], conn).await?;
+            Ok(result)
+        }
+        .scope_boxed()
+    })
+    .await
 }
 
 /// Update the planting in the database.
+/// Checks if the requesting user is allowed to edit the map.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the planting was changed since the version the update is based on,
+/// in which case the current planting is returned with status 409.
 pub async fn update(
     id: Uuid,
     dto: UpdatePlantingDto,
+    map_id: i32,
+    user_id: Uuid,
     app_data: &Data<AppDataInner>,
 ) -> Result<PlantingDto, ServiceError> {
     let mut conn = app_data.pool.get().await?;
-    let result = Planting::update(id, dto, &mut conn).await?;
-    Ok(result)
+    conn.transaction(|conn| {
+        async move {
+            check_planting_permissions(map_id, id, user_id, conn).await?;
+            let (before, result) = update_versioned(id, dto, conn).await?;
+            let change = EntityChange::new(id, Some(before), Some(result));
+            map_action_log::record(map_id, user_id, dto.action_id(), vec![change], conn).await?;
+            Ok(result)
+        }
+        .scope_boxed()
+    })
+    .await
 }
 
 /// Delete the planting from the database.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// Returns the deleted planting.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the planting is already deleted.
+pub async fn delete_by_id(
+    id: Uuid,
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<PlantingDto, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    conn.transaction(|conn| {
+        async move {
+            check_planting_permissions(map_id, id, user_id, conn).await?;
+            let before = delete_existing(id, conn).await?;
+            let change = EntityChange::new(id, Some(before), None);
+            map_action_log::record(map_id, user_id, action_id, vec![change], conn).await?;
+            Ok(Planting::find_by_id(id, conn).await?)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Restore a deleted planting.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the planting is not deleted or can no longer be restored.
+pub async fn restore_by_id(
+    id: Uuid,
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<PlantingDto, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    conn.transaction(|conn| {
+        async move {
+            check_planting_permissions(map_id, id, user_id, conn).await?;
+            let result = Planting::restore(id, conn).await?;
+            let change = EntityChange::new(id, None, Some(result));
+            map_action_log::record(map_id, user_id, action_id, vec![change], conn).await?;
+            Ok(result)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Create many plantings in a single transaction.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the batch contains more than [`MAX_BATCH_SIZE`](crate::service::util::MAX_BATCH_SIZE) plantings.
+/// If any of the plantings could not be created, in which case none are created.
+pub async fn create_batch(
+    dtos: Vec<NewPlantingDto>,
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<Vec<PlantingDto>, ServiceError> {
+    check_batch_size(dtos.len())?;
+    let mut conn = app_data.pool.get().await?;
+    conn.transaction(|conn| {
+        async move {
+            let layer_ids: Vec<i32> = dtos.iter().map(|dto| dto.layer_id).collect();
+            check_layers_permissions(map_id, &layer_ids, user_id, conn).await?;
+            let mut result = Vec::with_capacity(dtos.len());
+            for dto in dtos {
+                result.push(Planting::create(dto, conn).await?);
+            }
+            let changes = result
+                .iter()
+                .map(|planting| EntityChange::new(planting.id, None, Some(*planting)))
+                .collect();
+            map_action_log::record(map_id, user_id, action_id, changes, conn).await?;
+            Ok(result)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Update many plantings in a single transaction.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the batch contains more than [`MAX_BATCH_SIZE`](crate::service::util::MAX_BATCH_SIZE) plantings.
+/// If any of the plantings could not be updated, in which case none are updated.
+/// If any of the plantings was changed since the version its update is based on,
+/// in which case the current state of that planting is returned with status 409.
+pub async fn update_batch(
+    dtos: Vec<BatchUpdatePlantingDto>,
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<Vec<PlantingDto>, ServiceError> {
+    check_batch_size(dtos.len())?;
+    let mut conn = app_data.pool.get().await?;
+    conn.transaction(|conn| {
+        async move {
+            let ids: Vec<Uuid> = dtos.iter().map(|dto| dto.id).collect();
+            check_plantings_permissions(map_id, &ids, user_id, conn).await?;
+            let mut result = Vec::with_capacity(dtos.len());
+            let mut changes = Vec::with_capacity(dtos.len());
+            for dto in dtos {
+                let (before, planting) = update_versioned(dto.id, dto.update, conn).await?;
+                changes.push(EntityChange::new(dto.id, Some(before), Some(planting)));
+                result.push(planting);
+            }
+            map_action_log::record(map_id, user_id, action_id, changes, conn).await?;
+            Ok(result)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Delete many plantings in a single transaction.
+/// Checks if the requesting user is allowed to edit the map.
 ///
 /// # Errors
 /// If the connection to the database could not be established.
-pub async fn delete_by_id(id: Uuid, app_data: &Data<AppDataInner>) -> Result<(), ServiceError> {
+/// If the requesting user is not allowed to edit the map.
+/// If the batch contains more than [`MAX_BATCH_SIZE`](crate::service::util::MAX_BATCH_SIZE) plantings.
+/// If any of the plantings could not be deleted, in which case none are deleted.
+/// If any of the plantings is already deleted.
+///
+/// Returns the deleted plantings.
+pub async fn delete_batch(
+    ids: Vec<Uuid>,
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<Vec<PlantingDto>, ServiceError> {
+    check_batch_size(ids.len())?;
     let mut conn = app_data.pool.get().await?;
-    let _ = Planting::delete_by_id(id, &mut conn).await?;
-    Ok(())
+    conn.transaction(|conn| {
+        async move {
+            check_plantings_permissions(map_id, &ids, user_id, conn).await?;
+            let mut changes = Vec::with_capacity(ids.len());
+            let mut deleted = Vec::with_capacity(ids.len());
+            for id in ids {
+                let before = delete_existing(id, conn).await?;
+                changes.push(EntityChange::new(id, Some(before), None));
+                deleted.push(Planting::find_by_id(id, conn).await?);
+            }
+            map_action_log::record(map_id, user_id, action_id, changes, conn).await?;
+            Ok(deleted)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Update the planting if it is still in the version the update is based on.
+/// Returns the planting before and after the update.
+///
+/// # Errors
+/// If the planting doesn't exist or is deleted.
+/// If the planting was changed in the meantime, the current planting is returned as JSON.
+async fn update_versioned(
+    id: Uuid,
+    dto: UpdatePlantingDto,
+    conn: &mut AsyncPgConnection,
+) -> Result<(PlantingDto, PlantingDto), ServiceError> {
+    let before = Planting::find_existing_by_id(id, conn)
+        .await?
+        .ok_or_else(|| ServiceError::new(StatusCode::NOT_FOUND, "Planting not found".to_owned()))?;
+    let after = Planting::update(id, dto, conn)
+        .await?
+        .ok_or_else(|| ServiceError::with_json(StatusCode::CONFLICT, &before))?;
+    Ok((before, after))
+}
+
+/// Delete the planting if it is not deleted yet.
+/// Returns the planting before the deletion.
+///
+/// # Errors
+/// If the planting is already deleted.
+async fn delete_existing(
+    id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<PlantingDto, ServiceError> {
+    let already_deleted = || {
+        ServiceError::new(
+            StatusCode::CONFLICT,
+            "Planting is already deleted".to_owned(),
+        )
+    };
+    let before = Planting::find_existing_by_id(id, conn)
+        .await?
+        .ok_or_else(already_deleted)?;
+    if Planting::delete_by_id(id, conn).await? == 0 {
+        return Err(already_deleted());
+    }
+    Ok(before)
 }
@@ -0,0 +1,125 @@
+//! Service layer for the presence of users on maps.
+
+use actix_http::StatusCode;
+use actix_web::web::Data;
+use diesel_async::AsyncPgConnection;
+use uuid::Uuid;
+
+use crate::config::data::AppDataInner;
+use crate::error::ServiceError;
+use crate::model::dto::{UpdateSubscriptionDto, UpdateUserPresenceDto, UserPresenceDto};
+use crate::model::entity::{Layer, Map, MapClient};
+
+/// Find all users currently connected to the map on any backend instance.
+/// Checks if the map is visible to the requesting user.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the map does not exist or is not visible to the requesting user.
+pub async fn find(
+    map_id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<Vec<UserPresenceDto>, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    let _ = Map::find_visible_by_id(map_id, user_id, &mut conn).await?;
+
+    let mut users: Vec<UserPresenceDto> = Vec::new();
+    for client in MapClient::find_connected(map_id, &mut conn).await? {
+        match users.last_mut() {
+            Some(user) if user.user_id == client.user_id => {
+                user.selected_layer_id = client.selected_layer_id;
+            }
+            _ => users.push(UserPresenceDto {
+                user_id: client.user_id,
+                selected_layer_id: client.selected_layer_id,
+            }),
+        }
+    }
+    Ok(users)
+}
+
+/// Set the layer the requesting user has selected on the map.
+/// Checks if the map is visible to the requesting user.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the map does not exist or is not visible to the requesting user.
+/// If the layer is not part of the map.
+/// If the user has no client connected to the map.
+pub async fn update(
+    map_id: i32,
+    user_id: Uuid,
+    dto: UpdateUserPresenceDto,
+    app_data: &Data<AppDataInner>,
+) -> Result<(), ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    let _ = Map::find_visible_by_id(map_id, user_id, &mut conn).await?;
+    if let Some(layer_id) = dto.selected_layer_id {
+        check_layer_is_on_map(map_id, layer_id, &mut conn).await?;
+    }
+
+    let selected =
+        MapClient::select_layer(map_id, user_id, dto.selected_layer_id, &mut conn).await?;
+    if selected == 0 {
+        return Err(ServiceError::new(
+            StatusCode::NOT_FOUND,
+            "User is not connected to this map".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// Set the layers a client of the requesting user receives actions for,
+/// no matter which backend instance the client is connected to.
+/// Checks if the map is visible to the requesting user.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the map does not exist or is not visible to the requesting user.
+/// If any of the layers is not part of the map.
+/// If the client is not connected to the map.
+pub async fn update_subscription(
+    map_id: i32,
+    user_id: Uuid,
+    dto: UpdateSubscriptionDto,
+    app_data: &Data<AppDataInner>,
+) -> Result<(), ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    let _ = Map::find_visible_by_id(map_id, user_id, &mut conn).await?;
+    for layer_id in dto.layer_ids.iter().flatten() {
+        check_layer_is_on_map(map_id, *layer_id, &mut conn).await?;
+    }
+
+    let subscribed =
+        MapClient::subscribe(map_id, user_id, dto.client_id, dto.layer_ids, &mut conn).await?;
+    if subscribed.is_none() {
+        return Err(ServiceError::new(
+            StatusCode::NOT_FOUND,
+            "Client is not connected to this map".to_owned(),
+        ));
+    }
+    drop(conn);
+
+    app_data
+        .broadcaster
+        .reload_subscription(map_id, dto.client_id, user_id)
+        .await;
+    Ok(())
+}
+
+/// Check if the layer is part of the map.
+async fn check_layer_is_on_map(
+    map_id: i32,
+    layer_id: i32,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    let layer = Layer::find_by_id(layer_id, conn).await?;
+    if layer.map_id != map_id {
+        return Err(ServiceError::new(
+            StatusCode::BAD_REQUEST,
+            "Layer is not part of this map".to_owned(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+//! Service layer for areas on the shade layer.
+
+use actix_http::StatusCode;
+use actix_web::web::Data;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::AsyncConnection;
+use uuid::Uuid;
+
+use crate::config::data::AppDataInner;
+use crate::error::ServiceError;
+use crate::model::dto::action_log::EntityChange;
+use crate::model::dto::shade_areas::{
+    NewShadeAreaDto, ShadeAreaDto, ShadeAtPointDto, ShadeAtPointParameters, UpdateShadeAreaDto,
+};
+use crate::model::entity::shade_areas::ShadeArea;
+use crate::model::entity::Layer;
+use crate::model::r#enum::{layer_type::LayerType, shade::Shade};
+use crate::service::map_access_control::{check_layer_permissions, check_shade_area_permissions};
+use crate::service::map_action_log;
+use crate::service::util::{check_layer_type, validate_geometry};
+
+/// Fetch all shade areas of the layer from the database.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+pub async fn find(
+    layer_id: i32,
+    app_data: &Data<AppDataInner>,
+) -> Result<Vec<ShadeAreaDto>, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    let result = ShadeArea::find(layer_id, &mut conn).await?;
+    Ok(result)
+}
+
+/// Find out how much shade a point of the map gets according to the shade layer.
+/// If several areas cover the point, the one with the most shade counts.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the layer is not a shade layer of the map.
+pub async fn find_shade_at(
+    map_id: i32,
+    layer_id: i32,
+    point: ShadeAtPointParameters,
+    app_data: &Data<AppDataInner>,
+) -> Result<ShadeAtPointDto, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    let layer = Layer::find_by_id(layer_id, &mut conn).await?;
+    if layer.map_id != map_id || layer.type_ != LayerType::Shade {
+        return Err(ServiceError::new(
+            StatusCode::BAD_REQUEST,
+            "Layer is not a shade layer of this map".to_owned(),
+        ));
+    }
+    let area =
+        ShadeArea::find_covering(layer_id, f64::from(point.x), f64::from(point.y), &mut conn)
+            .await?;
+    Ok(area.map_or(
+        ShadeAtPointDto {
+            shade: Shade::NoShade,
+            shade_area_id: None,
+        },
+        |area| ShadeAtPointDto {
+            shade: area.shade,
+            shade_area_id: Some(area.id),
+        },
+    ))
+}
+
+/// Create a shade area in the database.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the layer is not a shade layer or the geometry is invalid.
+pub async fn create(
+    dto: NewShadeAreaDto,
+    map_id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<ShadeAreaDto, ServiceError> {
+    validate_geometry(&dto.geometry)?;
+    let mut conn = app_data.pool.get().await?;
+    conn.transaction(|conn| {
+        async move {
+            check_layer_permissions(map_id, dto.layer_id, user_id, conn).await?;
+            check_layer_type(dto.layer_id, LayerType::Shade, conn).await?;
+            let action_id = dto.action_id;
+            let result = ShadeArea::create(dto, conn).await?;
+            let change = EntityChange::new(result.id, None, Some(result.clone()));
+            map_action_log::record(map_id, user_id, action_id, vec![change], conn).await?;
+            Ok(result)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Change the intensity or the outline of the shade area in the database.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the geometry is invalid.
+pub async fn update(
+    id: Uuid,
+    dto: UpdateShadeAreaDto,
+    map_id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<ShadeAreaDto, ServiceError> {
+    if let Some(geometry) = &dto.geometry {
+        validate_geometry(geometry)?;
+    }
+    let mut conn = app_data.pool.get().await?;
+    conn.transaction(|conn| {
+        async move {
+            check_shade_area_permissions(map_id, id, user_id, conn).await?;
+            let action_id = dto.action_id;
+            let before = ShadeArea::find_by_id(id, conn).await?;
+            let result = ShadeArea::update(id, dto, conn).await?;
+            let change = EntityChange::new(id, Some(before), Some(result.clone()));
+            map_action_log::record(map_id, user_id, action_id, vec![change], conn).await?;
+            Ok(result)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Delete the shade area from the database.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// Returns the deleted shade area.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+pub async fn delete_by_id(
+    id: Uuid,
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<ShadeAreaDto, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    conn.transaction(|conn| {
+        async move {
+            check_shade_area_permissions(map_id, id, user_id, conn).await?;
+            let before = ShadeArea::find_by_id(id, conn).await?;
+            let _ = ShadeArea::delete_by_id(id, conn).await?;
+            let change = EntityChange::new(id, Some(before.clone()), None);
+            map_action_log::record(map_id, user_id, action_id, vec![change], conn).await?;
+            Ok(before)
+        }
+        .scope_boxed()
+    })
+    .await
+}
@@ -0,0 +1,122 @@
+//! Service layer for zones on the soil layer.
+
+use actix_web::web::Data;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::AsyncConnection;
+use uuid::Uuid;
+
+use crate::config::data::AppDataInner;
+use crate::error::ServiceError;
+use crate::model::dto::action_log::EntityChange;
+use crate::model::dto::soil_zones::{NewSoilZoneDto, SoilZoneDto, UpdateSoilZoneDto};
+use crate::model::entity::soil_zones::SoilZone;
+use crate::model::r#enum::layer_type::LayerType;
+use crate::service::map_access_control::{check_layer_permissions, check_soil_zone_permissions};
+use crate::service::map_action_log;
+use crate::service::util::{check_layer_type, validate_geometry};
+
+/// Fetch all soil zones of the layer from the database.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+pub async fn find(
+    layer_id: i32,
+    app_data: &Data<AppDataInner>,
+) -> Result<Vec<SoilZoneDto>, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    let result = SoilZone::find(layer_id, &mut conn).await?;
+    Ok(result)
+}
+
+/// Create a soil zone in the database.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the layer is not a soil layer or the geometry is invalid.
+pub async fn create(
+    dto: NewSoilZoneDto,
+    map_id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<SoilZoneDto, ServiceError> {
+    validate_geometry(&dto.geometry)?;
+    let mut conn = app_data.pool.get().await?;
+    conn.transaction(|conn| {
+        async move {
+            check_layer_permissions(map_id, dto.layer_id, user_id, conn).await?;
+            check_layer_type(dto.layer_id, LayerType::Soil, conn).await?;
+            let action_id = dto.action_id;
+            let result = SoilZone::create(dto, conn).await?;
+            let change = EntityChange::new(result.id, None, Some(result.clone()));
+            map_action_log::record(map_id, user_id, action_id, vec![change], conn).await?;
+            Ok(result)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Change the soil conditions or the outline of the soil zone in the database.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If the geometry is invalid.
+pub async fn update(
+    id: Uuid,
+    dto: UpdateSoilZoneDto,
+    map_id: i32,
+    user_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<SoilZoneDto, ServiceError> {
+    if let Some(geometry) = &dto.geometry {
+        validate_geometry(geometry)?;
+    }
+    let mut conn = app_data.pool.get().await?;
+    conn.transaction(|conn| {
+        async move {
+            check_soil_zone_permissions(map_id, id, user_id, conn).await?;
+            let action_id = dto.action_id;
+            let before = SoilZone::find_by_id(id, conn).await?;
+            let result = SoilZone::update(id, dto, conn).await?;
+            let change = EntityChange::new(id, Some(before), Some(result.clone()));
+            map_action_log::record(map_id, user_id, action_id, vec![change], conn).await?;
+            Ok(result)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Delete the soil zone from the database.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// Returns the deleted soil zone.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+pub async fn delete_by_id(
+    id: Uuid,
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    app_data: &Data<AppDataInner>,
+) -> Result<SoilZoneDto, ServiceError> {
+    let mut conn = app_data.pool.get().await?;
+    conn.transaction(|conn| {
+        async move {
+            check_soil_zone_permissions(map_id, id, user_id, conn).await?;
+            let before = SoilZone::find_by_id(id, conn).await?;
+            let _ = SoilZone::delete_by_id(id, conn).await?;
+            let change = EntityChange::new(id, Some(before.clone()), None);
+            map_action_log::record(map_id, user_id, action_id, vec![change], conn).await?;
+            Ok(before)
+        }
+        .scope_boxed()
+    })
+    .await
+}
@@ -0,0 +1,324 @@
+//! Service layer for synchronizing changes made while the map was edited offline.
+//!
+//! All operations are applied in a single transaction in the order of their client timestamps.
+//! Every operation runs in its own savepoint, so an operation that fails doesn't affect the others.
+//! Operations conflicting with changes made in the meantime are discarded
+//! and reported together with the current state of the entity.
+//! The accepted operations are recorded as a single action, so they can be undone together.
+
+use actix_web::web::Data;
+use diesel::OptionalExtension;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, AsyncPgConnection};
+use uuid::Uuid;
+
+use crate::config::data::AppDataInner;
+use crate::error::ServiceError;
+use crate::model::dto::action_log::EntityChange;
+use crate::model::dto::actions::{
+    Action, CreateBaseLayerImageActionPayload, CreatePlantActionPayload,
+    DeleteBaseLayerImageActionPayload, DeletePlantActionPayload, UpdateBaseLayerImageActionPayload,
+};
+use crate::model::dto::plantings::PlantingDto;
+use crate::model::dto::sync::{
+    SyncDto, SyncOperation, SyncOperationResultDto, SyncResultDto, SyncStatus,
+};
+use crate::model::dto::BaseLayerImageDto;
+use crate::model::entity::plantings::Planting;
+use crate::model::entity::BaseLayerImages;
+use crate::service::map_access_control::{
+    check_base_layer_image_permissions, check_layer_permissions, check_permissions,
+    check_planting_permissions,
+};
+use crate::service::map_action_log;
+use crate::service::util::check_batch_size;
+
+/// Reason of a conflict with a planting that was deleted.
+const PLANTING_DELETED: &str = "The planting was deleted in the meantime";
+/// Reason of a conflict with a base layer image that was deleted.
+const IMAGE_DELETED: &str = "The base layer image was deleted in the meantime";
+
+/// The outcome of applying a single offline operation.
+struct Outcome {
+    /// The result reported to the client.
+    result: SyncOperationResultDto,
+    /// The change for the action log and the action to broadcast, if the operation was accepted.
+    applied: Option<(EntityChange, Action)>,
+}
+
+/// Apply the operations done while editing the map offline.
+/// Checks if the requesting user is allowed to edit the map.
+///
+/// Returns the result of every operation and the actions that have to be broadcast
+/// for the accepted operations.
+///
+/// # Errors
+/// If the connection to the database could not be established.
+/// If the requesting user is not allowed to edit the map.
+/// If there are more than [`MAX_BATCH_SIZE`](crate::service::util::MAX_BATCH_SIZE) operations.
+pub async fn sync(
+    map_id: i32,
+    user_id: Uuid,
+    dto: SyncDto,
+    app_data: &Data<AppDataInner>,
+) -> Result<(SyncResultDto, Vec<Action>), ServiceError> {
+    check_batch_size(dto.operations.len())?;
+    let mut conn = app_data.pool.get().await?;
+    check_permissions(map_id, user_id, &mut conn).await?;
+
+    let SyncDto {
+        operations,
+        action_id,
+    } = dto;
+    let mut operations: Vec<_> = operations.into_iter().enumerate().collect();
+    // The sort is stable, so operations with the same timestamp keep their order.
+    operations.sort_by_key(|(_, operation)| operation.timestamp);
+
+    conn.transaction(|conn| {
+        async move {
+            let mut results = Vec::with_capacity(operations.len());
+            let mut changes = Vec::new();
+            let mut actions = Vec::new();
+            for (index, operation) in operations {
+                let outcome = conn
+                    .transaction(|conn| {
+                        apply(operation.operation, map_id, user_id, action_id, conn).scope_boxed()
+                    })
+                    .await;
+                let operation_result = match outcome {
+                    Ok(Outcome { result, applied }) => {
+                        if let Some((change, action)) = applied {
+                            changes.push(change);
+                            actions.push(action);
+                        }
+                        result
+                    }
+                    Err(e) => result(SyncStatus::Rejected, Some(e.reason)),
+                };
+                results.push((index, operation_result));
+            }
+
+            if !changes.is_empty() {
+                map_action_log::record(map_id, user_id, action_id, changes, conn).await?;
+            }
+            results.sort_by_key(|(index, _)| *index);
+            let results = results.into_iter().map(|(_, result)| result).collect();
+            Ok((SyncResultDto { results }, actions))
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Apply a single operation.
+/// Errors cause the operation to be rejected.
+async fn apply(
+    operation: SyncOperation,
+    map_id: i32,
+    user_id: Uuid,
+    action_id: Uuid,
+    conn: &mut AsyncPgConnection,
+) -> Result<Outcome, ServiceError> {
+    match operation {
+        SyncOperation::CreatePlanting(dto) => {
+            check_layer_permissions(map_id, dto.layer_id, user_id, conn).await?;
+            if let Some(id) = dto.id {
+                if let Some(existing) = Planting::find_by_id(id, conn).await.optional()? {
+                    return Ok(planting_conflict(
+                        Some(existing),
+                        "A planting with this id already exists",
+                    ));
+                }
+            }
+            let planting = Planting::create(dto, conn).await?;
+            let action =
+                Action::CreatePlanting(CreatePlantActionPayload::new(planting, user_id, action_id));
+            Ok(planting_accepted(planting, None, action))
+        }
+        SyncOperation::UpdatePlanting(dto) => {
+            check_planting_permissions(map_id, dto.id, user_id, conn).await?;
+            let Some(before) = Planting::find_existing_by_id(dto.id, conn).await? else {
+                return Ok(planting_conflict(None, PLANTING_DELETED));
+            };
+            let Some(planting) = Planting::update(dto.id, dto.update, conn).await? else {
+                return Ok(planting_conflict(
+                    Some(before),
+                    "The planting was changed in the meantime",
+                ));
+            };
+            let action = Action::update_planting(dto.update, planting, user_id, action_id);
+            Ok(planting_accepted(planting, Some(before), action))
+        }
+        SyncOperation::DeletePlanting(dto) => {
+            check_planting_permissions(map_id, dto.id, user_id, conn).await?;
+            let Some(before) = Planting::find_existing_by_id(dto.id, conn).await? else {
+                return Ok(planting_conflict(None, PLANTING_DELETED));
+            };
+            let _ = Planting::delete_by_id(dto.id, conn).await?;
+            let action = Action::DeletePlanting(DeletePlantActionPayload::new(
+                dto.id,
+                before.layer_id,
+                user_id,
+                action_id,
+            ));
+            Ok(planting_deleted(before, action))
+        }
+        SyncOperation::CreateBaseLayerImage(dto) => {
+            check_layer_permissions(map_id, dto.layer_id, user_id, conn).await?;
+            if let Some(existing) = BaseLayerImages::find_by_id(dto.id, conn).await.optional()? {
+                return Ok(image_conflict(
+                    Some(existing),
+                    "A base layer image with this id already exists",
+                ));
+            }
+            let image = BaseLayerImages::create(dto.into(), conn).await?;
+            let action = Action::CreateBaseLayerImage(CreateBaseLayerImageActionPayload::new(
+                image.clone(),
+                user_id,
+                action_id,
+            ));
+            Ok(image_accepted(image, None, action))
+        }
+        SyncOperation::UpdateBaseLayerImage(dto) => {
+            let Some(before) = BaseLayerImages::find_by_id(dto.id, conn).await.optional()? else {
+                return Ok(image_conflict(None, IMAGE_DELETED));
+            };
+            check_base_layer_image_permissions(map_id, dto.id, user_id, conn).await?;
+            // The image may be moved to another layer, which has to be on the same map.
+            check_layer_permissions(map_id, dto.update.layer_id, user_id, conn).await?;
+            let image = BaseLayerImages::update(dto.id, dto.update, conn).await?;
+            let action = Action::UpdateBaseLayerImage(UpdateBaseLayerImageActionPayload::new(
+                image.clone(),
+                user_id,
+                action_id,
+            ));
+            Ok(image_accepted(image, Some(before), action))
+        }
+        SyncOperation::DeleteBaseLayerImage(dto) => {
+            let Some(before) = BaseLayerImages::find_by_id(dto.id, conn).await.optional()? else {
+                return Ok(image_conflict(None, IMAGE_DELETED));
+            };
+            check_base_layer_image_permissions(map_id, dto.id, user_id, conn).await?;
+            let _ = BaseLayerImages::delete_by_id(dto.id, conn).await?;
+            let action = Action::DeleteBaseLayerImage(DeleteBaseLayerImageActionPayload::new(
+                dto.id,
+                before.layer_id,
+                user_id,
+                action_id,
+            ));
+            Ok(image_deleted(before, action))
+        }
+    }
+}
+
+/// A result without any entity.
+const fn result(status: SyncStatus, reason: Option<String>) -> SyncOperationResultDto {
+    SyncOperationResultDto {
+        status,
+        reason,
+        planting: None,
+        base_layer_image: None,
+    }
+}
+
+/// An accepted operation that created or updated the planting.
+fn planting_accepted(
+    planting: PlantingDto,
+    before: Option<PlantingDto>,
+    action: Action,
+) -> Outcome {
+    Outcome {
+        result: SyncOperationResultDto {
+            planting: Some(planting),
+            ..result(SyncStatus::Accepted, None)
+        },
+        applied: Some((
+            EntityChange::Planting {
+                id: planting.id,
+                before,
+                after: Some(planting),
+            },
+            action,
+        )),
+    }
+}
+
+/// An accepted operation that deleted the planting.
+fn planting_deleted(before: PlantingDto, action: Action) -> Outcome {
+    Outcome {
+        result: SyncOperationResultDto {
+            planting: Some(before),
+            ..result(SyncStatus::Accepted, None)
+        },
+        applied: Some((
+            EntityChange::Planting {
+                id: before.id,
+                before: Some(before),
+                after: None,
+            },
+            action,
+        )),
+    }
+}
+
+/// An operation conflicting with the `current` state of the planting.
+fn planting_conflict(current: Option<PlantingDto>, reason: &str) -> Outcome {
+    Outcome {
+        result: SyncOperationResultDto {
+            planting: current,
+            ..result(SyncStatus::Conflict, Some(reason.to_owned()))
+        },
+        applied: None,
+    }
+}
+
+/// An accepted operation that created or updated the base layer image.
+fn image_accepted(
+    image: BaseLayerImageDto,
+    before: Option<BaseLayerImageDto>,
+    action: Action,
+) -> Outcome {
+    Outcome {
+        result: SyncOperationResultDto {
+            base_layer_image: Some(image.clone()),
+            ..result(SyncStatus::Accepted, None)
+        },
+        applied: Some((
+            EntityChange::BaseLayerImage {
+                id: image.id,
+                before,
+                after: Some(image),
+            },
+            action,
+        )),
+    }
+}
+
+/// An accepted operation that deleted the base layer image.
+fn image_deleted(before: BaseLayerImageDto, action: Action) -> Outcome {
+    Outcome {
+        result: SyncOperationResultDto {
+            base_layer_image: Some(before.clone()),
+            ..result(SyncStatus::Accepted, None)
+        },
+        applied: Some((
+            EntityChange::BaseLayerImage {
+                id: before.id,
+                before: Some(before),
+                after: None,
+            },
+            action,
+        )),
+    }
+}
+
+/// An operation conflicting with the `current` state of the base layer image.
+fn image_conflict(current: Option<BaseLayerImageDto>, reason: &str) -> Outcome {
+    Outcome {
+        result: SyncOperationResultDto {
+            base_layer_image: current,
+            ..result(SyncStatus::Conflict, Some(reason.to_owned()))
+        },
+        applied: None,
+    }
+}
@@ -2,7 +2,17 @@
 
 use std::ops::Div;
 
+use actix_http::StatusCode;
 use chrono::Datelike;
+use diesel_async::AsyncPgConnection;
+use postgis_diesel::types::{Point, Polygon};
+
+use crate::error::ServiceError;
+use crate::model::entity::Layer;
+use crate::model::r#enum::layer_type::LayerType;
+
+/// The maximum number of changes that can be made in a single batch, e.g. of plantings.
+pub const MAX_BATCH_SIZE: usize = 1000;
 
 /// Trait for getting the half month bucket of a `NaiveDate`.
 pub trait HalfMonthBucket {
@@ -53,6 +63,184 @@ where
     }
 }
 
+/// Trait for validating polygons.
+pub trait PolygonGeometry {
+    /// Checks that the polygon has at least one ring and that every ring
+    /// is closed, has at least three distinct points and does not intersect itself.
+    /// The first ring is the shell, all other rings are holes
+    /// which have to lie inside the shell without touching it.
+    ///
+    /// # Errors
+    /// * A description of the first problem found.
+    fn validate(&self) -> Result<(), &'static str>;
+}
+
+impl PolygonGeometry for Polygon<Point> {
+    fn validate(&self) -> Result<(), &'static str> {
+        let Some((shell, holes)) = self.rings.split_first() else {
+            return Err("Polygon has no rings");
+        };
+        for ring in &self.rings {
+            let (Some(first), Some(last)) = (ring.first(), ring.last()) else {
+                return Err("Polygon contains an empty ring");
+            };
+            if !same_point(first, last) {
+                return Err("Polygon is not closed");
+            }
+            if !has_three_distinct_points(ring) {
+                return Err("Polygon needs at least three distinct points");
+            }
+            if ring_intersects_itself(ring) {
+                return Err("Polygon intersects itself");
+            }
+        }
+        for hole in holes {
+            if rings_intersect(shell, hole) {
+                return Err("Polygon has a hole touching its shell");
+            }
+            // As the rings don't intersect, the hole is inside if any of its points is.
+            if !hole
+                .first()
+                .is_some_and(|point| ring_contains(shell, point))
+            {
+                return Err("Polygon has a hole outside of its shell");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check that the geometry is a valid polygon.
+///
+/// # Errors
+/// * If the polygon is invalid, see [`PolygonGeometry::validate`].
+pub fn validate_geometry(geometry: &Polygon<Point>) -> Result<(), ServiceError> {
+    geometry.validate().map_err(|reason| {
+        ServiceError::new(
+            StatusCode::BAD_REQUEST,
+            format!("Invalid geometry: {reason}"),
+        )
+    })
+}
+
+/// Reject batches that are too large to be handled in a single transaction.
+///
+/// # Errors
+/// * If the batch contains more than [`MAX_BATCH_SIZE`] changes.
+pub fn check_batch_size(len: usize) -> Result<(), ServiceError> {
+    if len > MAX_BATCH_SIZE {
+        return Err(ServiceError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("A batch can contain at most {MAX_BATCH_SIZE} changes"),
+        ));
+    }
+    Ok(())
+}
+
+/// Check that the layer is of type `type_`, e.g. before placing an element on it.
+///
+/// # Errors
+/// * If the layer could not be found.
+/// * If the layer is of another type.
+pub async fn check_layer_type(
+    layer_id: i32,
+    type_: LayerType,
+    conn: &mut AsyncPgConnection,
+) -> Result<(), ServiceError> {
+    let layer = Layer::find_by_id(layer_id, conn).await?;
+    if layer.type_ != type_ {
+        return Err(ServiceError::new(
+            StatusCode::BAD_REQUEST,
+            format!("This can only be placed on a {type_} layer"),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns true if both points have the same coordinates.
+fn same_point(a: &Point, b: &Point) -> bool {
+    (a.x - b.x).abs() < f64::EPSILON && (a.y - b.y).abs() < f64::EPSILON
+}
+
+/// Returns true if the ring contains at least three points with different coordinates.
+fn has_three_distinct_points(ring: &[Point]) -> bool {
+    let mut distinct: Vec<&Point> = Vec::with_capacity(3);
+    for point in ring {
+        if !distinct.iter().any(|seen| same_point(seen, point)) {
+            distinct.push(point);
+            if distinct.len() == 3 {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Returns a positive value if `c` is left of the line through `a` and `b`,
+/// a negative value if it is right of it and zero if all three points are collinear.
+fn orientation(a: &Point, b: &Point, c: &Point) -> f64 {
+    (b.x - a.x).mul_add(c.y - a.y, -(b.y - a.y) * (c.x - a.x))
+}
+
+/// Returns true if `p`, which is collinear with `a` and `b`, lies on the segment from `a` to `b`.
+fn on_segment(a: &Point, b: &Point, p: &Point) -> bool {
+    p.x >= a.x.min(b.x) && p.x <= a.x.max(b.x) && p.y >= a.y.min(b.y) && p.y <= a.y.max(b.y)
+}
+
+/// Returns true if the segment from `p1` to `p2` touches or crosses the segment from `q1` to `q2`.
+fn segments_intersect(p1: &Point, p2: &Point, q1: &Point, q2: &Point) -> bool {
+    let d1 = orientation(q1, q2, p1);
+    let d2 = orientation(q1, q2, p2);
+    let d3 = orientation(p1, p2, q1);
+    let d4 = orientation(p1, p2, q2);
+
+    if d1 * d2 < 0.0 && d3 * d4 < 0.0 {
+        return true;
+    }
+
+    (d1.abs() < f64::EPSILON && on_segment(q1, q2, p1))
+        || (d2.abs() < f64::EPSILON && on_segment(q1, q2, p2))
+        || (d3.abs() < f64::EPSILON && on_segment(p1, p2, q1))
+        || (d4.abs() < f64::EPSILON && on_segment(p1, p2, q2))
+}
+
+/// Returns the edges of the ring as pairs of consecutive points.
+fn edges(ring: &[Point]) -> impl Iterator<Item = (&Point, &Point)> {
+    ring.windows(2).filter_map(|w| w.first().zip(w.last()))
+}
+
+/// Returns true if any two non-adjacent edges of the closed ring touch or cross.
+fn ring_intersects_itself(ring: &[Point]) -> bool {
+    let edges: Vec<(&Point, &Point)> = edges(ring).collect();
+    let edge_count = edges.len();
+
+    edges.iter().enumerate().any(|(i, (p1, p2))| {
+        edges
+            .iter()
+            .enumerate()
+            .skip(i + 2)
+            // The first and the last edge share the closing point.
+            .filter(|(j, _)| !(i == 0 && *j == edge_count - 1))
+            .any(|(_, (q1, q2))| segments_intersect(p1, p2, q1, q2))
+    })
+}
+
+/// Returns true if any edge of ring `a` touches or crosses any edge of ring `b`.
+fn rings_intersect(a: &[Point], b: &[Point]) -> bool {
+    edges(a).any(|(p1, p2)| edges(b).any(|(q1, q2)| segments_intersect(p1, p2, q1, q2)))
+}
+
+/// Returns true if `point` lies inside the closed ring.
+///
+/// Counts how many edges a ray from `point` to the right crosses, which is odd for points inside.
+fn ring_contains(ring: &[Point], point: &Point) -> bool {
+    let crossings = edges(ring)
+        .filter(|(a, b)| (a.y > point.y) != (b.y > point.y))
+        .filter(|(a, b)| point.x < (b.x - a.x).mul_add((point.y - a.y) / (b.y - a.y), a.x))
+        .count();
+    crossings % 2 == 1
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -79,4 +267,102 @@ mod tests {
         let date = NaiveDate::from_ymd_opt(2020, 12, 31).unwrap();
         assert_eq!(date.half_month_bucket(), 23);
     }
+
+    fn polygon(points: &[(f64, f64)]) -> Polygon<Point> {
+        polygon_with_holes(&[points])
+    }
+
+    fn polygon_with_holes(rings: &[&[(f64, f64)]]) -> Polygon<Point> {
+        Polygon {
+            rings: rings
+                .iter()
+                .map(|ring| {
+                    ring.iter()
+                        .map(|&(x, y)| Point::new(x, y, Some(4326)))
+                        .collect()
+                })
+                .collect(),
+            srid: Some(4326),
+        }
+    }
+
+    #[test]
+    fn test_validate_polygon() {
+        let square = polygon(&[
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+            (0.0, 0.0),
+        ]);
+        assert!(square.validate().is_ok());
+
+        let open = polygon(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+        assert!(open.validate().is_err());
+
+        let line = polygon(&[(0.0, 0.0), (10.0, 0.0), (0.0, 0.0)]);
+        assert!(line.validate().is_err());
+
+        let bowtie = polygon(&[
+            (0.0, 0.0),
+            (10.0, 10.0),
+            (10.0, 0.0),
+            (0.0, 10.0),
+            (0.0, 0.0),
+        ]);
+        assert!(bowtie.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_polygon_with_holes() {
+        let shell = [
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+            (0.0, 0.0),
+        ];
+
+        let inside = polygon_with_holes(&[
+            &shell,
+            &[(2.0, 2.0), (4.0, 2.0), (4.0, 4.0), (2.0, 4.0), (2.0, 2.0)],
+        ]);
+        assert!(inside.validate().is_ok());
+
+        let outside = polygon_with_holes(&[
+            &shell,
+            &[
+                (12.0, 2.0),
+                (14.0, 2.0),
+                (14.0, 4.0),
+                (12.0, 4.0),
+                (12.0, 2.0),
+            ],
+        ]);
+        assert!(outside.validate().is_err());
+
+        let around = polygon_with_holes(&[
+            &shell,
+            &[
+                (-2.0, -2.0),
+                (12.0, -2.0),
+                (12.0, 12.0),
+                (-2.0, 12.0),
+                (-2.0, -2.0),
+            ],
+        ]);
+        assert!(around.validate().is_err());
+
+        let crossing = polygon_with_holes(&[
+            &shell,
+            &[(8.0, 2.0), (12.0, 2.0), (12.0, 4.0), (8.0, 4.0), (8.0, 2.0)],
+        ]);
+        assert!(crossing.validate().is_err());
+
+        let touching = polygon_with_holes(&[
+            &shell,
+            &[(0.0, 2.0), (4.0, 2.0), (4.0, 4.0), (0.0, 4.0), (0.0, 2.0)],
+        ]);
+        assert!(touching.validate().is_err());
+    }
 }
@@ -1,142 +1,915 @@
-//! This module contains the Server-Sent Events broadcaster, which is responsible for keeping track of connected clients and broadcasting messages to them.
+//! This module contains the Server-Sent Events broadcaster.
+//!
+//! The broadcaster is responsible for keeping track of connected clients and broadcasting messages to them.
 //! For broadcasting, the broadcaster takes a `map_id` and an `Action` and broadcasts the action to all clients connected to that map.
+//! All actions are stored in the database, so clients reconnecting with a `Last-Event-ID` can catch up on actions they missed,
+//! no matter which instance they were connected to before.
+//! If multiple backend instances are running, broadcasts are shared between them via [`PubSub`].
+//! Clients can subscribe to a subset of the layers of a map, actions changing other layers are not sent to them.
+//!
+//! Every map has its own lock, so broadcasts on different maps don't block each other.
+//! Events are sent without waiting for the client.
+//! Clients that can't receive an event, because they disconnected or fell too far behind, are removed immediately.
+//! A client that fell behind reconnects and catches up using its `Last-Event-ID`.
+//! Missed actions are loaded without holding the lock of the map,
+//! actions broadcast in the meantime are queued and sent after them.
+//! Clients are also removed as soon as their connection is closed.
+//!
+//! The clients of all instances are stored in the database, so every instance knows which users are present on a map.
+//! Each instance regularly extends the time its clients count as connected, so clients of instances that stopped expire.
 
-use actix_web_lab::sse::{self, ChannelStream, Sse};
-use futures::{future::ready, stream, StreamExt};
-use std::{collections::HashMap, sync::Arc, time::Duration};
-use tokio::{sync::Mutex, time::interval};
+use actix_web_lab::sse::{self, Sse};
+use futures::Stream;
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::{
+    sync::{
+        mpsc::{self, error::SendError, Receiver, Sender, UnboundedReceiver, UnboundedSender},
+        Mutex,
+    },
+    time::interval,
+};
 
-use crate::model::dto::actions::Action;
+use uuid::Uuid;
 
-/// Map that clients are connected to.
+use crate::{
+    db::connection::Pool,
+    model::{
+        dto::actions::{Action, UserPresenceActionPayload},
+        entity::{MapClient, MapEvent, NewMapEvent},
+    },
+};
+
+use super::pubsub::{Notification, PubSub, Received};
+
+/// Maximum number of missed actions sent to a client that reconnects.
+/// Clients that missed more have to reload the map.
+pub const EVENT_BUFFER_SIZE: usize = 100;
+
+/// Name of the event telling a client that it missed too many actions and has to reload the map.
+pub const RESYNC_EVENT: &str = "resync";
+
+/// Interval in which clients are pinged to find the ones that disconnected, if not configured otherwise.
+pub const DEFAULT_PING_INTERVAL: Duration = Duration::from_mins(10);
+
+/// Interval in which a comment is sent to idle clients, so closed connections are noticed.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Interval in which the time the clients of this instance count as connected is extended.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Seconds a client counts as connected without being extended by its instance.
+const CLIENT_TIMEOUT_SECONDS: i32 = 90;
+
+/// Number of clients connected to this instance.
+#[derive(Debug, Clone, Copy)]
+pub struct Metrics {
+    /// The number of maps with connected clients.
+    pub maps: usize,
+    /// The number of connected clients.
+    pub clients: usize,
+}
+
+/// Client connected to a map.
 #[derive(Debug, Clone)]
+struct Client {
+    /// Id chosen by the client to identify its connection.
+    id: Uuid,
+    /// Id of the user the client belongs to.
+    user_id: Uuid,
+    /// Id of the connection, changes whenever the client reconnects.
+    connection_id: Uuid,
+    /// The layers the client subscribed to.
+    /// If `None`, the client receives the actions of all layers.
+    layer_ids: Option<Vec<i32>>,
+    /// The id of the latest event sent to the client again when it reconnected.
+    /// Broadcasts of events up to this one are not sent twice.
+    replayed_until: i64,
+    /// Events broadcast while the missed events of a reconnecting client are loaded, with their ids.
+    /// They are sent after the missed events.
+    /// If `None`, events are sent to the client immediately.
+    queued: Option<Vec<(Option<i64>, sse::Event)>>,
+    /// Sender for events to the client.
+    sender: Sender<sse::Event>,
+}
+
+impl Client {
+    /// Check if the client wants to receive an action changing the layers `layer_ids`.
+    /// Actions concerning the whole map are sent to every client.
+    fn is_subscribed(&self, layer_ids: Option<&[i32]>) -> bool {
+        match (&self.layer_ids, layer_ids) {
+            (Some(subscribed), Some(changed)) => changed.iter().any(|id| subscribed.contains(id)),
+            _ => true,
+        }
+    }
+
+    /// Send the event without waiting for the client.
+    /// While the client catches up on missed events, the event is queued instead.
+    ///
+    /// Returns false if the event could not be sent.
+    fn send(&mut self, event: &sse::Event, event_id: Option<i64>) -> bool {
+        match &mut self.queued {
+            Some(queued) => {
+                queued.push((event_id, event.clone()));
+                true
+            }
+            None => self.sender.try_send(event.clone()).is_ok(),
+        }
+    }
+
+    /// Send the missed events the client is subscribed to, or a [`RESYNC_EVENT`] if they are not known anymore,
+    /// followed by the queued events it did not receive yet.
+    /// Afterwards events are sent to the client immediately.
+    ///
+    /// Returns false if the events could not be sent.
+    fn catch_up(&mut self, missed_events: Option<Vec<MapEvent>>) -> bool {
+        let queued = self.queued.take().unwrap_or_default();
+        let mut events = Vec::new();
+        match missed_events {
+            Some(missed_events) => {
+                for event in missed_events {
+                    if self.is_subscribed(event.layer_ids.as_deref()) {
+                        events.push(sse::Data::new(event.data).id(event.id.to_string()).into());
+                    }
+                    self.replayed_until = event.id;
+                }
+            }
+            None => events.push(resync_event()),
+        }
+        let replayed_until = self.replayed_until;
+        events.extend(
+            queued
+                .into_iter()
+                .filter(|(id, _)| id.is_none_or(|id| id > replayed_until))
+                .map(|(_, event)| event),
+        );
+        events
+            .into_iter()
+            .all(|event| self.sender.try_send(event).is_ok())
+    }
+}
+
+/// Client removed from a map.
+#[derive(Debug, Clone, Copy)]
+struct RemovedClient {
+    /// Id of the user the client belongs to.
+    user_id: Uuid,
+    /// Id of the connection of the client.
+    connection_id: Uuid,
+}
+
+impl From<&Client> for RemovedClient {
+    fn from(client: &Client) -> Self {
+        Self {
+            user_id: client.user_id,
+            connection_id: client.connection_id,
+        }
+    }
+}
+
+/// Map that clients are connected to.
+#[derive(Debug, Default)]
 struct ConnectedMap {
-    /// Id of the map that the clients are connected to.
-    map_id: i32,
     /// List of clients connected to the map.
-    clients: Vec<sse::Sender>,
+    clients: Vec<Client>,
+}
+
+impl ConnectedMap {
+    /// Send the event with id `event_id` to all clients matching `filter` without waiting for them.
+    /// Clients the event could not be sent to are removed.
+    ///
+    /// Returns the removed clients.
+    fn send(
+        &mut self,
+        event: &sse::Event,
+        event_id: Option<i64>,
+        filter: impl Fn(&Client) -> bool,
+    ) -> Vec<RemovedClient> {
+        let mut removed = Vec::new();
+        self.clients.retain_mut(|client| {
+            if !filter(client) || client.send(event, event_id) {
+                return true;
+            }
+            removed.push((&*client).into());
+            false
+        });
+        removed
+    }
+}
+
+/// Stream of the events sent to a client.
+///
+/// The stream is dropped when the connection to the client is closed,
+/// which removes the client from the broadcaster.
+#[derive(Debug)]
+pub struct ClientStream {
+    /// The events sent to the client.
+    events: Receiver<sse::Event>,
+    /// The map the client is connected to.
+    map_id: i32,
+    /// Id of the connection of the client.
+    connection_id: Uuid,
+    /// Notifies the broadcaster about the closed connection.
+    disconnected: UnboundedSender<(i32, Uuid)>,
 }
 
-#[derive(Debug, Clone, Default)]
+impl Stream for ClientStream {
+    type Item = Result<sse::Event, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_recv(cx).map(|event| event.map(Ok))
+    }
+}
+
+impl Drop for ClientStream {
+    fn drop(&mut self) {
+        // Fails only if the broadcaster is gone, then there is nothing to clean up.
+        let _ = self.disconnected.send((self.map_id, self.connection_id));
+    }
+}
+
+#[derive(Clone)]
 /// SSE broadcaster.
 ///
 /// Inner `HashMap`:
-/// * Map of `map_id` to a list of connected clients.
-/// * The `map_id` is the id of the map that the client connected to.
-/// * The connected map contains the `map_id` and a list of clients connected to that map.
-pub struct Broadcaster(Arc<Mutex<HashMap<i32, ConnectedMap>>>);
+/// * Map of `map_id` to the clients connected to that map.
+/// * Each connected map has its own lock.
+///   The lock of the `HashMap` has to be acquired first if both are needed.
+pub struct Broadcaster {
+    /// The maps clients of this instance are connected to.
+    maps: Arc<Mutex<HashMap<i32, Arc<Mutex<ConnectedMap>>>>>,
+    /// Connection pool to the database the broadcast actions are stored in.
+    pool: Pool,
+    /// Shares broadcasts with other instances.
+    /// If `None`, broadcasts only reach clients connected to this instance.
+    pubsub: Option<Arc<PubSub>>,
+    /// Receives the connections of clients that were closed.
+    disconnected: UnboundedSender<(i32, Uuid)>,
+}
 
 impl Broadcaster {
-    /// Constructs new broadcaster and spawns ping loop.
+    /// Constructs new broadcaster and spawns ping loop pinging clients every `ping_interval`.
+    /// Broadcasts only reach clients connected to this instance.
     #[must_use]
-    pub fn new() -> Self {
-        let broadcaster = Self::default();
-        Self::spawn_ping(broadcaster.clone());
+    pub fn new(pool: Pool, ping_interval: Duration) -> Self {
+        Self::start(pool, None, ping_interval)
+    }
+
+    /// Constructs new broadcaster sharing broadcasts with all other instances connected to the database.
+    /// Clients are pinged every `ping_interval`.
+    ///
+    /// # Errors
+    /// * If the connection to the database could not be established.
+    pub async fn with_pubsub(
+        pool: Pool,
+        database_url: &str,
+        ping_interval: Duration,
+    ) -> Result<Self, tokio_postgres::Error> {
+        let (pubsub, mut notifications) = PubSub::connect(database_url).await?;
+        let broadcaster = Self::start(pool, Some(pubsub), ping_interval);
+
+        let receiver = broadcaster.clone();
+        actix_web::rt::spawn(async move {
+            while let Some(received) = notifications.recv().await {
+                match received {
+                    Received::Notification(map_id, Notification::Event { id }) => {
+                        receiver.send_stored_event(map_id, id).await;
+                    }
+                    Received::Notification(
+                        map_id,
+                        Notification::Subscription { client_id, user_id },
+                    ) => {
+                        receiver.load_subscription(map_id, client_id, user_id).await;
+                    }
+                    Received::Reconnected => {
+                        receiver.send_to_all_local_clients(resync_event()).await;
+                    }
+                }
+            }
+        });
+
+        Ok(broadcaster)
+    }
+
+    /// Constructs new broadcaster and spawns its background tasks.
+    fn start(pool: Pool, pubsub: Option<Arc<PubSub>>, ping_interval: Duration) -> Self {
+        let (disconnected, disconnections) = mpsc::unbounded_channel();
+        let broadcaster = Self {
+            maps: Arc::default(),
+            pool,
+            pubsub,
+            disconnected,
+        };
+        Self::spawn_ping(broadcaster.clone(), ping_interval);
+        Self::spawn_heartbeat(broadcaster.clone());
+        Self::spawn_disconnect(broadcaster.clone(), disconnections);
         broadcaster
     }
 
-    /// Pings clients every 10 minutes to see if they are alive and remove them from the broadcast list if not.
-    fn spawn_ping(self) {
+    /// Pings clients every `ping_interval` to see if they are alive and remove them from the broadcast list if not.
+    fn spawn_ping(self, ping_interval: Duration) {
         actix_web::rt::spawn(async move {
-            let mut interval = interval(Duration::from_secs(600));
+            let mut interval = interval(ping_interval);
             loop {
                 interval.tick().await;
-                self.clone().remove_stale_clients().await;
+                self.remove_stale_clients().await;
+
+                let metrics = self.metrics().await;
+                log::info!(
+                    "{} SSE clients connected to {} maps",
+                    metrics.clients,
+                    metrics.maps
+                );
             }
         });
     }
 
+    /// Extends the time the clients of this instance count as connected every [`HEARTBEAT_INTERVAL`].
+    fn spawn_heartbeat(self) {
+        actix_web::rt::spawn(async move {
+            let mut interval = interval(HEARTBEAT_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(err) = self.extend_clients().await {
+                    log::error!("Failed to extend connected clients: {}", err.to_string());
+                }
+            }
+        });
+    }
+
+    /// Removes the clients whose connection was closed.
+    fn spawn_disconnect(self, mut disconnections: UnboundedReceiver<(i32, Uuid)>) {
+        actix_web::rt::spawn(async move {
+            while let Some((map_id, connection_id)) = disconnections.recv().await {
+                self.disconnect(map_id, connection_id).await;
+            }
+        });
+    }
+
+    /// Extends the time all clients of this instance count as connected.
+    ///
+    /// # Errors
+    /// * If the connection to the database could not be established.
+    /// * If the query failed.
+    async fn extend_clients(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut connection_ids = Vec::new();
+        for (_, map) in self.connected_maps().await {
+            connection_ids.extend(
+                map.lock()
+                    .await
+                    .clients
+                    .iter()
+                    .map(|client| client.connection_id),
+            );
+        }
+        if connection_ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.pool.get().await?;
+        let _ = MapClient::extend(&connection_ids, CLIENT_TIMEOUT_SECONDS, &mut conn).await?;
+        Ok(())
+    }
+
+    /// Removes the client of the closed connection.
+    async fn disconnect(&self, map_id: i32, connection_id: Uuid) {
+        let Some(map) = self.connected_map(map_id).await else {
+            return;
+        };
+        let mut map = map.lock().await;
+        // The client might already be removed because an event could not be sent to it.
+        let Some(index) = map
+            .clients
+            .iter()
+            .position(|client| client.connection_id == connection_id)
+        else {
+            return;
+        };
+        let client = map.clients.remove(index);
+        drop(map);
+
+        self.handle_removed_clients(map_id, vec![(&client).into()])
+            .await;
+    }
+
     /// Removes all non-responsive clients from broadcast list.
-    /// TODO: this is a naive implementation, we should probably use a better data structure for this.
-    ///       Things to consider:
-    ///        - how can we do this without having to iterate over all clients?
+    ///
+    /// Clients are also removed as soon as an action can't be sent to them,
+    /// so this only finds clients of maps without recent actions.
     async fn remove_stale_clients(&self) {
-        let mut guard = self.0.lock().await;
-
-        let mut ok_maps = HashMap::with_capacity(guard.capacity());
-
-        stream::iter(guard.values())
-            .map(|map| async move {
-                (
-                    map,
-                    stream::iter(&map.clients)
-                        .filter(|client| async {
-                            client
-                                .send(sse::Event::Comment("ping".into()))
-                                .await
-                                .is_ok()
-                        })
-                        .map(|client| ready(client.clone()))
-                        .buffer_unordered(15)
-                        .collect::<Vec<_>>()
-                        .await,
-                )
-            })
-            .buffer_unordered(100)
-            .filter(|(_, ok_clients)| ready(!ok_clients.is_empty()))
-            .for_each(|(map, ok_clients)| {
-                ok_maps.insert(
-                    map.map_id,
-                    ConnectedMap {
-                        map_id: map.map_id,
-                        clients: ok_clients,
-                    },
-                );
-                ready(())
-            })
+        self.send_to_all_local_clients(sse::Event::Comment("ping".into()))
             .await;
+    }
+
+    /// Sends the event to all clients connected to this instance, no matter which map they are connected to.
+    async fn send_to_all_local_clients(&self, event: sse::Event) {
+        for (map_id, map) in self.connected_maps().await {
+            let removed = map.lock().await.send(&event, None, |_| true);
+            self.handle_removed_clients(map_id, removed).await;
+        }
+    }
+
+    /// Cleans up after clients were removed from the map.
+    /// Broadcasts [`Action::UserLeft`] for users without any remaining client on the map.
+    ///
+    /// Must not be called while holding any lock of the broadcaster.
+    async fn handle_removed_clients(&self, map_id: i32, removed: Vec<RemovedClient>) {
+        if removed.is_empty() {
+            return;
+        }
+
+        if let Some(pubsub) = &self.pubsub {
+            for _ in &removed {
+                pubsub.unlisten(map_id).await;
+            }
+        }
+        self.remove_map_if_empty(map_id).await;
+        for client in removed {
+            match self.leave(map_id, client).await {
+                Ok(true) => {
+                    self.broadcast(
+                        map_id,
+                        Action::UserLeft(UserPresenceActionPayload::new(client.user_id)),
+                    )
+                    .await;
+                }
+                Ok(false) => {}
+                Err(err) => log::error!("Failed to disconnect client: {}", err.to_string()),
+            }
+        }
+    }
+
+    /// Marks the removed client as disconnected in the database.
+    ///
+    /// Returns whether the user has no client left on the map on any instance.
+    ///
+    /// # Errors
+    /// * If the connection to the database could not be established.
+    /// * If the query failed.
+    async fn leave(
+        &self,
+        map_id: i32,
+        client: RemovedClient,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let _ = MapClient::disconnect(client.connection_id, &mut conn).await?;
+        Ok(!MapClient::is_connected(map_id, client.user_id, &mut conn).await?)
+    }
+
+    /// Stops keeping track of the map if no clients are connected to it anymore.
+    async fn remove_map_if_empty(&self, map_id: i32) {
+        let mut guard = self.maps.lock().await;
 
-        *guard = ok_maps;
+        let is_empty = match guard.get(&map_id) {
+            Some(map) => map.lock().await.clients.is_empty(),
+            None => false,
+        };
+        if !is_empty {
+            return;
+        }
+
+        let _ = guard.remove(&map_id);
+    }
+
+    /// Get all maps with connected clients.
+    async fn connected_maps(&self) -> Vec<(i32, Arc<Mutex<ConnectedMap>>)> {
+        self.maps
+            .lock()
+            .await
+            .iter()
+            .map(|(map_id, map)| (*map_id, Arc::clone(map)))
+            .collect()
     }
 
-    /// Registers client with broadcaster, returning an SSE response body.
+    /// Get the map if clients are connected to it.
+    async fn connected_map(&self, map_id: i32) -> Option<Arc<Mutex<ConnectedMap>>> {
+        self.maps.lock().await.get(&map_id).map(Arc::clone)
+    }
+
+    /// Registers client of the user with broadcaster, returning an SSE response body.
+    /// The client is subscribed to all layers of the map, unless it was connected before with the same `client_id`.
+    /// Then it keeps its subscription and a client still connected with that id is replaced.
+    ///
+    /// If `last_event_id` is given, all actions broadcast after it that the client is subscribed to
+    /// are sent to the client again.
+    /// If the action is no longer known or too many actions were missed, a [`RESYNC_EVENT`] is sent instead.
+    ///
+    /// Broadcasts [`Action::UserJoined`] if the user had no other client on the map.
     ///
     /// # Errors
-    /// * If sender.send() fails for the new client.
+    /// * If the connection to the database could not be established.
+    /// * If the client could not be stored.
+    /// * If an event could not be sent to the new client.
     pub async fn new_client(
         &self,
         map_id: i32,
-    ) -> Result<Sse<ChannelStream>, Box<dyn std::error::Error>> {
-        let (sender, channel_stream) = sse::channel(100);
-        let mut guard = self.0.lock().await;
+        client_id: Uuid,
+        user_id: Uuid,
+        last_event_id: Option<&str>,
+    ) -> Result<Sse<ClientStream>, Box<dyn std::error::Error>> {
+        let connection_id = Uuid::new_v4();
+        // Listening might take a while, so it must not block other maps.
+        if let Some(pubsub) = &self.pubsub {
+            pubsub.listen(map_id).await;
+        }
+
+        let (is_new_user, stored) = match self
+            .register(map_id, user_id, client_id, connection_id)
+            .await
+        {
+            Ok(registered) => registered,
+            Err(err) => {
+                if let Some(pubsub) = &self.pubsub {
+                    pubsub.unlisten(map_id).await;
+                }
+                return Err(err);
+            }
+        };
+
+        let (sender, events) = mpsc::channel(EVENT_BUFFER_SIZE + 2);
+        let client = Client {
+            id: client_id,
+            user_id,
+            connection_id,
+            layer_ids: stored.layer_ids,
+            replayed_until: 0,
+            queued: None,
+            sender,
+        };
+        let removed = match self.add_client(map_id, client, last_event_id).await {
+            Ok(removed) => removed,
+            Err(send_err) => {
+                if let Some(pubsub) = &self.pubsub {
+                    pubsub.unlisten(map_id).await;
+                }
+                self.remove_map_if_empty(map_id).await;
+                if let Err(err) = self.unregister(connection_id).await {
+                    log::error!("Failed to disconnect client: {}", err.to_string());
+                }
+                return Err(send_err.into());
+            }
+        };
+        self.handle_removed_clients(map_id, removed).await;
+
+        if is_new_user {
+            self.broadcast(
+                map_id,
+                Action::UserJoined(UserPresenceActionPayload::new(user_id)),
+            )
+            .await;
+        }
 
-        let map = guard.entry(map_id).or_insert_with(|| ConnectedMap {
+        let stream = ClientStream {
+            events,
             map_id,
-            clients: Vec::new(),
+            connection_id,
+            disconnected: self.disconnected.clone(),
+        };
+        Ok(Sse::from_stream(stream).with_keep_alive(KEEP_ALIVE_INTERVAL))
+    }
+
+    /// Stores the new connection of the client in the database.
+    ///
+    /// Returns whether the user had no client connected to the map before and the stored client.
+    ///
+    /// # Errors
+    /// * If the connection to the database could not be established.
+    /// * If the query failed.
+    async fn register(
+        &self,
+        map_id: i32,
+        user_id: Uuid,
+        client_id: Uuid,
+        connection_id: Uuid,
+    ) -> Result<(bool, MapClient), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let is_new_user = !MapClient::is_connected(map_id, user_id, &mut conn).await?;
+        let stored = MapClient::connect(
+            map_id,
+            user_id,
+            client_id,
+            connection_id,
+            CLIENT_TIMEOUT_SECONDS,
+            &mut conn,
+        )
+        .await?;
+        Ok((is_new_user, stored))
+    }
+
+    /// Marks the connection as disconnected in the database.
+    ///
+    /// # Errors
+    /// * If the connection to the database could not be established.
+    /// * If the query failed.
+    async fn unregister(
+        &self,
+        connection_id: Uuid,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        let _ = MapClient::disconnect(connection_id, &mut conn).await?;
+        Ok(())
+    }
+
+    /// Adds the client to the map, replacing a client of the user with the same id.
+    /// If `last_event_id` is given, the client catches up on the events it missed, see [`Self::replay`].
+    ///
+    /// Returns the replaced client and the new client if the missed events could not be sent to it.
+    ///
+    /// # Errors
+    /// * If the first event could not be sent to the client.
+    async fn add_client(
+        &self,
+        map_id: i32,
+        mut client: Client,
+        last_event_id: Option<&str>,
+    ) -> Result<Vec<RemovedClient>, SendError<sse::Event>> {
+        client
+            .sender
+            .send(sse::Data::new("connected").into())
+            .await?;
+        let connection_id = client.connection_id;
+        if last_event_id.is_some() {
+            client.queued = Some(Vec::new());
+        }
+
+        let mut guard = self.maps.lock().await;
+        let map = Arc::clone(guard.entry(map_id).or_default());
+        let mut map = map.lock().await;
+        // The map must not be removed before the client is added.
+        drop(guard);
+        let mut removed = Vec::new();
+        map.clients.retain(|connected| {
+            if connected.id != client.id || connected.user_id != client.user_id {
+                return true;
+            }
+            removed.push(connected.into());
+            false
         });
+        map.clients.push(client);
+        drop(map);
 
-        sender.send(sse::Data::new("connected")).await?;
+        if let Some(last_event_id) = last_event_id {
+            removed.extend(self.replay(map_id, connection_id, last_event_id).await);
+        }
+        Ok(removed)
+    }
 
-        map.clients.push(sender);
+    /// Sends the actions broadcast on the map after `last_event_id` that the client is subscribed to again.
+    /// If they are not known anymore, a [`RESYNC_EVENT`] is sent instead.
+    ///
+    /// The actions are loaded without holding the lock of the map.
+    /// Actions broadcast in the meantime are queued by the client and sent afterwards.
+    ///
+    /// Returns the client if it was removed because the actions could not be sent to it.
+    async fn replay(
+        &self,
+        map_id: i32,
+        connection_id: Uuid,
+        last_event_id: &str,
+    ) -> Option<RemovedClient> {
+        let missed_events = match self.find_missed_events(map_id, last_event_id).await {
+            Ok(missed_events) => missed_events,
+            Err(err) => {
+                log::error!("Failed to load missed events: {}", err.to_string());
+                None
+            }
+        };
 
-        Ok(channel_stream)
+        let map = self.connected_map(map_id).await?;
+        let mut map = map.lock().await;
+        // The client might already be removed, e.g. because its connection was closed.
+        let index = map
+            .clients
+            .iter()
+            .position(|client| client.connection_id == connection_id)?;
+        if map.clients.get_mut(index)?.catch_up(missed_events) {
+            return None;
+        }
+        let client = map.clients.remove(index);
+        drop(map);
+        Some((&client).into())
     }
 
-    /// Broadcasts `msg` to all clients on the same map.
-    pub async fn broadcast(&self, map_id: i32, action: Action) {
-        let action_id = action.action_id().to_string();
-
-        match sse::Data::new_json(action) {
-            Ok(mut serialized_action) => {
-                let guard = self.0.lock().await;
-
-                serialized_action.set_id(action_id);
-
-                if let Some(map) = guard.get(&map_id) {
-                    // try to send to all clients, ignoring failures
-                    // disconnected clients will get swept up by `remove_stale_clients`
-                    let _ = stream::iter(&map.clients)
-                        .map(|client| client.send(serialized_action.clone()))
-                        .buffer_unordered(15)
-                        .collect::<Vec<_>>()
-                        .await;
-                }
+    /// Get the events broadcast on the map after the event with id `last_event_id`, oldest first.
+    ///
+    /// Returns `None` if the event is not stored anymore or more than [`EVENT_BUFFER_SIZE`] events were missed.
+    ///
+    /// # Errors
+    /// * If the connection to the database could not be established.
+    /// * If the query failed.
+    async fn find_missed_events(
+        &self,
+        map_id: i32,
+        last_event_id: &str,
+    ) -> Result<Option<Vec<MapEvent>>, Box<dyn std::error::Error>> {
+        let Ok(last_event_id) = last_event_id.parse::<i64>() else {
+            return Ok(None);
+        };
+        // The last event is loaded as well to check that no events were removed in between.
+        let limit = i64::try_from(EVENT_BUFFER_SIZE + 2)?;
+        let mut conn = self.pool.get().await?;
+        let mut events =
+            MapEvent::find_starting_at(map_id, last_event_id, limit, &mut conn).await?;
+        drop(conn);
+
+        if events.first().map(|event| event.id) != Some(last_event_id)
+            || events.len() > EVENT_BUFFER_SIZE + 1
+        {
+            return Ok(None);
+        }
+        let _ = events.remove(0);
+        Ok(Some(events))
+    }
+
+    /// Makes the client of the user receive actions for the layers it subscribed to.
+    /// The subscription has to be stored in the database before,
+    /// it is loaded by the instance the client is connected to.
+    pub async fn reload_subscription(&self, map_id: i32, client_id: Uuid, user_id: Uuid) {
+        if let Some(pubsub) = &self.pubsub {
+            let notification = Notification::Subscription { client_id, user_id };
+            match pubsub.publish(map_id, &notification).await {
+                Ok(()) => return,
+                Err(err) => log::error!(
+                    "Failed to publish subscription, only local clients are updated: {}",
+                    err.to_string()
+                ),
             }
+        }
+
+        self.load_subscription(map_id, client_id, user_id).await;
+    }
+
+    /// Loads the stored subscription of the client if it is connected to this instance.
+    async fn load_subscription(&self, map_id: i32, client_id: Uuid, user_id: Uuid) {
+        let Some(map) = self.connected_map(map_id).await else {
+            return;
+        };
+        let is_connected = map
+            .lock()
+            .await
+            .clients
+            .iter()
+            .any(|client| client.id == client_id && client.user_id == user_id);
+        if !is_connected {
+            return;
+        }
+
+        let stored = match self.find_client(map_id, client_id, user_id).await {
+            Ok(stored) => stored,
+            Err(err) => {
+                log::error!("Failed to load subscription: {}", err.to_string());
+                return;
+            }
+        };
+        for client in map
+            .lock()
+            .await
+            .clients
+            .iter_mut()
+            .filter(|client| client.id == client_id && client.user_id == user_id)
+        {
+            client.layer_ids.clone_from(&stored.layer_ids);
+        }
+    }
+
+    /// Fetches the stored client from the database.
+    ///
+    /// # Errors
+    /// * If the connection to the database could not be established.
+    /// * If the query failed.
+    async fn find_client(
+        &self,
+        map_id: i32,
+        client_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<MapClient, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        Ok(MapClient::find_by_id(map_id, user_id, client_id, &mut conn).await?)
+    }
+
+    /// Count the maps and clients connected to this instance.
+    pub async fn metrics(&self) -> Metrics {
+        let maps = self.connected_maps().await;
+
+        let mut clients = 0;
+        for (_, map) in &maps {
+            clients += map.lock().await.clients.len();
+        }
+        Metrics {
+            maps: maps.len(),
+            clients,
+        }
+    }
+
+    /// Broadcasts `msg` to all clients on the same map subscribed to the layers it changes.
+    ///
+    /// The action is stored first, so clients that miss it can catch up when they reconnect.
+    pub async fn broadcast(&self, map_id: i32, action: Action) {
+        let data = match serde_json::to_string(&action) {
+            Ok(data) => data,
             Err(err) => {
                 // log the error and continue
                 // serialization errors are also highly unlikely to happen
                 log::error!("{}", err.to_string());
+                return;
+            }
+        };
+        let event = NewMapEvent {
+            map_id,
+            data,
+            layer_ids: action.layer_ids(),
+        };
+        let event = match self.store(event).await {
+            Ok(event) => event,
+            Err(err) => {
+                log::error!("Failed to store broadcast: {}", err.to_string());
+                return;
             }
         };
+        if let Some(pubsub) = &self.pubsub {
+            // the notification is also received by this instance, which then sends it to its clients
+            match pubsub
+                .publish(map_id, &Notification::Event { id: event.id })
+                .await
+            {
+                Ok(()) => return,
+                Err(err) => log::error!(
+                    "Failed to publish broadcast, only local clients are notified: {}",
+                    err.to_string()
+                ),
+            }
+        }
+
+        self.send_to_local_clients(map_id, event).await;
     }
+
+    /// Stores the event in the database.
+    ///
+    /// # Errors
+    /// * If the connection to the database could not be established.
+    /// * If the query failed.
+    async fn store(&self, event: NewMapEvent) -> Result<MapEvent, Box<dyn std::error::Error>> {
+        let mut conn = self.pool.get().await?;
+        Ok(MapEvent::create(event, &mut conn).await?)
+    }
+
+    /// Loads the event broadcast by any instance and sends it to the clients connected to this instance.
+    async fn send_stored_event(&self, map_id: i32, event_id: i64) {
+        if self.connected_map(map_id).await.is_none() {
+            return;
+        }
+        match self.find_event(event_id).await {
+            Ok(event) => self.send_to_local_clients(map_id, event).await,
+            Err(err) => log::error!("Failed to load broadcast: {}", err.to_string()),
+        }
+    }
+
+    /// Fetches the stored event from the database.
+    ///
+    /// # Errors
+    /// * If the connection to the database could not be established.
+    /// * If the query failed.
+    async fn find_event(
+        &self,
+        event_id: i64,
+    ) -> Result<MapEvent, Box<dyn std::error::Error + Send + Sync>> {
+        let mut conn = self.pool.get().await?;
+        Ok(MapEvent::find_by_id(event_id, &mut conn).await?)
+    }
+
+    /// Sends the event to all subscribed clients on the same map connected to this instance.
+    async fn send_to_local_clients(&self, map_id: i32, event: MapEvent) {
+        let Some(map) = self.connected_map(map_id).await else {
+            return;
+        };
+        let event_id = event.id;
+        let layer_ids = event.layer_ids.as_deref();
+        let data = sse::Data::new(event.data.clone()).id(event_id.to_string());
+
+        let removed = map
+            .lock()
+            .await
+            .send(&data.into(), Some(event_id), |client| {
+                client.replayed_until < event_id && client.is_subscribed(layer_ids)
+            });
+
+        if !removed.is_empty() {
+            // Spawned, as the cleanup broadcasts again.
+            let broadcaster = self.clone();
+            actix_web::rt::spawn(async move {
+                broadcaster.handle_removed_clients(map_id, removed).await;
+            });
+        }
+    }
+}
+
+/// Create the event telling a client that it has to reload the map.
+fn resync_event() -> sse::Event {
+    sse::Data::new("resync required").event(RESYNC_EVENT).into()
 }
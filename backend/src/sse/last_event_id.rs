@@ -0,0 +1,27 @@
+//! Contains [`LastEventId`] which stores the id of the last event a reconnecting client received.
+
+use std::convert::Infallible;
+
+use actix_utils::future::{ready, Ready};
+use actix_web::FromRequest;
+
+/// The content of the `Last-Event-ID` header sent by clients reconnecting to the event stream.
+#[derive(Debug, Clone, Default)]
+pub struct LastEventId(pub Option<String>);
+
+impl FromRequest for LastEventId {
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Error = Infallible;
+
+    fn from_request(
+        req: &actix_web::HttpRequest,
+        _payload: &mut actix_http::Payload,
+    ) -> Self::Future {
+        let last_event_id = req
+            .headers()
+            .get("Last-Event-ID")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+        ready(Ok(Self(last_event_id)))
+    }
+}
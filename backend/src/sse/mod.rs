@@ -1,3 +1,5 @@
 //! Sever Sent Events.
 
 pub mod broadcaster;
+pub mod last_event_id;
+pub mod pubsub;
@@ -0,0 +1,266 @@
+//! Shares broadcasts between backend instances using Postgres `LISTEN`/`NOTIFY`.
+//!
+//! Every map has its own channel.
+//! An instance only listens on the channels of maps it has connected clients for.
+//!
+//! Broadcast actions and subscriptions of clients are stored in the database,
+//! notifications only tell the instances where to find them.
+//! This keeps notifications small, Postgres rejects payloads of 8000 bytes or more.
+//!
+//! If the connection to the database is lost, it is re-established with increasing delays
+//! and all channels are listened on again.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{
+    mpsc::{self, UnboundedReceiver, UnboundedSender},
+    Mutex, RwLock,
+};
+use tokio_postgres::{tls::NoTlsStream, AsyncMessage, Client, Connection, NoTls, Socket};
+use uuid::Uuid;
+
+/// Delay before the first attempt to re-establish a lost connection.
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Maximum delay between attempts to re-establish a lost connection.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_mins(1);
+
+/// A message sent between backend instances.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Notification {
+    /// An action was broadcast.
+    Event {
+        /// The id of the stored event.
+        id: i64,
+    },
+    /// A client changed the layers it receives actions for.
+    Subscription {
+        /// The id the client connected with.
+        client_id: Uuid,
+        /// The id of the user the client belongs to.
+        user_id: Uuid,
+    },
+}
+
+/// Messages received from the connection.
+#[derive(Debug)]
+pub enum Received {
+    /// A notification concerning the map with the given id.
+    Notification(i32, Notification),
+    /// The connection was lost and re-established.
+    /// Notifications sent in between are lost.
+    Reconnected,
+}
+
+/// Connection used to publish broadcasts and listen for broadcasts of other instances.
+#[derive(Debug)]
+pub struct PubSub {
+    /// The client of the current connection.
+    client: RwLock<Client>,
+    /// Number of clients per map the channel of the map is listened on for.
+    channels: Mutex<HashMap<i32, usize>>,
+}
+
+impl PubSub {
+    /// Connect to the database.
+    ///
+    /// Returns the connection and a receiver for all notifications on channels listened on.
+    ///
+    /// # Errors
+    /// * If the connection to the database could not be established.
+    pub async fn connect(
+        database_url: &str,
+    ) -> Result<(Arc<Self>, UnboundedReceiver<Received>), tokio_postgres::Error> {
+        let (client, connection) = tokio_postgres::connect(database_url, NoTls).await?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let pubsub = Arc::new(Self {
+            client: RwLock::new(client),
+            channels: Mutex::default(),
+        });
+
+        let weak = Arc::downgrade(&pubsub);
+        let database_url = database_url.to_owned();
+        actix_web::rt::spawn(async move {
+            let mut connection = connection;
+            loop {
+                receive(connection, &sender).await;
+                if sender.is_closed() {
+                    break;
+                }
+                let Some(reconnected) = reconnect(&weak, &database_url).await else {
+                    break;
+                };
+                connection = reconnected;
+                if sender.send(Received::Reconnected).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok((pubsub, receiver))
+    }
+
+    /// Start receiving broadcasts for the map on behalf of one more client.
+    ///
+    /// If the query fails, the channel is listened on once the connection is re-established.
+    pub async fn listen(&self, map_id: i32) {
+        let mut channels = self.channels.lock().await;
+        let count = channels.entry(map_id).or_default();
+        *count += 1;
+        if *count == 1 {
+            if let Err(err) = self
+                .execute(&format!("LISTEN \"{}\"", channel(map_id)))
+                .await
+            {
+                log::error!("Failed to listen for broadcasts: {}", err.to_string());
+            }
+        }
+        // Held until the statement completed, so it can't overtake an `UNLISTEN` for the map.
+        drop(channels);
+    }
+
+    /// Stop receiving broadcasts for the map on behalf of one client.
+    /// The channel is only left once no client needs it anymore.
+    pub async fn unlisten(&self, map_id: i32) {
+        let mut channels = self.channels.lock().await;
+        let Some(count) = channels.get_mut(&map_id) else {
+            return;
+        };
+        *count -= 1;
+        if *count > 0 {
+            return;
+        }
+
+        let _ = channels.remove(&map_id);
+        if let Err(err) = self
+            .execute(&format!("UNLISTEN \"{}\"", channel(map_id)))
+            .await
+        {
+            log::error!(
+                "Failed to stop listening for broadcasts: {}",
+                err.to_string()
+            );
+        }
+        drop(channels);
+    }
+
+    /// Send the notification to all instances listening for the map, including this one.
+    ///
+    /// # Errors
+    /// * If the notification could not be serialized.
+    /// * If the query failed.
+    pub async fn publish(
+        &self,
+        map_id: i32,
+        notification: &Notification,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let payload = serde_json::to_string(notification)?;
+        let _ = self
+            .client
+            .read()
+            .await
+            .execute("SELECT pg_notify($1, $2)", &[&channel(map_id), &payload])
+            .await?;
+        Ok(())
+    }
+
+    /// Execute the statement on the current connection.
+    ///
+    /// # Errors
+    /// * If the query failed.
+    async fn execute(&self, statement: &str) -> Result<(), tokio_postgres::Error> {
+        self.client.read().await.batch_execute(statement).await
+    }
+
+    /// Listen on the channels of all maps again after the connection was re-established.
+    async fn listen_again(&self) {
+        let channels = self.channels.lock().await;
+        for map_id in channels.keys() {
+            if let Err(err) = self
+                .execute(&format!("LISTEN \"{}\"", channel(*map_id)))
+                .await
+            {
+                log::error!("Failed to listen for broadcasts: {}", err.to_string());
+            }
+        }
+    }
+}
+
+/// Forward all notifications received on the connection until it fails or nobody receives them anymore.
+async fn receive(
+    mut connection: Connection<Socket, NoTlsStream>,
+    sender: &UnboundedSender<Received>,
+) {
+    let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+    while let Some(message) = messages.next().await {
+        match message {
+            Ok(AsyncMessage::Notification(notification)) => {
+                let Some(map_id) = map_id(notification.channel()) else {
+                    continue;
+                };
+                match serde_json::from_str::<Notification>(notification.payload()) {
+                    Ok(notification) => {
+                        if sender
+                            .send(Received::Notification(map_id, notification))
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                    Err(err) => log::error!("Received invalid broadcast: {err}"),
+                }
+            }
+            Ok(_) => {}
+            Err(err) => {
+                log::error!("Connection for receiving broadcasts failed: {err}");
+                return;
+            }
+        }
+    }
+    log::error!("Connection for receiving broadcasts was closed");
+}
+
+/// Re-establish the connection, waiting longer after every failed attempt.
+/// The new connection replaces the connection of `pubsub`, which then listens on all channels again.
+///
+/// Returns `None` if `pubsub` was dropped in the meantime.
+async fn reconnect(
+    pubsub: &Weak<PubSub>,
+    database_url: &str,
+) -> Option<Connection<Socket, NoTlsStream>> {
+    let mut delay = MIN_RECONNECT_DELAY;
+    loop {
+        tokio::time::sleep(delay).await;
+        let strong = pubsub.upgrade()?;
+        match tokio_postgres::connect(database_url, NoTls).await {
+            Ok((client, connection)) => {
+                *strong.client.write().await = client;
+                // The connection has to be polled by the caller for the queries to complete.
+                actix_web::rt::spawn(async move { strong.listen_again().await });
+                log::info!("Connection for receiving broadcasts was re-established");
+                return Some(connection);
+            }
+            Err(err) => {
+                log::error!("Failed to re-establish connection for receiving broadcasts: {err}");
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        }
+    }
+}
+
+/// Get the name of the channel of the map.
+fn channel(map_id: i32) -> String {
+    format!("map_{map_id}")
+}
+
+/// Get the id of the map from the name of its channel.
+fn map_id(channel: &str) -> Option<i32> {
+    channel.strip_prefix("map_")?.parse().ok()
+}
@@ -8,7 +8,7 @@ use crate::{
         dto::{BaseLayerImageDto, UpdateBaseLayerImageDto},
         r#enum::{layer_type::LayerType, privacy_option::PrivacyOption},
     },
-    test::util::{init_test_app, init_test_database},
+    test::util::{init_test_app, init_test_app_for_user, init_test_database},
 };
 use actix_web::{
     http::{
@@ -81,7 +81,7 @@ async fn test_find_succeeds() {
         initial_db_values(conn, small_rectangle_with_non_0_xmin()).scope_boxed()
     })
     .await;
-    let (token, app) = init_test_app(pool).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
 
     let resp = test::TestRequest::get()
         .uri("/api/maps/-1/layers/base/-1/images")
@@ -99,13 +99,30 @@ async fn test_find_succeeds() {
     assert_eq!(results.len(), 1);
 }
 
+#[actix_rt::test]
+async fn test_find_fails_for_non_member() {
+    let pool = init_test_database(|conn| {
+        initial_db_values(conn, small_rectangle_with_non_0_xmin()).scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::new_v4()).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/base/-1/images")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
 #[actix_rt::test]
 async fn test_create_succeeds() {
     let pool = init_test_database(|conn| {
         initial_db_values(conn, small_rectangle_with_non_0_xmin()).scope_boxed()
     })
     .await;
-    let (token, app) = init_test_app(pool.clone()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
 
     let resp = test::TestRequest::post()
         .uri("/api/maps/-1/layers/base/images")
@@ -157,7 +174,7 @@ async fn test_delete_by_id_succeeds() {
         initial_db_values(conn, small_rectangle_with_non_0_xmin()).scope_boxed()
     })
     .await;
-    let (token, app) = init_test_app(pool).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
 
     let resp = test::TestRequest::delete()
         .uri(&format!("/api/maps/-1/layers/base/images/{}", Uuid::nil()))
@@ -170,3 +187,72 @@ async fn test_delete_by_id_succeeds() {
 
     assert_eq!(resp.status(), StatusCode::OK);
 }
+
+#[actix_rt::test]
+async fn test_create_fails_for_not_owner() {
+    let pool = init_test_database(|conn| {
+        initial_db_values(conn, small_rectangle_with_non_0_xmin()).scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::new_v4()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/base/images")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(BaseLayerImageDto {
+            id: Uuid::new_v4(),
+            action_id: Uuid::new_v4(),
+            layer_id: -1,
+            path: "/path".to_owned(),
+            rotation: 0.0,
+            scale: 0.0,
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn test_update_fails_for_not_owner() {
+    let pool = init_test_database(|conn| {
+        initial_db_values(conn, small_rectangle_with_non_0_xmin()).scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::new_v4()).await;
+
+    let resp = test::TestRequest::patch()
+        .uri(&format!("/api/maps/-1/layers/base/images/{}", Uuid::nil()))
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(UpdateBaseLayerImageDto {
+            action_id: Uuid::new_v4(),
+            layer_id: -1,
+            path: "/path".to_owned(),
+            rotation: 0.0,
+            scale: 0.0,
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn test_delete_by_id_fails_for_not_owner() {
+    let pool = init_test_database(|conn| {
+        initial_db_values(conn, small_rectangle_with_non_0_xmin()).scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::new_v4()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri(&format!("/api/maps/-1/layers/base/images/{}", Uuid::nil()))
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(DeleteBaseLayerImageDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
@@ -3,10 +3,14 @@
 use crate::{
     error::ServiceError,
     model::{
-        dto::{LayerDto, NewLayerDto},
+        dto::{
+            plantings::PlantingDto, DeleteLayerDto, DuplicateLayerDto, LayerDto,
+            LayerPreferencesDto, NewLayerDto, PromoteLayerDto, RestoreLayerDto, TimelinePage,
+            UpdateLayerDto, UpdateLayerPreferencesDto,
+        },
         r#enum::{layer_type::LayerType, privacy_option::PrivacyOption},
     },
-    test::util::{init_test_app, init_test_database},
+    test::util::{data, init_test_app, init_test_app_for_user, init_test_database},
 };
 use actix_web::{
     http::{
@@ -64,7 +68,7 @@ async fn initial_db_values(conn: &mut AsyncPgConnection) -> Result<(), ServiceEr
 #[actix_rt::test]
 async fn test_find_layers_succeeds() {
     let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
-    let (token, app) = init_test_app(pool).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
 
     let resp = test::TestRequest::get()
         .uri("/api/maps/-1/layers")
@@ -85,7 +89,7 @@ async fn test_find_layers_succeeds() {
 #[actix_rt::test]
 async fn test_find_layer_by_id_succeeds() {
     let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
-    let (token, app) = init_test_app(pool).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
 
     let resp = test::TestRequest::get()
         .uri("/api/maps/-1/layers/-1")
@@ -106,10 +110,38 @@ async fn test_find_layer_by_id_succeeds() {
     assert_eq!(dto.id, -1);
 }
 
+#[actix_rt::test]
+async fn test_find_layers_fails_for_non_member() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::new_v4()).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn test_find_layer_by_id_fails_for_non_member() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::new_v4()).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/-1")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
 #[actix_rt::test]
 async fn test_create_layer_succeeds() {
     let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
-    let (token, app) = init_test_app(pool.clone()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
 
     let resp = test::TestRequest::post()
         .uri("/api/maps/-1/layers")
@@ -119,6 +151,7 @@ async fn test_create_layer_succeeds() {
             type_: LayerType::Base,
             name: "MyBaseLayer".to_string(),
             is_alternative: false,
+            action_id: Uuid::new_v4(),
         })
         .send_request(&app)
         .await;
@@ -139,23 +172,406 @@ async fn test_create_layer_with_invalid_map_id_fails() {
             type_: LayerType::Base,
             name: "MyBaseLayer2".to_string(),
             is_alternative: false,
+            action_id: Uuid::new_v4(),
         })
         .send_request(&app)
         .await;
 
-    assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
 }
 
 #[actix_rt::test]
 async fn test_delete_by_id_succeeds() {
     let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
-    let (token, app) = init_test_app(pool).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri("/api/maps/-1/layers/-2")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(DeleteLayerDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+async fn test_delete_last_main_layer_of_required_type_fails() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri("/api/maps/-1/layers/-1")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(DeleteLayerDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+}
+
+#[actix_rt::test]
+async fn test_delete_main_layer_succeeds_after_promoting_alternative() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/-2/promote")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(PromoteLayerDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::delete()
+        .uri("/api/maps/-1/layers/-1")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(DeleteLayerDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+async fn test_restore_deleted_layer_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri("/api/maps/-1/layers/-2")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(DeleteLayerDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/-2")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/-2/restore")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(RestoreLayerDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let layer: LayerDto = test::read_body_json(resp).await;
+    assert_eq!(layer.id, -2);
+}
+
+#[actix_rt::test]
+async fn test_restore_main_layer_with_existing_main_layer_restores_alternative() {
+    let pool = init_test_database(|conn| {
+        async move {
+            initial_db_values(conn).await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values((
+                    &crate::schema::layers::id.eq(-3),
+                    &crate::schema::layers::map_id.eq(-1),
+                    &crate::schema::layers::type_.eq(LayerType::Plants),
+                    &crate::schema::layers::name.eq("Deleted"),
+                    &crate::schema::layers::is_alternative.eq(false),
+                    &crate::schema::layers::deletion_date.eq(Utc::now().date_naive()),
+                ))
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/-3/restore")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(RestoreLayerDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let layer: LayerDto = test::read_body_json(resp).await;
+    assert_eq!(layer.id, -3);
+    assert!(layer.is_alternative);
+}
+
+#[actix_rt::test]
+async fn test_create_layer_fails_for_not_owner() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::new_v4()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(NewLayerDto {
+            map_id: -1,
+            type_: LayerType::Base,
+            name: "MyBaseLayer".to_owned(),
+            is_alternative: false,
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn test_delete_by_id_fails_for_not_owner() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::new_v4()).await;
 
     let resp = test::TestRequest::delete()
         .uri("/api/maps/-1/layers/-1")
         .insert_header((header::AUTHORIZATION, token))
+        .set_json(DeleteLayerDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn test_duplicate_layer_copies_plantings() {
+    let planting_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async move {
+            initial_db_values(conn).await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plantings::table)
+                .values(data::TestInsertablePlanting {
+                    id: planting_id,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/-1/duplicate")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(DuplicateLayerDto {
+            name: "Alternative".to_owned(),
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let layer: LayerDto = test::read_body_json(resp).await;
+    assert!(layer.is_alternative);
+    assert_eq!(layer.name, "Alternative");
+
+    let resp = test::TestRequest::get()
+        .uri(&format!(
+            "/api/maps/-1/layers/plants/plantings?layer_id={}&relative_to_date=2023-05-08",
+            layer.id
+        ))
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    let page: TimelinePage<PlantingDto> = test::read_body_json(resp).await;
+    assert_eq!(page.results.len(), 1);
+    assert!(page.results.iter().all(|copy| copy.id != planting_id));
+}
+
+#[actix_rt::test]
+async fn test_promote_alternative_layer_demotes_main_layer() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/-2/promote")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(PromoteLayerDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let changed: Vec<LayerDto> = test::read_body_json(resp).await;
+    let alternatives: Vec<_> = changed
+        .iter()
+        .map(|layer| (layer.id, layer.is_alternative))
+        .collect();
+    assert_eq!(alternatives, vec![(-2, false), (-1, true)]);
+}
+
+#[actix_rt::test]
+async fn test_rename_layer_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::patch()
+        .uri("/api/maps/-1/layers/-2")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(UpdateLayerDto {
+            name: Some("  Renamed ".to_owned()),
+            order_index: None,
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let layer: LayerDto = test::read_body_json(resp).await;
+    assert_eq!(layer.name, "Renamed");
+}
+
+#[actix_rt::test]
+async fn test_rename_layer_to_used_name_fails() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::patch()
+        .uri("/api/maps/-1/layers/-2")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(UpdateLayerDto {
+            name: Some("My Map".to_owned()),
+            order_index: None,
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+}
+
+#[actix_rt::test]
+async fn test_create_layer_with_name_of_deleted_layer_fails() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri("/api/maps/-1/layers/-2")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(DeleteLayerDto {
+            action_id: Uuid::new_v4(),
+        })
         .send_request(&app)
         .await;
+    assert_eq!(resp.status(), StatusCode::OK);
 
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(NewLayerDto {
+            map_id: -1,
+            type_: LayerType::Plants,
+            name: " MyMap2 ".to_owned(),
+            is_alternative: true,
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+}
+
+#[actix_rt::test]
+async fn test_reorder_layers_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::patch()
+        .uri("/api/maps/-1/layers/-2")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(UpdateLayerDto {
+            name: None,
+            order_index: Some(1),
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
     assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    let layers: Vec<LayerDto> = test::read_body_json(resp).await;
+    let order: Vec<_> = layers
+        .iter()
+        .map(|layer| (layer.id, layer.order_index))
+        .collect();
+    assert_eq!(order, vec![(-1, 0), (-2, 1)]);
+}
+
+#[actix_rt::test]
+async fn test_selecting_layer_deselects_other_alternatives() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    for layer_id in [-1, -2] {
+        let resp = test::TestRequest::put()
+            .uri(&format!("/api/maps/-1/layers/{layer_id}/preferences"))
+            .insert_header((header::AUTHORIZATION, token.clone()))
+            .set_json(UpdateLayerPreferencesDto {
+                is_visible: true,
+                opacity: 0.5,
+                is_selected: true,
+            })
+            .send_request(&app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/preferences")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let mut preferences: Vec<LayerPreferencesDto> = test::read_body_json(resp).await;
+    preferences.sort_by_key(|p| p.layer_id);
+    let selected: Vec<_> = preferences
+        .iter()
+        .map(|p| (p.layer_id, p.is_selected))
+        .collect();
+    assert_eq!(selected, vec![(-2, true), (-1, false)]);
+}
+
+#[actix_rt::test]
+async fn test_layer_preferences_with_invalid_opacity_fail() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::put()
+        .uri("/api/maps/-1/layers/-1/preferences")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(UpdateLayerPreferencesDto {
+            is_visible: true,
+            opacity: 1.5,
+            is_selected: false,
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 }
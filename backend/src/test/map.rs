@@ -1,19 +1,28 @@
 //! Tests for [`crate::controller::map`].
 
 use crate::{
+    error::ServiceError,
     model::{
-        dto::{MapDto, NewMapDto, Page, UpdateMapDto},
-        r#enum::privacy_option::PrivacyOption,
+        dto::{MapChangesDto, MapDto, NewMapDto, Page, UpdateMapDto, UpdatedMapDto},
+        entity::MapCollaborator,
+        r#enum::{
+            collaborator_role::CollaboratorRole, membership::Membership,
+            privacy_option::PrivacyOption, salutation::Salutation,
+        },
+    },
+    test::util::{
+        data::{self, TestInsertableMap},
+        dummy_map_polygons::{small_rectangle, tall_rectangle},
+        init_committed_test_database, init_test_app, init_test_app_for_user, init_test_database,
     },
-    test::util::{dummy_map_polygons::tall_rectangle, init_test_app, init_test_database},
 };
 use actix_web::{
     http::{header, StatusCode},
     test,
 };
-use chrono::NaiveDate;
-use diesel::ExpressionMethods;
-use diesel_async::{scoped_futures::ScopedFutureExt, RunQueryDsl};
+use chrono::{Days, Duration, NaiveDate, Utc};
+use diesel::{ExpressionMethods, QueryResult};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncPgConnection, RunQueryDsl};
 use uuid::Uuid;
 
 #[actix_rt::test]
@@ -197,6 +206,7 @@ async fn test_update_fails_for_not_owner() {
         description: None,
         location: None,
         geometry: None,
+        action_id: Uuid::new_v4(),
     };
 
     let resp = test::TestRequest::patch()
@@ -243,6 +253,7 @@ async fn test_can_update_map() {
         description: None,
         location: None,
         geometry: None,
+        action_id: Uuid::new_v4(),
     };
 
     let resp = test::TestRequest::patch()
@@ -252,6 +263,635 @@ async fn test_can_update_map() {
         .send_request(&app)
         .await;
 
-    let updated_map: MapDto = test::read_body_json(resp).await;
-    assert_ne!(updated_map.name, map.name)
+    let updated_map: UpdatedMapDto = test::read_body_json(resp).await;
+    assert_ne!(updated_map.map.name, map.name)
+}
+
+#[actix_rt::test]
+async fn test_search_by_owner_includes_collaborated_maps() {
+    let user_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async move {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(vec![
+                    TestInsertableMap {
+                        owner_id: user_id,
+                        ..Default::default()
+                    },
+                    TestInsertableMap {
+                        id: -2,
+                        name: "Collaborated Map".to_owned(),
+                        ..Default::default()
+                    },
+                    TestInsertableMap {
+                        id: -3,
+                        name: "Unrelated Map".to_owned(),
+                        ..Default::default()
+                    },
+                ])
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::map_collaborators::table)
+                .values(MapCollaborator {
+                    map_id: -2,
+                    user_id,
+                    role: CollaboratorRole::Viewer,
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, user_id).await;
+
+    let resp = test::TestRequest::get()
+        .uri(&format!("/api/maps?owner_id={user_id}"))
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let page: Page<MapDto> = test::read_body_json(resp).await;
+    let mut ids: Vec<i32> = page.results.iter().map(|map| map.id).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, vec![-2, -1]);
+}
+
+async fn maps_with_all_privacy_options(conn: &mut AsyncPgConnection) -> Result<(), ServiceError> {
+    diesel::insert_into(crate::schema::maps::table)
+        .values(vec![
+            TestInsertableMap {
+                owner_id: Uuid::new_v4(),
+                ..Default::default()
+            },
+            TestInsertableMap {
+                id: -2,
+                name: "Protected Map".to_owned(),
+                privacy: PrivacyOption::Protected,
+                owner_id: Uuid::new_v4(),
+                ..Default::default()
+            },
+            TestInsertableMap {
+                id: -3,
+                name: "Private Map".to_owned(),
+                privacy: PrivacyOption::Private,
+                owner_id: Uuid::new_v4(),
+                ..Default::default()
+            },
+        ])
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_search_only_returns_public_maps_for_non_members() {
+    let pool = init_test_database(|conn| maps_with_all_privacy_options(conn).scope_boxed()).await;
+    let (token, app) = init_test_app(pool).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let page: Page<MapDto> = test::read_body_json(resp).await;
+    let ids: Vec<i32> = page.results.iter().map(|map| map.id).collect();
+    assert_eq!(ids, vec![-1]);
+}
+
+#[actix_rt::test]
+async fn test_search_returns_protected_maps_for_members() {
+    let user_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async move {
+            maps_with_all_privacy_options(conn).await?;
+            diesel::insert_into(crate::schema::users::table)
+                .values((
+                    &crate::schema::users::id.eq(user_id),
+                    &crate::schema::users::salutation.eq(Salutation::Mx),
+                    &crate::schema::users::country.eq("Austria"),
+                    &crate::schema::users::membership.eq(Membership::Regular),
+                ))
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, user_id).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let page: Page<MapDto> = test::read_body_json(resp).await;
+    let mut ids: Vec<i32> = page.results.iter().map(|map| map.id).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, vec![-2, -1]);
+}
+
+#[actix_rt::test]
+async fn test_find_private_map_by_id_fails_for_other_users() {
+    let pool = init_test_database(|conn| maps_with_all_privacy_options(conn).scope_boxed()).await;
+    let (token, app) = init_test_app(pool).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-3")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn test_private_map_is_visible_to_collaborators() {
+    let user_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async move {
+            maps_with_all_privacy_options(conn).await?;
+            diesel::insert_into(crate::schema::map_collaborators::table)
+                .values(MapCollaborator {
+                    map_id: -3,
+                    user_id,
+                    role: CollaboratorRole::Viewer,
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, user_id).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-3")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let page: Page<MapDto> = test::read_body_json(resp).await;
+    let mut ids: Vec<i32> = page.results.iter().map(|map| map.id).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, vec![-3, -1]);
+}
+
+#[actix_rt::test]
+async fn test_delete_map_hides_it_from_search() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri("/api/maps/-1")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let map: MapDto = test::read_body_json(resp).await;
+    assert!(map.deletion_date.is_some());
+    assert!(!map.is_inactive);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    let page: Page<MapDto> = test::read_body_json(resp).await;
+    assert!(page.results.is_empty());
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps?is_deleted=true")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    let page: Page<MapDto> = test::read_body_json(resp).await;
+    assert_eq!(page.results.len(), 1);
+}
+
+#[actix_rt::test]
+async fn test_delete_map_fails_for_not_owner() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app(pool).await;
+
+    let resp = test::TestRequest::delete()
+        .uri("/api/maps/-1")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn test_can_restore_deleted_map() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(TestInsertableMap {
+                    deletion_date: Some(Utc::now().date_naive()),
+                    is_inactive: true,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/restore")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let map: MapDto = test::read_body_json(resp).await;
+    assert!(map.deletion_date.is_none());
+    assert!(map.is_inactive);
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/restore")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+}
+
+#[actix_rt::test]
+async fn test_restore_fails_after_retention_period() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(TestInsertableMap {
+                    deletion_date: Utc::now().date_naive().checked_sub_days(Days::new(31)),
+                    is_inactive: true,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/restore")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::GONE);
+}
+
+#[actix_rt::test]
+async fn test_update_map_with_invalid_geometry_fails() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let bowtie = serde_json::from_value(serde_json::json!({
+        "rings": [[
+            {"x": 0.0, "y": 0.0},
+            {"x": 100.0, "y": 100.0},
+            {"x": 100.0, "y": 0.0},
+            {"x": 0.0, "y": 100.0},
+            {"x": 0.0, "y": 0.0}
+        ]],
+        "srid": 4326
+    }))
+    .unwrap();
+    let map_update = UpdateMapDto {
+        name: None,
+        privacy: None,
+        description: None,
+        location: None,
+        geometry: Some(bowtie),
+        action_id: Uuid::new_v4(),
+    };
+
+    let resp = test::TestRequest::patch()
+        .uri("/api/maps/-1")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(map_update)
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+async fn test_update_map_with_repeated_points_fails() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    // Five points, but only two distinct ones.
+    let line = serde_json::from_value(serde_json::json!({
+        "rings": [[
+            {"x": 0.0, "y": 0.0},
+            {"x": 100.0, "y": 0.0},
+            {"x": 100.0, "y": 0.0},
+            {"x": 0.0, "y": 0.0},
+            {"x": 0.0, "y": 0.0}
+        ]],
+        "srid": 4326
+    }))
+    .unwrap();
+    let map_update = UpdateMapDto {
+        name: None,
+        privacy: None,
+        description: None,
+        location: None,
+        geometry: Some(line),
+        action_id: Uuid::new_v4(),
+    };
+
+    let resp = test::TestRequest::patch()
+        .uri("/api/maps/-1")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(map_update)
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+async fn test_update_map_geometry_reports_plantings_outside() {
+    let outside_planting = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async move {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plantings::table)
+                .values(vec![
+                    data::TestInsertablePlanting {
+                        id: Uuid::new_v4(),
+                        x: 5,
+                        y: 50,
+                        ..Default::default()
+                    },
+                    data::TestInsertablePlanting {
+                        id: outside_planting,
+                        x: 300,
+                        y: 500,
+                        ..Default::default()
+                    },
+                ])
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let map_update = UpdateMapDto {
+        name: None,
+        privacy: None,
+        description: None,
+        location: None,
+        geometry: Some(small_rectangle()),
+        action_id: Uuid::new_v4(),
+    };
+
+    let resp = test::TestRequest::patch()
+        .uri("/api/maps/-1")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(map_update)
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let updated_map: UpdatedMapDto = test::read_body_json(resp).await;
+    assert_eq!(updated_map.map.geometry, small_rectangle());
+    let outside: Vec<Uuid> = updated_map
+        .plantings_outside_geometry
+        .iter()
+        .map(|planting| planting.id)
+        .collect();
+    assert_eq!(outside, vec![outside_planting]);
+}
+
+/// Id of the map of tests that commit their data, see [`init_committed_test_database`].
+const COMMITTED_MAP_ID: i32 = -101;
+
+/// Id of the plant of tests that commit their data, see [`init_committed_test_database`].
+const COMMITTED_PLANT_ID: i32 = -101;
+
+/// Remove the data committed by a test, the layers and plantings are removed together with the map.
+async fn remove_committed_data(conn: &mut AsyncPgConnection) -> QueryResult<()> {
+    diesel::delete(crate::schema::maps::table)
+        .filter(crate::schema::maps::id.eq(COMMITTED_MAP_ID))
+        .execute(conn)
+        .await?;
+    diesel::delete(crate::schema::plants::table)
+        .filter(crate::schema::plants::id.eq(COMMITTED_PLANT_ID))
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+// The changes are read in a transaction of their own, which can't be nested in a test transaction.
+#[actix_rt::test]
+async fn test_find_changes_returns_changed_and_deleted_content() {
+    let planting_id = Uuid::new_v4();
+    let deleted_planting_id = Uuid::new_v4();
+    let pool = init_committed_test_database(|conn| {
+        async move {
+            remove_committed_data(conn).await?;
+            diesel::insert_into(crate::schema::maps::table)
+                .values(TestInsertableMap {
+                    id: COMMITTED_MAP_ID,
+                    name: "Test Map with committed data".to_owned(),
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer {
+                    id: COMMITTED_MAP_ID,
+                    map_id: COMMITTED_MAP_ID,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant {
+                    id: COMMITTED_PLANT_ID,
+                    unique_name: "Test Plant with committed data".to_owned(),
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plantings::table)
+                .values(vec![
+                    data::TestInsertablePlanting {
+                        id: planting_id,
+                        layer_id: COMMITTED_MAP_ID,
+                        plant_id: COMMITTED_PLANT_ID,
+                        ..Default::default()
+                    },
+                    data::TestInsertablePlanting {
+                        id: deleted_planting_id,
+                        layer_id: COMMITTED_MAP_ID,
+                        plant_id: COMMITTED_PLANT_ID,
+                        delete_date: Some(Utc::now().date_naive()),
+                        ..Default::default()
+                    },
+                ])
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let past = (Utc::now() - Duration::hours(1))
+        .naive_utc()
+        .format("%Y-%m-%dT%H:%M:%S");
+    let resp = test::TestRequest::get()
+        .uri(&format!(
+            "/api/maps/{COMMITTED_MAP_ID}/changes?since={past}"
+        ))
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    let status = resp.status();
+    let changes: Option<MapChangesDto> = test::try_read_body_json(resp).await.ok();
+
+    let future = (Utc::now() + Duration::hours(1))
+        .naive_utc()
+        .format("%Y-%m-%dT%H:%M:%S");
+    let resp = test::TestRequest::get()
+        .uri(&format!(
+            "/api/maps/{COMMITTED_MAP_ID}/changes?since={future}"
+        ))
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    let future_status = resp.status();
+    let no_changes: Option<MapChangesDto> = test::try_read_body_json(resp).await.ok();
+
+    // Removed before asserting, so a failing assertion doesn't leave the data behind.
+    let mut conn = pool
+        .get()
+        .await
+        .expect("Failed to get connection from pool");
+    remove_committed_data(&mut conn)
+        .await
+        .expect("Failed to remove committed data");
+
+    assert_eq!(status, StatusCode::OK);
+    let changes = changes.expect("Failed to read changes");
+    let planting_ids: Vec<_> = changes.plantings.iter().map(|p| p.id).collect();
+    assert_eq!(planting_ids, vec![planting_id]);
+    assert_eq!(changes.deleted_plantings, vec![deleted_planting_id]);
+    assert_eq!(changes.layers.len(), 1);
+
+    assert_eq!(future_status, StatusCode::OK);
+    let no_changes = no_changes.expect("Failed to read changes");
+    assert!(no_changes.plantings.is_empty());
+    assert!(no_changes.deleted_plantings.is_empty());
+    assert!(no_changes.layers.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_find_changes_fails_after_retention_period() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let since = (Utc::now() - Duration::days(31))
+        .naive_utc()
+        .format("%Y-%m-%dT%H:%M:%S");
+    let resp = test::TestRequest::get()
+        .uri(&format!("/api/maps/-1/changes?since={since}"))
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::GONE);
 }
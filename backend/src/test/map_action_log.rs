@@ -0,0 +1,250 @@
+//! Tests for undoing and redoing actions via [`crate::controller::map`].
+
+use actix_http::StatusCode;
+use actix_web::{http::header, test};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::{
+    error::ServiceError,
+    model::{
+        dto::{
+            plantings::{MovePlantingDto, NewPlantingDto, PlantingDto, UpdatePlantingDto},
+            TimelinePage,
+        },
+        entity::MapCollaborator,
+        r#enum::collaborator_role::CollaboratorRole,
+    },
+    test::util::{data, init_test_app_for_user, init_test_database},
+};
+
+/// Insert a map with a plant layer and a planting at (0, 0).
+async fn map_with_planting(
+    conn: &mut AsyncPgConnection,
+    planting_id: Uuid,
+) -> Result<(), ServiceError> {
+    diesel::insert_into(crate::schema::maps::table)
+        .values(data::TestInsertableMap::default())
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::layers::table)
+        .values(data::TestInsertableLayer::default())
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::plants::table)
+        .values(data::TestInsertablePlant::default())
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::plantings::table)
+        .values(data::TestInsertablePlanting {
+            id: planting_id,
+            ..Default::default()
+        })
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Build a request moving the planting in `version` to (`x`, `y`).
+fn move_request(planting_id: Uuid, x: i32, y: i32, version: i32) -> test::TestRequest {
+    test::TestRequest::patch()
+        .uri(&format!(
+            "/api/maps/-1/layers/plants/plantings/{planting_id}"
+        ))
+        .set_json(UpdatePlantingDto::Move(MovePlantingDto {
+            x,
+            y,
+            version,
+            action_id: Uuid::new_v4(),
+        }))
+}
+
+/// Build a request searching all plantings on the map.
+fn search_request() -> test::TestRequest {
+    test::TestRequest::get().uri("/api/maps/-1/layers/plants/plantings?relative_to_date=2023-05-08")
+}
+
+#[actix_rt::test]
+async fn test_undo_and_redo_create() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/plants/plantings")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(NewPlantingDto {
+            id: Some(Uuid::new_v4()),
+            action_id: Uuid::new_v4(),
+            layer_id: -1,
+            plant_id: -1,
+            ..Default::default()
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/undo")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = search_request()
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    let page: TimelinePage<PlantingDto> = test::read_body_json(resp).await;
+    assert!(page.results.is_empty());
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/redo")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = search_request()
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    let page: TimelinePage<PlantingDto> = test::read_body_json(resp).await;
+    assert_eq!(page.results.len(), 1);
+}
+
+#[actix_rt::test]
+async fn test_undo_move_restores_position() {
+    let planting_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| map_with_planting(conn, planting_id).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = move_request(planting_id, 10, 20, 1)
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/undo")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = search_request()
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    let page: TimelinePage<PlantingDto> = test::read_body_json(resp).await;
+    let planting = page.results.into_iter().next().unwrap();
+    assert_eq!(planting.x, 0);
+    assert_eq!(planting.y, 0);
+
+    // Undoing increments the version as well.
+    assert_eq!(planting.version, 3);
+
+    // A new action clears the undone actions.
+    let resp = move_request(planting_id, 5, 5, 3)
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/redo")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn test_undo_fails_if_changed_by_other_user() {
+    let planting_id = Uuid::new_v4();
+    let editor_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async move {
+            map_with_planting(conn, planting_id).await?;
+            diesel::insert_into(crate::schema::map_collaborators::table)
+                .values(MapCollaborator {
+                    map_id: -1,
+                    user_id: editor_id,
+                    role: CollaboratorRole::Editor,
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (owner_token, owner_app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+    let (editor_token, editor_app) = init_test_app_for_user(pool.clone(), editor_id).await;
+
+    let resp = move_request(planting_id, 10, 10, 1)
+        .insert_header((header::AUTHORIZATION, owner_token.clone()))
+        .send_request(&owner_app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = move_request(planting_id, 20, 20, 2)
+        .insert_header((header::AUTHORIZATION, editor_token))
+        .send_request(&editor_app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/undo")
+        .insert_header((header::AUTHORIZATION, owner_token.clone()))
+        .send_request(&owner_app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    // The conflicting action was discarded.
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/undo")
+        .insert_header((header::AUTHORIZATION, owner_token))
+        .send_request(&owner_app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn test_undo_fails_without_actions() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/undo")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,233 @@
+//! Tests for [`crate::controller::map_collaborators`].
+
+use actix_http::StatusCode;
+use actix_web::{http::header, test};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::{
+    error::ServiceError,
+    model::{
+        dto::{MapCollaboratorDto, NewLayerDto, NewMapCollaboratorDto, UpdateMapCollaboratorDto},
+        entity::MapCollaborator,
+        r#enum::{collaborator_role::CollaboratorRole, layer_type::LayerType},
+    },
+    test::util::{data, init_test_app, init_test_app_for_user, init_test_database},
+};
+
+const EDITOR_ID: Uuid = Uuid::from_u128(1);
+const VIEWER_ID: Uuid = Uuid::from_u128(2);
+
+async fn initial_db_values(conn: &mut AsyncPgConnection) -> Result<(), ServiceError> {
+    diesel::insert_into(crate::schema::maps::table)
+        .values(data::TestInsertableMap::default())
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::map_collaborators::table)
+        .values(vec![
+            MapCollaborator {
+                map_id: -1,
+                user_id: EDITOR_ID,
+                role: CollaboratorRole::Editor,
+            },
+            MapCollaborator {
+                map_id: -1,
+                user_id: VIEWER_ID,
+                role: CollaboratorRole::Viewer,
+            },
+        ])
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_find_collaborators_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, VIEWER_ID).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/collaborators")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let collaborators: Vec<MapCollaboratorDto> = test::read_body_json(resp).await;
+    assert_eq!(collaborators.len(), 2);
+}
+
+#[actix_rt::test]
+async fn test_find_collaborators_fails_for_non_member() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app(pool).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/collaborators")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn test_invite_collaborator_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+    let invited_user = Uuid::new_v4();
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/collaborators")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(NewMapCollaboratorDto {
+            user_id: invited_user,
+            role: CollaboratorRole::Editor,
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let collaborator: MapCollaboratorDto = test::read_body_json(resp).await;
+    assert_eq!(collaborator.user_id, invited_user);
+    assert_eq!(collaborator.role, CollaboratorRole::Editor);
+}
+
+#[actix_rt::test]
+async fn test_invite_collaborator_fails_for_editor() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, EDITOR_ID).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/collaborators")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(NewMapCollaboratorDto {
+            user_id: Uuid::new_v4(),
+            role: CollaboratorRole::Viewer,
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn test_invite_existing_member_fails() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    for user_id in [EDITOR_ID, Uuid::default()] {
+        let resp = test::TestRequest::post()
+            .uri("/api/maps/-1/collaborators")
+            .insert_header((header::AUTHORIZATION, token.clone()))
+            .set_json(NewMapCollaboratorDto {
+                user_id,
+                role: CollaboratorRole::Viewer,
+            })
+            .send_request(&app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+    }
+}
+
+#[actix_rt::test]
+async fn test_change_role_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::patch()
+        .uri(&format!("/api/maps/-1/collaborators/{VIEWER_ID}"))
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(UpdateMapCollaboratorDto {
+            role: CollaboratorRole::Owner,
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let collaborator: MapCollaboratorDto = test::read_body_json(resp).await;
+    assert_eq!(collaborator.role, CollaboratorRole::Owner);
+}
+
+#[actix_rt::test]
+async fn test_remove_collaborator_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri(&format!("/api/maps/-1/collaborators/{EDITOR_ID}"))
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::delete()
+        .uri(&format!("/api/maps/-1/collaborators/{EDITOR_ID}"))
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn test_collaborator_can_leave_map() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, VIEWER_ID).await;
+
+    let resp = test::TestRequest::delete()
+        .uri(&format!("/api/maps/-1/collaborators/{EDITOR_ID}"))
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let resp = test::TestRequest::delete()
+        .uri(&format!("/api/maps/-1/collaborators/{VIEWER_ID}"))
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+async fn test_editor_can_edit_map_content() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, EDITOR_ID).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(NewLayerDto {
+            map_id: -1,
+            type_: LayerType::Plants,
+            name: "Editor Layer".to_owned(),
+            is_alternative: true,
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::CREATED);
+}
+
+#[actix_rt::test]
+async fn test_viewer_cannot_edit_map_content() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, VIEWER_ID).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(NewLayerDto {
+            map_id: -1,
+            type_: LayerType::Plants,
+            name: "Viewer Layer".to_owned(),
+            is_alternative: true,
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
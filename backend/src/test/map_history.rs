@@ -0,0 +1,132 @@
+//! Tests for [`crate::controller::map::find_history`].
+
+use actix_http::StatusCode;
+use actix_web::{http::header, test};
+use diesel_async::{scoped_futures::ScopedFutureExt, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::{
+    model::{
+        dto::{
+            history::{HistoryEntityDto, MapHistoryEntryDto},
+            plantings::{MovePlantingDto, UpdatePlantingDto},
+            Page,
+        },
+        entity::MapCollaborator,
+        r#enum::{collaborator_role::CollaboratorRole, history_entity_type::HistoryEntityType},
+    },
+    test::util::{data, init_test_app_for_user, init_test_database},
+};
+
+#[actix_rt::test]
+async fn test_history_records_who_changed_what() {
+    let planting_id = Uuid::new_v4();
+    let editor_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async move {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plantings::table)
+                .values(data::TestInsertablePlanting {
+                    id: planting_id,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::map_collaborators::table)
+                .values(MapCollaborator {
+                    map_id: -1,
+                    user_id: editor_id,
+                    role: CollaboratorRole::Editor,
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (editor_token, editor_app) = init_test_app_for_user(pool.clone(), editor_id).await;
+    let (owner_token, owner_app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::patch()
+        .uri(&format!(
+            "/api/maps/-1/layers/plants/plantings/{planting_id}"
+        ))
+        .insert_header((header::AUTHORIZATION, editor_token))
+        .set_json(UpdatePlantingDto::Move(MovePlantingDto {
+            x: 10,
+            y: 20,
+            version: 1,
+            action_id: Uuid::new_v4(),
+        }))
+        .send_request(&editor_app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/history")
+        .insert_header((header::AUTHORIZATION, owner_token.clone()))
+        .send_request(&owner_app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let page: Page<MapHistoryEntryDto> = test::read_body_json(resp).await;
+    assert_eq!(page.results.len(), 1);
+    let entry = page.results.into_iter().next().unwrap();
+    assert_eq!(entry.user_id, editor_id);
+    assert_eq!(entry.entity_type, HistoryEntityType::Planting);
+    assert_eq!(entry.entity_id, Some(planting_id));
+    assert!(matches!(entry.before, Some(HistoryEntityDto::Planting(p)) if p.x == 0));
+    assert!(matches!(entry.after, Some(HistoryEntityDto::Planting(p)) if p.x == 10));
+
+    let resp = test::TestRequest::get()
+        .uri(&format!("/api/maps/-1/history?user_id={}", Uuid::default()))
+        .insert_header((header::AUTHORIZATION, owner_token))
+        .send_request(&owner_app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let filtered: Page<MapHistoryEntryDto> = test::read_body_json(resp).await;
+    assert!(filtered.results.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_history_fails_for_not_owner() {
+    let editor_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async move {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::map_collaborators::table)
+                .values(MapCollaborator {
+                    map_id: -1,
+                    user_id: editor_id,
+                    role: CollaboratorRole::Editor,
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, editor_id).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/history")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
@@ -9,12 +9,21 @@ mod config;
 mod guided_tours;
 mod layers;
 mod map;
+mod map_action_log;
+mod map_collaborators;
+mod map_history;
 mod pagination;
 mod plant;
 mod plant_layer;
 // mod plant_layer_heatmap;
 mod planting_suggestions;
+mod planting_warnings;
 mod plantings;
+mod presence;
 mod seed;
+mod shade_areas;
+mod soil_zones;
+mod sse;
+mod sync;
 mod users;
 pub mod util;
@@ -6,10 +6,16 @@ use actix_web::{
 };
 use diesel::ExpressionMethods;
 use diesel_async::{scoped_futures::ScopedFutureExt, RunQueryDsl};
+use uuid::Uuid;
 
 use crate::{
-    model::{dto::RelationsDto, r#enum::relation_type::RelationType},
-    test::util::{init_test_app, init_test_database},
+    model::{
+        dto::RelationsDto,
+        entity::plant_layer::HeatMap,
+        r#enum::{layer_type::LayerType, relation_type::RelationType, shade::Shade},
+    },
+    service::plant_layer::apply_shade_penalty,
+    test::util::{data, dummy_map_polygons::small_rectangle, init_test_app, init_test_database},
 };
 
 #[actix_rt::test]
@@ -201,3 +207,54 @@ async fn test_plants_relations_are_distinct() {
     assert_eq!(dto.relations.len(), 1);
     assert!(dto.relations.iter().any(|r| r.id == -1));
 }
+
+#[actix_rt::test]
+async fn test_heatmap_penalizes_places_with_more_shade_than_tolerated() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer {
+                    type_: LayerType::Shade,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::shade_areas::table)
+                .values((
+                    &crate::schema::shade_areas::id.eq(Uuid::new_v4()),
+                    &crate::schema::shade_areas::layer_id.eq(-1),
+                    &crate::schema::shade_areas::shade.eq(Shade::PermanentShade),
+                    &crate::schema::shade_areas::geometry.eq(small_rectangle()),
+                ))
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let mut conn = pool.get().await.unwrap();
+    let mut heatmap = HeatMap {
+        x_min: 0,
+        y_min: 0,
+        scores: vec![vec![1.0; 2]; 11],
+    };
+
+    apply_shade_penalty(&mut heatmap, -1, Shade::NoShade, &mut conn)
+        .await
+        .unwrap();
+
+    // The area covers the centers of the first column up to a height of 100 cm.
+    for (row, scores) in heatmap.scores.iter().enumerate() {
+        let expected = if row < 10 {
+            vec![0.25, 1.0]
+        } else {
+            vec![1.0, 1.0]
+        };
+        assert_eq!(scores, &expected, "row {row}");
+    }
+}
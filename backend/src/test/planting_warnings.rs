@@ -0,0 +1,240 @@
+//! Tests for [`crate::controller::plant_layer::find_warnings`].
+
+use actix_web::{
+    http::{header, StatusCode},
+    test,
+};
+use diesel::ExpressionMethods;
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::{
+    error::ServiceError,
+    model::{
+        dto::planting_warnings::{PlantingWarningDto, PlantingWarningKind},
+        r#enum::{
+            layer_type::LayerType, light_requirement::LightRequirement, shade::Shade,
+            soil_ph::SoilPh, soil_texture::SoilTexture,
+        },
+    },
+    test::util::{data, dummy_map_polygons::small_rectangle, init_test_app, init_test_database},
+};
+
+/// A sun loving plant in the shade.
+const SHADED_PLANTING_ID: Uuid = Uuid::from_u128(1);
+/// A sun loving plant outside of the shade.
+const SUNNY_PLANTING_ID: Uuid = Uuid::from_u128(2);
+/// A shade loving plant in the shade.
+const SHADE_LOVING_PLANTING_ID: Uuid = Uuid::from_u128(3);
+/// A plant without known shade tolerance in the shade.
+const UNKNOWN_PLANTING_ID: Uuid = Uuid::from_u128(4);
+/// A plant that needs acid soil on alkaline soil.
+const ACID_SOIL_PLANTING_ID: Uuid = Uuid::from_u128(5);
+
+async fn initial_db_values(
+    conn: &mut AsyncPgConnection,
+    shade_layer_is_alternative: bool,
+) -> Result<(), ServiceError> {
+    diesel::insert_into(crate::schema::maps::table)
+        .values(data::TestInsertableMap::default())
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::layers::table)
+        .values(vec![
+            data::TestInsertableLayer::default(),
+            data::TestInsertableLayer {
+                id: -2,
+                type_: LayerType::Shade,
+                name: "Shade".to_owned(),
+                is_alternative: shade_layer_is_alternative,
+                ..Default::default()
+            },
+        ])
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::plants::table)
+        .values((
+            &crate::schema::plants::id.eq(-1),
+            &crate::schema::plants::unique_name.eq("Sun loving plant"),
+            &crate::schema::plants::shade.eq(Shade::NoShade),
+        ))
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::plants::table)
+        .values((
+            &crate::schema::plants::id.eq(-2),
+            &crate::schema::plants::unique_name.eq("Shade loving plant"),
+            &crate::schema::plants::light_requirement.eq(vec![Some(LightRequirement::FullShade)]),
+        ))
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::plants::table)
+        .values(data::TestInsertablePlant {
+            id: -3,
+            unique_name: "Unknown plant".to_owned(),
+            ..Default::default()
+        })
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::shade_areas::table)
+        .values((
+            &crate::schema::shade_areas::id.eq(Uuid::new_v4()),
+            &crate::schema::shade_areas::layer_id.eq(-2),
+            &crate::schema::shade_areas::shade.eq(Shade::PermanentShade),
+            &crate::schema::shade_areas::geometry.eq(small_rectangle()),
+        ))
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::plantings::table)
+        .values(vec![
+            data::TestInsertablePlanting {
+                id: SHADED_PLANTING_ID,
+                plant_id: -1,
+                x: 5,
+                y: 50,
+                ..Default::default()
+            },
+            data::TestInsertablePlanting {
+                id: SUNNY_PLANTING_ID,
+                plant_id: -1,
+                x: 300,
+                y: 500,
+                ..Default::default()
+            },
+            data::TestInsertablePlanting {
+                id: SHADE_LOVING_PLANTING_ID,
+                plant_id: -2,
+                x: 5,
+                y: 50,
+                ..Default::default()
+            },
+            data::TestInsertablePlanting {
+                id: UNKNOWN_PLANTING_ID,
+                plant_id: -3,
+                x: 5,
+                y: 50,
+                ..Default::default()
+            },
+        ])
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// Add a soil layer with an alkaline zone where the shade is
+/// and a planting that needs acid soil in it.
+async fn soil_db_values(conn: &mut AsyncPgConnection) -> Result<(), ServiceError> {
+    initial_db_values(conn, false).await?;
+    diesel::insert_into(crate::schema::layers::table)
+        .values(data::TestInsertableLayer {
+            id: -3,
+            type_: LayerType::Soil,
+            name: "Soil".to_owned(),
+            ..Default::default()
+        })
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::soil_zones::table)
+        .values((
+            &crate::schema::soil_zones::id.eq(Uuid::new_v4()),
+            &crate::schema::soil_zones::layer_id.eq(-3),
+            &crate::schema::soil_zones::soil_ph.eq(SoilPh::Alkaline),
+            &crate::schema::soil_zones::soil_texture.eq(SoilTexture::Clay),
+            &crate::schema::soil_zones::geometry.eq(small_rectangle()),
+        ))
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::plants::table)
+        .values((
+            &crate::schema::plants::id.eq(-4),
+            &crate::schema::plants::unique_name.eq("Acid soil loving plant"),
+            &crate::schema::plants::shade.eq(Shade::PermanentDeepShade),
+            &crate::schema::plants::soil_ph.eq(vec![Some(SoilPh::Acidic)]),
+            &crate::schema::plants::soil_texture
+                .eq(vec![Some(SoilTexture::Loamy), Some(SoilTexture::Clay)]),
+        ))
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::plantings::table)
+        .values(data::TestInsertablePlanting {
+            id: ACID_SOIL_PLANTING_ID,
+            plant_id: -4,
+            x: 5,
+            y: 50,
+            ..Default::default()
+        })
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_find_warnings_reports_plantings_in_too_much_shade() {
+    let pool = init_test_database(|conn| initial_db_values(conn, false).scope_boxed()).await;
+    let (token, app) = init_test_app(pool).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/plants/warnings?layer_id=-1")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let warnings: Vec<PlantingWarningDto> = test::read_body_json(resp).await;
+    let warning = warnings.into_iter().next();
+    assert_eq!(
+        warning.map(|warning| (warning.planting_id, warning.kind)),
+        Some((SHADED_PLANTING_ID, PlantingWarningKind::Shade))
+    );
+}
+
+#[actix_rt::test]
+async fn test_find_warnings_ignores_shade_on_alternative_layer() {
+    let pool = init_test_database(|conn| initial_db_values(conn, true).scope_boxed()).await;
+    let (token, app) = init_test_app(pool).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/plants/warnings?layer_id=-1")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let warnings: Vec<PlantingWarningDto> = test::read_body_json(resp).await;
+    assert!(warnings.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_find_warnings_fails_for_shade_layer() {
+    let pool = init_test_database(|conn| initial_db_values(conn, false).scope_boxed()).await;
+    let (token, app) = init_test_app(pool).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/plants/warnings?layer_id=-2")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+async fn test_find_warnings_reports_plantings_on_unsuited_soil() {
+    let pool = init_test_database(|conn| soil_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app(pool).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/plants/warnings?layer_id=-1")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let warnings: Vec<PlantingWarningDto> = test::read_body_json(resp).await;
+    let soil_warnings: Vec<_> = warnings
+        .into_iter()
+        .filter(|warning| warning.planting_id == ACID_SOIL_PLANTING_ID)
+        .map(|warning| warning.kind)
+        .collect();
+    assert_eq!(soil_warnings, vec![PlantingWarningKind::SoilPh]);
+}
@@ -4,25 +4,28 @@ use std::ops::Add;
 
 use actix_http::StatusCode;
 use actix_web::{http::header, test};
-use chrono::{Days, NaiveDate};
-use diesel_async::{scoped_futures::ScopedFutureExt, RunQueryDsl};
+use chrono::{Days, NaiveDate, Utc};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncPgConnection, RunQueryDsl};
 use uuid::Uuid;
 
 use crate::{
+    error::ServiceError,
     model::{
         dto::{
             plantings::{
-                DeletePlantingDto, MovePlantingDto, NewPlantingDto, PlantingDto, UpdatePlantingDto,
+                BatchCreatePlantingsDto, BatchDeletePlantingsDto, BatchUpdatePlantingDto,
+                BatchUpdatePlantingsDto, DeletePlantingDto, MovePlantingDto, NewPlantingDto,
+                PlantingDto, RestorePlantingDto, UpdatePlantingDto,
             },
             TimelinePage,
         },
-        r#enum::layer_type::LayerType,
+        r#enum::{layer_type::LayerType, privacy_option::PrivacyOption},
     },
-    service::plantings::TIME_LINE_LOADING_OFFSET_DAYS,
+    service::{plantings::TIME_LINE_LOADING_OFFSET_DAYS, util::MAX_BATCH_SIZE},
     test::util::data,
 };
 
-use crate::test::util::{init_test_app, init_test_database};
+use crate::test::util::{init_test_app, init_test_app_for_user, init_test_database};
 
 #[actix_rt::test]
 async fn test_can_search_plantings() {
@@ -93,6 +96,36 @@ async fn test_can_search_plantings() {
     assert_eq!(page.results.len(), 2);
 }
 
+#[actix_rt::test]
+async fn test_search_plantings_fails_for_non_member() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap {
+                    privacy: PrivacyOption::Private,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::new_v4()).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/plants/plantings?layer_id=-1&relative_to_date=2023-05-08")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
 #[actix_rt::test]
 async fn test_create_fails_with_invalid_layer() {
     let pool = init_test_database(|conn| {
@@ -117,7 +150,7 @@ async fn test_create_fails_with_invalid_layer() {
         .scope_boxed()
     })
     .await;
-    let (token, app) = init_test_app(pool.clone()).await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
 
     let new_planting = NewPlantingDto {
         id: Some(Uuid::new_v4()),
@@ -164,7 +197,7 @@ async fn test_can_create_plantings() {
         .scope_boxed()
     })
     .await;
-    let (token, app) = init_test_app(pool.clone()).await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
 
     let new_planting = NewPlantingDto {
         id: Some(Uuid::new_v4()),
@@ -219,11 +252,12 @@ async fn test_can_update_plantings() {
         .scope_boxed()
     })
     .await;
-    let (token, app) = init_test_app(pool.clone()).await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
 
     let update_data = MovePlantingDto {
         x: 1,
         y: 1,
+        version: 1,
         action_id: Uuid::new_v4(),
     };
     let update_object = UpdatePlantingDto::Move(update_data);
@@ -241,6 +275,65 @@ async fn test_can_update_plantings() {
     let planting: PlantingDto = test::read_body_json(resp).await;
     assert_eq!(planting.x, 1);
     assert_eq!(planting.y, 1);
+    assert_eq!(planting.version, 2);
+}
+
+#[actix_rt::test]
+async fn test_update_with_outdated_version_conflicts() {
+    let planting_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plantings::table)
+                .values(data::TestInsertablePlanting {
+                    id: planting_id,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let move_request = |x| {
+        test::TestRequest::patch()
+            .uri(&format!(
+                "/api/maps/-1/layers/plants/plantings/{planting_id}"
+            ))
+            .insert_header((header::AUTHORIZATION, token.clone()))
+            .set_json(UpdatePlantingDto::Move(MovePlantingDto {
+                x,
+                y: 0,
+                version: 1,
+                action_id: Uuid::new_v4(),
+            }))
+    };
+
+    let resp = move_request(1).send_request(&app).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // The second update is based on the same version, so it doesn't know about the first one.
+    let resp = move_request(2).send_request(&app).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    // The response contains the current state of the planting.
+    let current: PlantingDto = test::read_body_json(resp).await;
+    assert_eq!(current.x, 1);
+    assert_eq!(current.version, 2);
 }
 
 #[actix_rt::test]
@@ -272,7 +365,7 @@ async fn test_can_delete_planting() {
         .scope_boxed()
     })
     .await;
-    let (token, app) = init_test_app(pool.clone()).await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
 
     let resp = test::TestRequest::delete()
         .uri(&format!(
@@ -297,6 +390,51 @@ async fn test_can_delete_planting() {
     assert_eq!(page.results.len(), 0);
 }
 
+#[actix_rt::test]
+async fn test_delete_fails_for_deleted_planting() {
+    let planting_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plantings::table)
+                .values(data::TestInsertablePlanting {
+                    id: planting_id,
+                    delete_date: Some(Utc::now().date_naive()),
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri(&format!(
+            "/api/maps/-1/layers/plants/plantings/{planting_id}",
+        ))
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(DeletePlantingDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+}
+
 #[actix_rt::test]
 async fn test_removed_planting_outside_loading_offset_is_not_in_timeline() {
     let planting_id = Uuid::new_v4();
@@ -443,3 +581,556 @@ async fn test_added_planting_outside_loading_offset_is_not_in_timeline() {
     let page: TimelinePage<PlantingDto> = test::read_body_json(resp).await;
     assert_eq!(page.results.len(), 0);
 }
+
+#[actix_rt::test]
+async fn test_create_fails_for_not_owner() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::new_v4()).await;
+
+    let new_planting = NewPlantingDto {
+        id: Some(Uuid::new_v4()),
+        action_id: Uuid::new_v4(),
+        layer_id: -1,
+        plant_id: -1,
+        ..Default::default()
+    };
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/plants/plantings")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(new_planting)
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn test_create_fails_for_layer_of_other_map() {
+    let user_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(vec![
+                    data::TestInsertableMap::default(),
+                    data::TestInsertableMap {
+                        id: -2,
+                        name: "Test Map 2".to_owned(),
+                        owner_id: user_id,
+                        ..Default::default()
+                    },
+                ])
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), user_id).await;
+
+    let new_planting = NewPlantingDto {
+        id: Some(Uuid::new_v4()),
+        action_id: Uuid::new_v4(),
+        layer_id: -1,
+        plant_id: -1,
+        ..Default::default()
+    };
+
+    // The user owns map -2, but layer -1 is part of map -1.
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-2/layers/plants/plantings")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(new_planting)
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn test_update_fails_for_not_owner() {
+    let planting_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plantings::table)
+                .values(data::TestInsertablePlanting {
+                    id: planting_id,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::new_v4()).await;
+
+    let update_object = UpdatePlantingDto::Move(MovePlantingDto {
+        x: 1,
+        y: 1,
+        version: 1,
+        action_id: Uuid::new_v4(),
+    });
+
+    let resp = test::TestRequest::patch()
+        .uri(&format!(
+            "/api/maps/-1/layers/plants/plantings/{planting_id}"
+        ))
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(update_object)
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn test_delete_fails_for_not_owner() {
+    let planting_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plantings::table)
+                .values(data::TestInsertablePlanting {
+                    id: planting_id,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::new_v4()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri(&format!(
+            "/api/maps/-1/layers/plants/plantings/{planting_id}",
+        ))
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(DeletePlantingDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn test_can_restore_deleted_planting() {
+    let planting_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plantings::table)
+                .values(data::TestInsertablePlanting {
+                    id: planting_id,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri(&format!(
+            "/api/maps/-1/layers/plants/plantings/{planting_id}",
+        ))
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(DeletePlantingDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::post()
+        .uri(&format!(
+            "/api/maps/-1/layers/plants/plantings/{planting_id}/restore",
+        ))
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(RestorePlantingDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/plants/plantings?relative_to_date=2023-05-08")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let page: TimelinePage<PlantingDto> = test::read_body_json(resp).await;
+    assert_eq!(page.results.len(), 1);
+}
+
+#[actix_rt::test]
+async fn test_restore_fails_for_planting_that_is_not_deleted() {
+    let planting_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plantings::table)
+                .values(data::TestInsertablePlanting {
+                    id: planting_id,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri(&format!(
+            "/api/maps/-1/layers/plants/plantings/{planting_id}/restore",
+        ))
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(RestorePlantingDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn test_restore_fails_after_retention_period() {
+    let planting_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plantings::table)
+                .values(data::TestInsertablePlanting {
+                    id: planting_id,
+                    delete_date: Utc::now().date_naive().checked_sub_days(Days::new(31)),
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri(&format!(
+            "/api/maps/-1/layers/plants/plantings/{planting_id}/restore",
+        ))
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(RestorePlantingDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+async fn batch_db_values(conn: &mut AsyncPgConnection) -> Result<(), ServiceError> {
+    diesel::insert_into(crate::schema::maps::table)
+        .values(vec![
+            data::TestInsertableMap::default(),
+            data::TestInsertableMap {
+                id: -2,
+                name: "Test Map 2".to_owned(),
+                ..Default::default()
+            },
+        ])
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::layers::table)
+        .values(vec![
+            data::TestInsertableLayer::default(),
+            data::TestInsertableLayer {
+                id: -2,
+                map_id: -2,
+                ..Default::default()
+            },
+        ])
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::plants::table)
+        .values(data::TestInsertablePlant::default())
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::plantings::table)
+        .values(vec![
+            data::TestInsertablePlanting {
+                id: Uuid::from_u128(1),
+                ..Default::default()
+            },
+            data::TestInsertablePlanting {
+                id: Uuid::from_u128(2),
+                ..Default::default()
+            },
+        ])
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_can_create_plantings_in_batch() {
+    let pool = init_test_database(|conn| batch_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let new_plantings = (0..3)
+        .map(|i| NewPlantingDto {
+            id: Some(Uuid::new_v4()),
+            layer_id: -1,
+            plant_id: -1,
+            x: i * 10,
+            ..Default::default()
+        })
+        .collect();
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/plants/plantings/batch")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(BatchCreatePlantingsDto {
+            plantings: new_plantings,
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let plantings: Vec<PlantingDto> = test::read_body_json(resp).await;
+    assert_eq!(plantings.len(), 3);
+}
+
+#[actix_rt::test]
+async fn test_create_plantings_in_batch_is_atomic() {
+    let pool = init_test_database(|conn| batch_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    // The second planting is on a layer of another map, so nothing must be created.
+    let new_plantings = vec![
+        NewPlantingDto {
+            id: Some(Uuid::new_v4()),
+            layer_id: -1,
+            plant_id: -1,
+            ..Default::default()
+        },
+        NewPlantingDto {
+            id: Some(Uuid::new_v4()),
+            layer_id: -2,
+            plant_id: -1,
+            ..Default::default()
+        },
+    ];
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/plants/plantings/batch")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(BatchCreatePlantingsDto {
+            plantings: new_plantings,
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/plants/plantings?relative_to_date=2023-05-08")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    let page: TimelinePage<PlantingDto> = test::read_body_json(resp).await;
+    assert_eq!(page.results.len(), 2);
+}
+
+#[actix_rt::test]
+async fn test_can_move_plantings_in_batch() {
+    let pool = init_test_database(|conn| batch_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let updates = [Uuid::from_u128(1), Uuid::from_u128(2)]
+        .into_iter()
+        .map(|id| BatchUpdatePlantingDto {
+            id,
+            update: UpdatePlantingDto::Move(MovePlantingDto {
+                x: 100,
+                y: 100,
+                version: 1,
+                action_id: Uuid::new_v4(),
+            }),
+        })
+        .collect();
+
+    let resp = test::TestRequest::patch()
+        .uri("/api/maps/-1/layers/plants/plantings/batch")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(BatchUpdatePlantingsDto {
+            updates,
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let plantings: Vec<PlantingDto> = test::read_body_json(resp).await;
+    assert_eq!(plantings.len(), 2);
+    assert!(plantings.iter().all(|p| p.x == 100 && p.y == 100));
+}
+
+#[actix_rt::test]
+async fn test_can_delete_plantings_in_batch() {
+    let pool = init_test_database(|conn| batch_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri("/api/maps/-1/layers/plants/plantings/batch")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(BatchDeletePlantingsDto {
+            ids: vec![Uuid::from_u128(1), Uuid::from_u128(2)],
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/plants/plantings?relative_to_date=2023-05-08")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    let page: TimelinePage<PlantingDto> = test::read_body_json(resp).await;
+    assert_eq!(page.results.len(), 0);
+}
+
+#[actix_rt::test]
+async fn test_delete_plantings_in_batch_fails_for_planting_not_on_map() {
+    let pool = init_test_database(|conn| batch_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri("/api/maps/-1/layers/plants/plantings/batch")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(BatchDeletePlantingsDto {
+            ids: vec![Uuid::from_u128(1), Uuid::new_v4()],
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/plants/plantings?relative_to_date=2023-05-08")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    let page: TimelinePage<PlantingDto> = test::read_body_json(resp).await;
+    assert_eq!(page.results.len(), 2);
+}
+
+#[actix_rt::test]
+async fn test_delete_plantings_in_batch_fails_for_too_large_batch() {
+    let pool = init_test_database(|conn| batch_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri("/api/maps/-1/layers/plants/plantings/batch")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(BatchDeletePlantingsDto {
+            ids: (0..=MAX_BATCH_SIZE).map(|_| Uuid::new_v4()).collect(),
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
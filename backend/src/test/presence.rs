@@ -0,0 +1,231 @@
+//! Tests for [`crate::controller::presence`].
+
+use actix_http::StatusCode;
+use actix_web::{http::header, test};
+use diesel_async::{scoped_futures::ScopedFutureExt, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::{
+    model::dto::{UpdateSubscriptionDto, UpdateUserPresenceDto, UserPresenceDto},
+    sse::broadcaster::{Broadcaster, DEFAULT_PING_INTERVAL},
+    test::util::{data, init_test_app_for_user, init_test_database},
+};
+
+#[actix_rt::test]
+async fn test_connected_user_is_present_with_selected_layer() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/presence")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let users: Vec<UserPresenceDto> = test::read_body_json(resp).await;
+    assert!(users.is_empty());
+
+    // The event stream stays open as long as the response is alive.
+    let event_stream = test::TestRequest::get()
+        .uri(&format!(
+            "/api/updates/maps?map_id=-1&token={}",
+            token.trim_start_matches("Bearer ")
+        ))
+        .send_request(&app)
+        .await;
+    assert_eq!(event_stream.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::put()
+        .uri("/api/maps/-1/presence")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(UpdateUserPresenceDto {
+            selected_layer_id: Some(-1),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/presence")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let users: Vec<UserPresenceDto> = test::read_body_json(resp).await;
+    assert_eq!(
+        users,
+        vec![UserPresenceDto {
+            user_id: Uuid::default(),
+            selected_layer_id: Some(-1),
+        }]
+    );
+}
+
+#[actix_rt::test]
+async fn test_users_connected_to_other_instances_are_present() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+    let other_instance = Broadcaster::new(pool, DEFAULT_PING_INTERVAL);
+    let user_id = Uuid::new_v4();
+
+    let client = other_instance
+        .new_client(-1, Uuid::new_v4(), user_id, None)
+        .await
+        .unwrap();
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/presence")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let users: Vec<UserPresenceDto> = test::read_body_json(resp).await;
+    assert_eq!(
+        users,
+        vec![UserPresenceDto {
+            user_id,
+            selected_layer_id: None,
+        }]
+    );
+
+    // The client is removed in the background once its connection is closed.
+    drop(client);
+    for _ in 0..50 {
+        let polled = test::TestRequest::get()
+            .uri("/api/maps/-1/presence")
+            .insert_header((header::AUTHORIZATION, token.clone()))
+            .send_request(&app)
+            .await;
+        let present: Vec<UserPresenceDto> = test::read_body_json(polled).await;
+        if present.is_empty() {
+            return;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    panic!("User is still present after the client disconnected");
+}
+
+#[actix_rt::test]
+async fn test_select_layer_of_other_map_fails() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(vec![
+                    data::TestInsertableMap::default(),
+                    data::TestInsertableMap {
+                        id: -2,
+                        name: "Other map".to_owned(),
+                        ..Default::default()
+                    },
+                ])
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer {
+                    map_id: -2,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::put()
+        .uri("/api/maps/-1/presence")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(UpdateUserPresenceDto {
+            selected_layer_id: Some(-1),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+async fn test_select_layer_fails_for_user_not_connected() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::put()
+        .uri("/api/maps/-1/presence")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(UpdateUserPresenceDto {
+            selected_layer_id: Some(-1),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn test_subscribe_fails_for_unknown_client() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::put()
+        .uri("/api/maps/-1/presence/subscription")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(UpdateSubscriptionDto {
+            client_id: Uuid::new_v4(),
+            layer_ids: Some(vec![-1]),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
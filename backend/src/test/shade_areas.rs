@@ -0,0 +1,293 @@
+//! Tests for [`crate::controller::shade_areas`].
+
+use actix_web::{
+    http::{header, StatusCode},
+    test,
+};
+use chrono::Utc;
+use diesel::ExpressionMethods;
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncPgConnection, RunQueryDsl};
+use postgis_diesel::types::{Point, Polygon};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    error::ServiceError,
+    model::{
+        dto::shade_areas::{
+            DeleteShadeAreaDto, NewShadeAreaDto, ShadeAreaDto, ShadeAtPointDto, UpdateShadeAreaDto,
+        },
+        r#enum::{layer_type::LayerType, privacy_option::PrivacyOption, shade::Shade},
+    },
+    test::util::{
+        dummy_map_polygons::{small_rectangle, tall_rectangle},
+        init_test_app, init_test_app_for_user, init_test_database,
+    },
+};
+
+/// The id of the area covering the whole map.
+const LIGHT_SHADE_AREA_ID: Uuid = Uuid::from_u128(1);
+/// The id of the small area in the corner of the map.
+const PERMANENT_SHADE_AREA_ID: Uuid = Uuid::from_u128(2);
+
+async fn initial_db_values(conn: &mut AsyncPgConnection) -> Result<(), ServiceError> {
+    diesel::insert_into(crate::schema::maps::table)
+        .values((
+            &crate::schema::maps::id.eq(-1),
+            &crate::schema::maps::name.eq("MyMap"),
+            &crate::schema::maps::creation_date.eq(Utc::now().date_naive()),
+            &crate::schema::maps::is_inactive.eq(false),
+            &crate::schema::maps::zoom_factor.eq(0),
+            &crate::schema::maps::honors.eq(0),
+            &crate::schema::maps::visits.eq(0),
+            &crate::schema::maps::harvested.eq(0),
+            &crate::schema::maps::owner_id.eq(Uuid::default()),
+            &crate::schema::maps::privacy.eq(PrivacyOption::Private),
+            &crate::schema::maps::geometry.eq(tall_rectangle()),
+        ))
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::layers::table)
+        .values(vec![
+            (
+                &crate::schema::layers::id.eq(-1),
+                &crate::schema::layers::map_id.eq(-1),
+                &crate::schema::layers::type_.eq(LayerType::Shade),
+                &crate::schema::layers::name.eq("Shade"),
+                &crate::schema::layers::is_alternative.eq(false),
+            ),
+            (
+                &crate::schema::layers::id.eq(-2),
+                &crate::schema::layers::map_id.eq(-1),
+                &crate::schema::layers::type_.eq(LayerType::Plants),
+                &crate::schema::layers::name.eq("Plants"),
+                &crate::schema::layers::is_alternative.eq(false),
+            ),
+        ])
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::shade_areas::table)
+        .values(vec![
+            (
+                &crate::schema::shade_areas::id.eq(LIGHT_SHADE_AREA_ID),
+                &crate::schema::shade_areas::layer_id.eq(-1),
+                &crate::schema::shade_areas::shade.eq(Shade::LightShade),
+                &crate::schema::shade_areas::geometry.eq(tall_rectangle()),
+            ),
+            (
+                &crate::schema::shade_areas::id.eq(PERMANENT_SHADE_AREA_ID),
+                &crate::schema::shade_areas::layer_id.eq(-1),
+                &crate::schema::shade_areas::shade.eq(Shade::PermanentShade),
+                &crate::schema::shade_areas::geometry.eq(small_rectangle()),
+            ),
+        ])
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// A polygon whose ring is not closed.
+fn open_polygon() -> Polygon<Point> {
+    serde_json::from_value(json!({
+        "rings": [[{"x": 0.0, "y": 0.0}, {"x": 10.0, "y": 0.0}, {"x": 10.0, "y": 10.0}]],
+        "srid": 4326
+    }))
+    .unwrap()
+}
+
+#[actix_rt::test]
+async fn test_find_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app(pool).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/shade/-1/areas")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let areas: Vec<ShadeAreaDto> = test::read_body_json(resp).await;
+    assert_eq!(areas.len(), 2);
+}
+
+#[actix_rt::test]
+async fn test_find_shade_at_returns_most_shade() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app(pool).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/shade/-1/point?x=5&y=50")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let in_both: ShadeAtPointDto = test::read_body_json(resp).await;
+    assert_eq!(in_both.shade, Shade::PermanentShade);
+    assert_eq!(in_both.shade_area_id, Some(PERMANENT_SHADE_AREA_ID));
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/shade/-1/point?x=100&y=500")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .send_request(&app)
+        .await;
+    let in_one: ShadeAtPointDto = test::read_body_json(resp).await;
+    assert_eq!(in_one.shade, Shade::LightShade);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/shade/-1/point?x=600&y=500")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    let outside: ShadeAtPointDto = test::read_body_json(resp).await;
+    assert_eq!(outside.shade, Shade::NoShade);
+    assert_eq!(outside.shade_area_id, None);
+}
+
+#[actix_rt::test]
+async fn test_find_shade_at_fails_for_plants_layer() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app(pool).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/shade/-2/point?x=5&y=50")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+async fn test_create_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/shade/areas")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(NewShadeAreaDto {
+            id: None,
+            layer_id: -1,
+            shade: Shade::PartialShade,
+            geometry: small_rectangle(),
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let area: ShadeAreaDto = test::read_body_json(resp).await;
+    assert_eq!(area.shade, Shade::PartialShade);
+}
+
+#[actix_rt::test]
+async fn test_create_fails_for_plants_layer() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/shade/areas")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(NewShadeAreaDto {
+            id: None,
+            layer_id: -2,
+            shade: Shade::PartialShade,
+            geometry: small_rectangle(),
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+async fn test_create_fails_for_invalid_geometry() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/shade/areas")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(NewShadeAreaDto {
+            id: None,
+            layer_id: -1,
+            shade: Shade::PartialShade,
+            geometry: open_polygon(),
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+async fn test_update_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::patch()
+        .uri(&format!(
+            "/api/maps/-1/layers/shade/areas/{PERMANENT_SHADE_AREA_ID}"
+        ))
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(UpdateShadeAreaDto {
+            shade: Some(Shade::PermanentDeepShade),
+            geometry: None,
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let area: ShadeAreaDto = test::read_body_json(resp).await;
+    assert_eq!(area.shade, Shade::PermanentDeepShade);
+    assert_eq!(area.geometry, small_rectangle());
+}
+
+#[actix_rt::test]
+async fn test_delete_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri(&format!(
+            "/api/maps/-1/layers/shade/areas/{PERMANENT_SHADE_AREA_ID}"
+        ))
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(DeleteShadeAreaDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/shade/-1/areas")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    let areas: Vec<ShadeAreaDto> = test::read_body_json(resp).await;
+    assert_eq!(areas.len(), 1);
+}
+
+#[actix_rt::test]
+async fn test_delete_fails_for_not_owner() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::new_v4()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri(&format!(
+            "/api/maps/-1/layers/shade/areas/{PERMANENT_SHADE_AREA_ID}"
+        ))
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(DeleteShadeAreaDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,204 @@
+//! Tests for [`crate::controller::soil_zones`].
+
+use actix_web::{
+    http::{header, StatusCode},
+    test,
+};
+use chrono::Utc;
+use diesel::ExpressionMethods;
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncPgConnection, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::{
+    error::ServiceError,
+    model::{
+        dto::soil_zones::{DeleteSoilZoneDto, NewSoilZoneDto, SoilZoneDto, UpdateSoilZoneDto},
+        r#enum::{
+            layer_type::LayerType, privacy_option::PrivacyOption, soil_ph::SoilPh,
+            soil_texture::SoilTexture,
+        },
+    },
+    test::util::{
+        dummy_map_polygons::{small_rectangle, tall_rectangle},
+        init_test_app, init_test_app_for_user, init_test_database,
+    },
+};
+
+/// The id of the zone in the corner of the map.
+const SOIL_ZONE_ID: Uuid = Uuid::from_u128(1);
+
+async fn initial_db_values(conn: &mut AsyncPgConnection) -> Result<(), ServiceError> {
+    diesel::insert_into(crate::schema::maps::table)
+        .values((
+            &crate::schema::maps::id.eq(-1),
+            &crate::schema::maps::name.eq("MyMap"),
+            &crate::schema::maps::creation_date.eq(Utc::now().date_naive()),
+            &crate::schema::maps::is_inactive.eq(false),
+            &crate::schema::maps::zoom_factor.eq(0),
+            &crate::schema::maps::honors.eq(0),
+            &crate::schema::maps::visits.eq(0),
+            &crate::schema::maps::harvested.eq(0),
+            &crate::schema::maps::owner_id.eq(Uuid::default()),
+            &crate::schema::maps::privacy.eq(PrivacyOption::Private),
+            &crate::schema::maps::geometry.eq(tall_rectangle()),
+        ))
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::layers::table)
+        .values(vec![
+            (
+                &crate::schema::layers::id.eq(-1),
+                &crate::schema::layers::map_id.eq(-1),
+                &crate::schema::layers::type_.eq(LayerType::Soil),
+                &crate::schema::layers::name.eq("Soil"),
+                &crate::schema::layers::is_alternative.eq(false),
+            ),
+            (
+                &crate::schema::layers::id.eq(-2),
+                &crate::schema::layers::map_id.eq(-1),
+                &crate::schema::layers::type_.eq(LayerType::Plants),
+                &crate::schema::layers::name.eq("Plants"),
+                &crate::schema::layers::is_alternative.eq(false),
+            ),
+        ])
+        .execute(conn)
+        .await?;
+    diesel::insert_into(crate::schema::soil_zones::table)
+        .values((
+            &crate::schema::soil_zones::id.eq(SOIL_ZONE_ID),
+            &crate::schema::soil_zones::layer_id.eq(-1),
+            &crate::schema::soil_zones::soil_ph.eq(SoilPh::Acidic),
+            &crate::schema::soil_zones::soil_texture.eq(SoilTexture::Sandy),
+            &crate::schema::soil_zones::geometry.eq(small_rectangle()),
+        ))
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+#[actix_rt::test]
+async fn test_find_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app(pool).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/soil/-1/zones")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let zones: Vec<SoilZoneDto> = test::read_body_json(resp).await;
+    assert_eq!(zones.len(), 1);
+}
+
+#[actix_rt::test]
+async fn test_create_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/soil/zones")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(NewSoilZoneDto {
+            id: None,
+            layer_id: -1,
+            soil_ph: SoilPh::Alkaline,
+            soil_texture: SoilTexture::Clay,
+            geometry: tall_rectangle(),
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let zone: SoilZoneDto = test::read_body_json(resp).await;
+    assert_eq!(zone.soil_ph, SoilPh::Alkaline);
+    assert_eq!(zone.soil_texture, SoilTexture::Clay);
+}
+
+#[actix_rt::test]
+async fn test_create_fails_for_plants_layer() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/soil/zones")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(NewSoilZoneDto {
+            id: None,
+            layer_id: -2,
+            soil_ph: SoilPh::Alkaline,
+            soil_texture: SoilTexture::Clay,
+            geometry: tall_rectangle(),
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+async fn test_update_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::patch()
+        .uri(&format!("/api/maps/-1/layers/soil/zones/{SOIL_ZONE_ID}"))
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(UpdateSoilZoneDto {
+            soil_ph: Some(SoilPh::Neutral),
+            soil_texture: None,
+            geometry: None,
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::OK);
+    let zone: SoilZoneDto = test::read_body_json(resp).await;
+    assert_eq!(zone.soil_ph, SoilPh::Neutral);
+    assert_eq!(zone.soil_texture, SoilTexture::Sandy);
+}
+
+#[actix_rt::test]
+async fn test_delete_succeeds() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::default()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri(&format!("/api/maps/-1/layers/soil/zones/{SOIL_ZONE_ID}"))
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(DeleteSoilZoneDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/soil/-1/zones")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    let zones: Vec<SoilZoneDto> = test::read_body_json(resp).await;
+    assert!(zones.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_delete_fails_for_not_owner() {
+    let pool = init_test_database(|conn| initial_db_values(conn).scope_boxed()).await;
+    let (token, app) = init_test_app_for_user(pool, Uuid::new_v4()).await;
+
+    let resp = test::TestRequest::delete()
+        .uri(&format!("/api/maps/-1/layers/soil/zones/{SOIL_ZONE_ID}"))
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(DeleteSoilZoneDto {
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,593 @@
+//! Tests for [`crate::controller::sse`].
+
+use std::{pin::Pin, time::Duration};
+
+use actix_http::StatusCode;
+use actix_web::{body::MessageBody, cookie::Cookie, http::header, test, web::Bytes};
+use diesel_async::{
+    pooled_connection::deadpool::Pool, scoped_futures::ScopedFutureExt, AsyncPgConnection,
+    RunQueryDsl,
+};
+use dotenvy::dotenv;
+use futures::future::poll_fn;
+use uuid::Uuid;
+
+use crate::{
+    config::{app, auth::sse_user_info::TOKEN_COOKIE},
+    model::{
+        dto::{
+            actions::{Action, BatchActionPayload, DeletePlantActionPayload},
+            DuplicateLayerDto, NewLayerDto,
+        },
+        entity::MapClient,
+        r#enum::{layer_type::LayerType, privacy_option::PrivacyOption},
+    },
+    sse::broadcaster::{Broadcaster, DEFAULT_PING_INTERVAL, RESYNC_EVENT},
+    test::util::{data, init_test_app_for_user, init_test_database},
+};
+
+/// Initialize a test database containing a single map.
+async fn init_map_database() -> Pool<AsyncPgConnection> {
+    init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+/// Store the layers the client subscribed to.
+async fn subscribe(
+    pool: &Pool<AsyncPgConnection>,
+    client_id: Uuid,
+    user_id: Uuid,
+    layer_ids: Option<Vec<i32>>,
+) {
+    let mut conn = pool.get().await.unwrap();
+    let subscribed = MapClient::subscribe(-1, user_id, client_id, layer_ids, &mut conn)
+        .await
+        .unwrap();
+    assert!(subscribed.is_some());
+}
+
+/// Get the id of an event sent to the client.
+fn event_id(event: &[u8]) -> String {
+    String::from_utf8_lossy(event)
+        .lines()
+        .find_map(|line| line.strip_prefix("id: "))
+        .expect("Event has no id")
+        .to_owned()
+}
+
+/// Receive the next event sent to the client, skipping pings.
+async fn next_event<B: MessageBody + Unpin>(body: &mut B) -> Bytes {
+    loop {
+        let event = poll_fn(|cx| Pin::new(&mut *body).poll_next(cx));
+        let event = tokio::time::timeout(Duration::from_secs(5), event)
+            .await
+            .expect("No event received")
+            .expect("Stream ended")
+            .unwrap_or_else(|_| panic!("Failed to receive event"));
+        if !event.starts_with(b":") {
+            return event;
+        }
+    }
+}
+
+#[actix_rt::test]
+async fn test_connect_fails_without_token() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (_, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::get()
+        .uri("/api/updates/maps?map_id=-1")
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/updates/maps?map_id=-1&token=invalid")
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_rt::test]
+async fn test_connect_with_token_in_query_or_cookie() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap {
+                    privacy: PrivacyOption::Private,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+    let token = token.trim_start_matches("Bearer ").to_owned();
+
+    let resp = test::TestRequest::get()
+        .uri(&format!("/api/updates/maps?map_id=-1&token={token}"))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/updates/maps?map_id=-1")
+        .cookie(Cookie::new(TOKEN_COOKIE, token))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+async fn test_connect_fails_for_invisible_map() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap {
+                    privacy: PrivacyOption::Private,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::new_v4()).await;
+    let token = token.trim_start_matches("Bearer ");
+
+    let resp = test::TestRequest::get()
+        .uri(&format!("/api/updates/maps?map_id=-1&token={token}"))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn test_broadcast_reaches_clients_of_other_instances() {
+    dotenv().ok();
+    let app_config = app::Config::from_env().expect("Error loading configuration");
+    let pool = init_map_database().await;
+    let first = Broadcaster::with_pubsub(
+        pool.clone(),
+        &app_config.database_url,
+        DEFAULT_PING_INTERVAL,
+    )
+    .await
+    .expect("Failed to connect broadcaster");
+    let second = Broadcaster::with_pubsub(pool, &app_config.database_url, DEFAULT_PING_INTERVAL)
+        .await
+        .expect("Failed to connect broadcaster");
+
+    let mut client = second
+        .new_client(-1, Uuid::new_v4(), Uuid::new_v4(), None)
+        .await
+        .unwrap();
+    let connected = next_event(&mut client).await;
+    assert!(String::from_utf8_lossy(&connected).contains("connected"));
+    let joined = next_event(&mut client).await;
+    assert!(String::from_utf8_lossy(&joined).contains("UserJoined"));
+
+    let action_id = Uuid::new_v4();
+    first
+        .broadcast(
+            -1,
+            Action::DeletePlanting(DeletePlantActionPayload::new(
+                Uuid::new_v4(),
+                -1,
+                Uuid::new_v4(),
+                action_id,
+            )),
+        )
+        .await;
+
+    let event = next_event(&mut client).await;
+    assert!(String::from_utf8_lossy(&event).contains(&action_id.to_string()));
+}
+
+#[actix_rt::test]
+async fn test_large_broadcast_reaches_clients_of_other_instances() {
+    dotenv().ok();
+    let app_config = app::Config::from_env().expect("Error loading configuration");
+    let pool = init_map_database().await;
+    let first = Broadcaster::with_pubsub(
+        pool.clone(),
+        &app_config.database_url,
+        DEFAULT_PING_INTERVAL,
+    )
+    .await
+    .expect("Failed to connect broadcaster");
+    let second = Broadcaster::with_pubsub(pool, &app_config.database_url, DEFAULT_PING_INTERVAL)
+        .await
+        .expect("Failed to connect broadcaster");
+
+    let mut client = second
+        .new_client(-1, Uuid::new_v4(), Uuid::new_v4(), None)
+        .await
+        .unwrap();
+    let _connected = next_event(&mut client).await;
+    let _joined = next_event(&mut client).await;
+
+    let user_id = Uuid::new_v4();
+    let action_id = Uuid::new_v4();
+    let actions = (0..200)
+        .map(|_| {
+            Action::DeletePlanting(DeletePlantActionPayload::new(
+                Uuid::new_v4(),
+                -1,
+                user_id,
+                action_id,
+            ))
+        })
+        .collect();
+    first
+        .broadcast(
+            -1,
+            Action::Batch(BatchActionPayload::new(actions, user_id, action_id)),
+        )
+        .await;
+
+    let event = next_event(&mut client).await;
+    assert!(event.len() > 8000);
+    assert!(String::from_utf8_lossy(&event).contains(&action_id.to_string()));
+}
+
+#[actix_rt::test]
+async fn test_layer_changes_are_broadcast() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::get()
+        .uri(&format!(
+            "/api/updates/maps?map_id=-1&token={}",
+            token.trim_start_matches("Bearer ")
+        ))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let mut client = resp.into_body();
+    let _connected = next_event(&mut client).await;
+    let _joined = next_event(&mut client).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(NewLayerDto {
+            map_id: -1,
+            type_: LayerType::Plants,
+            name: "Alternative".to_owned(),
+            is_alternative: true,
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let created = next_event(&mut client).await;
+    assert!(String::from_utf8_lossy(&created).contains("CreateLayer"));
+
+    let resp = test::TestRequest::patch()
+        .uri("/api/maps/-1")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(serde_json::json!({ "name": "Renamed", "action_id": Uuid::new_v4() }))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let updated = next_event(&mut client).await;
+    let updated = String::from_utf8_lossy(&updated);
+    assert!(updated.contains("UpdateMap"));
+    assert!(updated.contains("Renamed"));
+}
+
+#[actix_rt::test]
+async fn test_duplicated_layer_content_is_broadcast() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plantings::table)
+                .values(data::TestInsertablePlanting::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let resp = test::TestRequest::get()
+        .uri(&format!(
+            "/api/updates/maps?map_id=-1&token={}",
+            token.trim_start_matches("Bearer ")
+        ))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let mut client = resp.into_body();
+    let _connected = next_event(&mut client).await;
+    let _joined = next_event(&mut client).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/layers/-1/duplicate")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(DuplicateLayerDto {
+            name: "Alternative".to_owned(),
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let created = next_event(&mut client).await;
+    assert!(String::from_utf8_lossy(&created).contains("CreateLayer"));
+    let content = next_event(&mut client).await;
+    let content = String::from_utf8_lossy(&content);
+    assert!(content.contains("Batch"));
+    assert!(content.contains("CreatePlanting"));
+}
+
+#[actix_rt::test]
+async fn test_clients_only_receive_actions_of_subscribed_layers() {
+    let pool = init_map_database().await;
+    let broadcaster = Broadcaster::new(pool.clone(), DEFAULT_PING_INTERVAL);
+    let client_id = Uuid::new_v4();
+    let user_id = Uuid::new_v4();
+
+    let mut client = broadcaster
+        .new_client(-1, client_id, user_id, None)
+        .await
+        .unwrap();
+    let _connected = next_event(&mut client).await;
+    let _joined = next_event(&mut client).await;
+
+    subscribe(&pool, client_id, user_id, Some(vec![-1])).await;
+    broadcaster
+        .reload_subscription(-1, client_id, user_id)
+        .await;
+    // Only the user owning the client can change its subscription.
+    let mut conn = pool.get().await.unwrap();
+    let foreign = MapClient::subscribe(-1, Uuid::new_v4(), client_id, None, &mut conn)
+        .await
+        .unwrap();
+    assert!(foreign.is_none());
+    drop(conn);
+
+    let hidden_action_id = Uuid::new_v4();
+    let visible_action_id = Uuid::new_v4();
+    for (layer_id, action_id) in [(-2, hidden_action_id), (-1, visible_action_id)] {
+        broadcaster
+            .broadcast(
+                -1,
+                Action::DeletePlanting(DeletePlantActionPayload::new(
+                    Uuid::new_v4(),
+                    layer_id,
+                    user_id,
+                    action_id,
+                )),
+            )
+            .await;
+    }
+
+    let event = next_event(&mut client).await;
+    let event = String::from_utf8_lossy(&event);
+    assert!(!event.contains(&hidden_action_id.to_string()));
+    assert!(event.contains(&visible_action_id.to_string()));
+}
+
+#[actix_rt::test]
+async fn test_subscription_changes_reach_clients_of_other_instances() {
+    dotenv().ok();
+    let app_config = app::Config::from_env().expect("Error loading configuration");
+    let pool = init_map_database().await;
+    let first = Broadcaster::with_pubsub(
+        pool.clone(),
+        &app_config.database_url,
+        DEFAULT_PING_INTERVAL,
+    )
+    .await
+    .expect("Failed to connect broadcaster");
+    let second = Broadcaster::with_pubsub(
+        pool.clone(),
+        &app_config.database_url,
+        DEFAULT_PING_INTERVAL,
+    )
+    .await
+    .expect("Failed to connect broadcaster");
+    let client_id = Uuid::new_v4();
+    let user_id = Uuid::new_v4();
+
+    let mut client = second
+        .new_client(-1, client_id, user_id, None)
+        .await
+        .unwrap();
+    let _connected = next_event(&mut client).await;
+    let _joined = next_event(&mut client).await;
+
+    subscribe(&pool, client_id, user_id, Some(vec![-1])).await;
+    first.reload_subscription(-1, client_id, user_id).await;
+
+    let hidden_action_id = Uuid::new_v4();
+    let visible_action_id = Uuid::new_v4();
+    for (layer_id, action_id) in [(-2, hidden_action_id), (-1, visible_action_id)] {
+        first
+            .broadcast(
+                -1,
+                Action::DeletePlanting(DeletePlantActionPayload::new(
+                    Uuid::new_v4(),
+                    layer_id,
+                    user_id,
+                    action_id,
+                )),
+            )
+            .await;
+    }
+
+    let event = next_event(&mut client).await;
+    let event = String::from_utf8_lossy(&event);
+    assert!(!event.contains(&hidden_action_id.to_string()));
+    assert!(event.contains(&visible_action_id.to_string()));
+}
+
+#[actix_rt::test]
+async fn test_closed_clients_are_removed() {
+    let broadcaster = Broadcaster::new(init_map_database().await, DEFAULT_PING_INTERVAL);
+    let leaving_user_id = Uuid::new_v4();
+
+    let mut client = broadcaster
+        .new_client(-1, Uuid::new_v4(), Uuid::new_v4(), None)
+        .await
+        .unwrap();
+    let _connected = next_event(&mut client).await;
+    let _first_joined = next_event(&mut client).await;
+    let leaving_client = broadcaster
+        .new_client(-1, Uuid::new_v4(), leaving_user_id, None)
+        .await
+        .unwrap();
+    let _second_joined = next_event(&mut client).await;
+    assert_eq!(broadcaster.metrics().await.clients, 2);
+
+    // Closing the connection drops the response body.
+    drop(leaving_client);
+
+    let left = next_event(&mut client).await;
+    let left = String::from_utf8_lossy(&left);
+    assert!(left.contains("UserLeft"));
+    assert!(left.contains(&leaving_user_id.to_string()));
+    assert_eq!(broadcaster.metrics().await.clients, 1);
+}
+
+#[actix_rt::test]
+async fn test_reconnecting_client_receives_missed_actions() {
+    let pool = init_map_database().await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+    let token = token.trim_start_matches("Bearer ").to_owned();
+    let client_id = Uuid::new_v4();
+
+    let resp = test::TestRequest::get()
+        .uri(&format!(
+            "/api/updates/maps?map_id=-1&client_id={client_id}"
+        ))
+        .cookie(Cookie::new(TOKEN_COOKIE, token.clone()))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let mut client = resp.into_body();
+    let _connected = next_event(&mut client).await;
+    let joined = next_event(&mut client).await;
+    drop(client);
+
+    // No client is connected while the layers are created.
+    for name in ["First", "Second"] {
+        let resp = test::TestRequest::post()
+            .uri("/api/maps/-1/layers")
+            .insert_header((header::AUTHORIZATION, format!("Bearer {token}")))
+            .set_json(NewLayerDto {
+                map_id: -1,
+                type_: LayerType::Plants,
+                name: name.to_owned(),
+                is_alternative: true,
+                action_id: Uuid::new_v4(),
+            })
+            .send_request(&app)
+            .await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
+    let resp = test::TestRequest::get()
+        .uri(&format!(
+            "/api/updates/maps?map_id=-1&client_id={client_id}"
+        ))
+        .cookie(Cookie::new(TOKEN_COOKIE, token))
+        .insert_header(("Last-Event-ID", event_id(&joined)))
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let mut reconnected = resp.into_body();
+    let _reconnected = next_event(&mut reconnected).await;
+    // The removal of the old client might be noticed in between, so presence changes are skipped.
+    let mut created = Vec::new();
+    while created.len() < 2 {
+        let event = next_event(&mut reconnected).await;
+        let event = String::from_utf8_lossy(&event).into_owned();
+        if event.contains("CreateLayer") {
+            created.push(event);
+        }
+    }
+    let mut created = created.iter();
+    assert!(created.next().is_some_and(|event| event.contains("First")));
+    assert!(created.next().is_some_and(|event| event.contains("Second")));
+}
+
+#[actix_rt::test]
+async fn test_reconnecting_client_with_unknown_event_has_to_resync() {
+    let broadcaster = Broadcaster::new(init_map_database().await, DEFAULT_PING_INTERVAL);
+
+    let mut client = broadcaster
+        .new_client(-1, Uuid::new_v4(), Uuid::new_v4(), Some("-1"))
+        .await
+        .unwrap();
+    let _connected = next_event(&mut client).await;
+    let resync = next_event(&mut client).await;
+    assert!(String::from_utf8_lossy(&resync).contains(RESYNC_EVENT));
+}
+
+#[actix_rt::test]
+async fn test_access_log_leaves_out_query_of_event_streams() {
+    let event_stream = test::TestRequest::get()
+        .uri("/api/updates/maps?map_id=-1&token=secret")
+        .to_srv_request();
+    assert_eq!(
+        crate::request_line(&event_stream),
+        "GET /api/updates/maps HTTP/1.1"
+    );
+
+    let search = test::TestRequest::get()
+        .uri("/api/maps?name=Test")
+        .to_srv_request();
+    assert_eq!(
+        crate::request_line(&search),
+        "GET /api/maps?name=Test HTTP/1.1"
+    );
+}
@@ -0,0 +1,188 @@
+//! Tests for [`crate::controller::map::sync`].
+
+use actix_http::StatusCode;
+use actix_web::{http::header, test};
+use chrono::{Duration, Utc};
+use diesel_async::{scoped_futures::ScopedFutureExt, RunQueryDsl};
+use uuid::Uuid;
+
+use crate::{
+    model::dto::{
+        plantings::{
+            BatchUpdatePlantingDto, MovePlantingDto, NewPlantingDto, PlantingDto, UpdatePlantingDto,
+        },
+        sync::{
+            SyncDeleteDto, SyncDto, SyncOperation, SyncOperationDto, SyncResultDto, SyncStatus,
+        },
+        TimelinePage,
+    },
+    service::util::MAX_BATCH_SIZE,
+    test::util::{data, init_test_app_for_user, init_test_database},
+};
+
+/// Build an operation moving the planting in `version` to (`x`, 0).
+fn move_operation(planting_id: Uuid, x: i32, version: i32) -> SyncOperation {
+    SyncOperation::UpdatePlanting(BatchUpdatePlantingDto {
+        id: planting_id,
+        update: UpdatePlantingDto::Move(MovePlantingDto {
+            x,
+            y: 0,
+            version,
+            action_id: Uuid::new_v4(),
+        }),
+    })
+}
+
+#[actix_rt::test]
+async fn test_sync_reports_conflicts_per_operation() {
+    let planting_id = Uuid::new_v4();
+    let pool = init_test_database(|conn| {
+        async move {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::layers::table)
+                .values(data::TestInsertableLayer::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plants::table)
+                .values(data::TestInsertablePlant::default())
+                .execute(conn)
+                .await?;
+            diesel::insert_into(crate::schema::plantings::table)
+                .values(data::TestInsertablePlanting {
+                    id: planting_id,
+                    ..Default::default()
+                })
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let now = Utc::now();
+    let operations = vec![
+        // Sent first, but done last.
+        SyncOperationDto {
+            timestamp: now + Duration::minutes(3),
+            operation: SyncOperation::DeletePlanting(SyncDeleteDto { id: Uuid::new_v4() }),
+        },
+        SyncOperationDto {
+            timestamp: now,
+            operation: move_operation(planting_id, 10, 1),
+        },
+        // Based on the same version as the previous move.
+        SyncOperationDto {
+            timestamp: now + Duration::minutes(1),
+            operation: move_operation(planting_id, 20, 1),
+        },
+        SyncOperationDto {
+            timestamp: now + Duration::minutes(2),
+            operation: SyncOperation::CreatePlanting(NewPlantingDto {
+                id: Some(Uuid::new_v4()),
+                layer_id: -1,
+                plant_id: -1,
+                ..Default::default()
+            }),
+        },
+    ];
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/sync")
+        .insert_header((header::AUTHORIZATION, token.clone()))
+        .set_json(SyncDto {
+            operations,
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let result: SyncResultDto = test::read_body_json(resp).await;
+    let statuses: Vec<_> = result.results.iter().map(|r| r.status).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            SyncStatus::Rejected,
+            SyncStatus::Accepted,
+            SyncStatus::Conflict,
+            SyncStatus::Accepted
+        ]
+    );
+    // The conflict contains the state the client has to resolve it with.
+    let current = result.results.get(2).and_then(|r| r.planting).unwrap();
+    assert_eq!(current.x, 10);
+    assert_eq!(current.version, 2);
+
+    let resp = test::TestRequest::get()
+        .uri("/api/maps/-1/layers/plants/plantings?relative_to_date=2023-05-08")
+        .insert_header((header::AUTHORIZATION, token))
+        .send_request(&app)
+        .await;
+    let page: TimelinePage<PlantingDto> = test::read_body_json(resp).await;
+    assert_eq!(page.results.len(), 2);
+}
+
+#[actix_rt::test]
+async fn test_sync_fails_for_not_editor() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::new_v4()).await;
+
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/sync")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(SyncDto {
+            operations: vec![],
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[actix_rt::test]
+async fn test_sync_fails_for_too_many_operations() {
+    let pool = init_test_database(|conn| {
+        async {
+            diesel::insert_into(crate::schema::maps::table)
+                .values(data::TestInsertableMap::default())
+                .execute(conn)
+                .await?;
+            Ok(())
+        }
+        .scope_boxed()
+    })
+    .await;
+    let (token, app) = init_test_app_for_user(pool.clone(), Uuid::default()).await;
+
+    let now = Utc::now();
+    let resp = test::TestRequest::post()
+        .uri("/api/maps/-1/sync")
+        .insert_header((header::AUTHORIZATION, token))
+        .set_json(SyncDto {
+            operations: (0..=MAX_BATCH_SIZE)
+                .map(|_| SyncOperationDto {
+                    timestamp: now,
+                    operation: SyncOperation::DeletePlanting(SyncDeleteDto { id: Uuid::new_v4() }),
+                })
+                .collect(),
+            action_id: Uuid::new_v4(),
+        })
+        .send_request(&app)
+        .await;
+    assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+}
@@ -17,7 +17,7 @@ use dotenvy::dotenv;
 
 use crate::config::{app, data::AppDataInner, routes};
 use crate::error::ServiceError;
-use crate::sse::broadcaster::Broadcaster;
+use crate::sse::broadcaster::{Broadcaster, DEFAULT_PING_INTERVAL};
 
 use self::token::{generate_token, generate_token_for_user};
 
@@ -39,15 +39,7 @@ where
         + Send
         + 'a,
 {
-    dotenv().ok();
-    let app_config = app::Config::from_env().expect("Error loading configuration");
-
-    let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new(app_config.database_url);
-    let pool = Pool::builder(manager)
-        .max_size(1) // allow only one connection (which is the test transaction)
-        .build()
-        .expect("Failed to init pool");
-
+    let pool = init_pool();
     let mut conn = pool
         .get()
         .await
@@ -64,6 +56,51 @@ where
     pool
 }
 
+/// Initializes a test database with the given initializer function without a test transaction.
+///
+/// Only use it for code that starts transactions which can't be nested in a test transaction,
+/// e.g. because they need another isolation level.
+/// All changes are committed and visible to other tests running at the same time,
+/// so use ids no other test uses and remove the data at the end of the test.
+/// As a failed test leaves its data behind, remove it in `init_database` as well.
+///
+/// The pool is limited to 1 connection.
+///
+/// # Panics
+/// If the database could not be initialized.
+pub async fn init_committed_test_database<'a, F>(init_database: F) -> Pool<AsyncPgConnection>
+where
+    F: for<'r> FnOnce(
+            &'r mut AsyncPgConnection,
+        ) -> ScopedBoxFuture<'a, 'r, Result<(), ServiceError>>
+        + Send
+        + 'a,
+{
+    let pool = init_pool();
+    let mut conn = pool
+        .get()
+        .await
+        .expect("Failed to get connection from pool");
+
+    conn.transaction(|conn| init_database(conn))
+        .await
+        .expect("Failed to initialize test database");
+
+    pool
+}
+
+/// Create a pool of at most 1 connection to the test database.
+fn init_pool() -> Pool<AsyncPgConnection> {
+    dotenv().ok();
+    let app_config = app::Config::from_env().expect("Error loading configuration");
+
+    let manager = AsyncDieselConnectionManager::<AsyncPgConnection>::new(app_config.database_url);
+    Pool::builder(manager)
+        .max_size(1) // allow only one connection (which is the test transaction)
+        .build()
+        .expect("Failed to init pool")
+}
+
 /// Create the test service out of the connection pool.
 ///
 /// Returns a token in bearer format ("Bearer <token>") and the app to send the request to.
@@ -98,8 +135,8 @@ async fn init_test_app_impl(
     test::init_service(
         App::new()
             .app_data(Data::new(AppDataInner {
+                broadcaster: Broadcaster::new(pool.clone(), DEFAULT_PING_INTERVAL),
                 pool,
-                broadcaster: Broadcaster::new(),
             }))
             .configure(routes::config),
     )
@@ -15,6 +15,7 @@ pub struct TestInsertableMap {
     pub id: i32,
     pub name: String,
     pub creation_date: NaiveDate,
+    pub deletion_date: Option<NaiveDate>,
     pub is_inactive: bool,
     pub zoom_factor: i16,
     pub honors: i16,
@@ -31,6 +32,7 @@ impl Default for TestInsertableMap {
             id: -1,
             name: "Test Map 1".to_owned(),
             creation_date: NaiveDate::from_ymd_opt(2023, 5, 8).expect("Could not parse date!"),
+            deletion_date: None,
             is_inactive: false,
             zoom_factor: 100,
             honors: 0,
@@ -98,6 +100,7 @@ pub struct TestInsertablePlanting {
     pub scale_y: f32,
     pub add_date: Option<NaiveDate>,
     pub remove_date: Option<NaiveDate>,
+    pub delete_date: Option<NaiveDate>,
 }
 
 impl Default for TestInsertablePlanting {
@@ -115,6 +118,7 @@ impl Default for TestInsertablePlanting {
             scale_y: 0.0,
             add_date: None,
             remove_date: None,
+            delete_date: None,
         }
     }
 }
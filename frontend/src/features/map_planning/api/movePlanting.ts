@@ -4,7 +4,7 @@ import { createAPI } from '@/config/axios';
 export const movePlanting = async (
   mapId: number,
   id: string,
-  planting: Required<Pick<MovePlantingDto, 'x' | 'y' | 'actionId'>>,
+  planting: Required<Pick<MovePlantingDto, 'x' | 'y' | 'version' | 'actionId'>>,
 ): Promise<PlantingDto> => {
   const http = createAPI();
 
@@ -5,7 +5,7 @@ export const transformPlanting = async (
   mapId: number,
   id: string,
   planting: Required<
-    Pick<TransformPlantingDto, 'x' | 'y' | 'scaleX' | 'scaleY' | 'rotation' | 'actionId'>
+    Pick<TransformPlantingDto, 'x' | 'y' | 'scaleX' | 'scaleY' | 'rotation' | 'version' | 'actionId'>
   >,
 ): Promise<PlantingDto> => {
   const http = createAPI();
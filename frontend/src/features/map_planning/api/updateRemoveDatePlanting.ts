@@ -4,7 +4,7 @@ import { createAPI } from '@/config/axios';
 export async function updateRemoveDatePlanting(
   mapId: number,
   id: string,
-  planting: Pick<UpdateRemoveDatePlantingDto, 'removeDate' | 'actionId'>,
+  planting: Pick<UpdateRemoveDatePlantingDto, 'removeDate' | 'version' | 'actionId'>,
 ) {
   const http = createAPI();
 
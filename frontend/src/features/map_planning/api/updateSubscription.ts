@@ -0,0 +1,15 @@
+import { UpdateSubscriptionDto } from '@/bindings/definitions';
+import { createAPI } from '@/config/axios';
+
+export const updateSubscription = async (
+  mapId: number,
+  subscription: UpdateSubscriptionDto,
+): Promise<void> => {
+  const http = createAPI();
+
+  try {
+    await http.put(`api/maps/${mapId}/presence/subscription`, subscription);
+  } catch (error) {
+    throw error as Error;
+  }
+};
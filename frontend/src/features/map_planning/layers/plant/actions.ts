@@ -21,6 +21,34 @@ import {
 } from '@/bindings/definitions';
 import { v4 } from 'uuid';
 
+/**
+ * The data of a plant action.
+ * `Optional` fields are only known for actions received from the backend.
+ */
+type PlantActionData<T, Optional extends keyof T> = Omit<T, 'userId' | 'actionId' | Optional> &
+  Partial<Pick<T, Optional>>;
+
+/**
+ * The version of the planting the store is based on.
+ * It is sent with updates, so the backend can reject them
+ * if the planting was changed in the meantime.
+ */
+function plantingVersion(id: string): number {
+  const plant = useMapStore
+    .getState()
+    .trackedState.layers.plants.loadedObjects.find((p) => p.id === id);
+  return plant?.version ?? 1;
+}
+
+/**
+ * Store the versions of the plantings returned by the backend after an update.
+ */
+function storeVersions<T extends PlantingDto | PlantingDto[]>(plantings: T): T {
+  const updated = Array.isArray(plantings) ? plantings : [plantings];
+  useMapStore.getState().updatePlantingVersions(updated);
+  return plantings;
+}
+
 export class CreatePlantAction
   implements Action<Awaited<ReturnType<typeof createPlanting>>, boolean>
 {
@@ -31,7 +59,7 @@ export class CreatePlantAction
   }
 
   constructor(
-    private readonly _data: Omit<CreatePlantActionPayload, 'userId' | 'actionId'>,
+    private readonly _data: PlantActionData<CreatePlantActionPayload, 'version'>,
     public actionId = v4(),
   ) {
     this._id = _data.id;
@@ -50,6 +78,7 @@ export class CreatePlantAction
     const newPlant = {
       ...this._data,
       id: this._id,
+      version: this._data.version ?? 1,
     };
 
     const timelineDate = useMapStore.getState().untrackedState.timelineDate;
@@ -83,7 +112,7 @@ export class DeletePlantAction
   implements Action<boolean, Awaited<ReturnType<typeof createPlanting>>>
 {
   constructor(
-    private readonly _data: Omit<DeletePlantActionPayload, 'userId' | 'actionId'>,
+    private readonly _data: PlantActionData<DeletePlantActionPayload, 'layerId'>,
     public actionId = v4(),
   ) {}
 
@@ -133,7 +162,7 @@ export class MovePlantAction
   }
 
   constructor(
-    private readonly _data: Omit<MovePlantActionPayload, 'userId' | 'actionId'>[],
+    private readonly _data: PlantActionData<MovePlantActionPayload, 'layerId' | 'version'>[],
     public actionId = v4(),
   ) {
     this._ids = _data.map((d) => d.id);
@@ -160,6 +189,7 @@ export class MovePlantAction
             ...p,
             x: this._data.find((d) => d.id === p.id)?.x ?? p.x,
             y: this._data.find((d) => d.id === p.id)?.y ?? p.y,
+            version: this._data.find((d) => d.id === p.id)?.version ?? p.version,
           };
         }
 
@@ -185,11 +215,12 @@ export class MovePlantAction
       movePlanting(mapId, d.id, {
         x: d.x,
         y: d.y,
+        version: plantingVersion(d.id),
         actionId: this.actionId,
       }),
     );
 
-    return Promise.all(tasks);
+    return Promise.all(tasks).then(storeVersions);
   }
 }
 
@@ -204,7 +235,7 @@ export class TransformPlantAction
   }
 
   constructor(
-    private readonly _data: Omit<TransformPlantActionPayload, 'userId' | 'actionId'>[],
+    private readonly _data: PlantActionData<TransformPlantActionPayload, 'layerId' | 'version'>[],
     public actionId = v4(),
   ) {
     this._ids = _data.map((d) => d.id);
@@ -241,6 +272,7 @@ export class TransformPlantAction
             scaleX: this._data.find((d) => d.id === p.id)?.scaleX ?? p.scaleX,
             scaleY: this._data.find((d) => d.id === p.id)?.scaleY ?? p.scaleY,
             rotation: this._data.find((d) => d.id === p.id)?.rotation ?? p.rotation,
+            version: this._data.find((d) => d.id === p.id)?.version ?? p.version,
           };
         }
 
@@ -269,11 +301,12 @@ export class TransformPlantAction
         scaleX: d.scaleX,
         scaleY: d.scaleY,
         rotation: d.rotation,
+        version: plantingVersion(d.id),
         actionId: this.actionId,
       }),
     );
 
-    return Promise.all(tasks);
+    return Promise.all(tasks).then(storeVersions);
   }
 }
 
@@ -285,7 +318,10 @@ export class UpdateAddDatePlantAction
     >
 {
   constructor(
-    private readonly _data: Omit<UpdatePlantingAddDateActionPayload, 'userId' | 'actionId'>,
+    private readonly _data: PlantActionData<
+      UpdatePlantingAddDateActionPayload,
+      'layerId' | 'version'
+    >,
     public actionId = v4(),
   ) {}
 
@@ -316,6 +352,7 @@ export class UpdateAddDatePlantAction
           return {
             ...p,
             addDate: this._data.addDate,
+            version: this._data.version ?? p.version,
           };
         }
 
@@ -344,8 +381,9 @@ export class UpdateAddDatePlantAction
   execute(mapId: number): Promise<PlantingDto> {
     return updateAddDatePlanting(mapId, this._data.id, {
       addDate: this._data.addDate,
+      version: plantingVersion(this._data.id),
       actionId: this.actionId,
-    });
+    }).then(storeVersions);
   }
 }
 
@@ -357,7 +395,10 @@ export class UpdateRemoveDatePlantAction
     >
 {
   constructor(
-    private readonly _data: Omit<UpdatePlantingRemoveDateActionPayload, 'userId' | 'actionId'>,
+    private readonly _data: PlantActionData<
+      UpdatePlantingRemoveDateActionPayload,
+      'layerId' | 'version'
+    >,
     public actionId = v4(),
   ) {}
 
@@ -388,6 +429,7 @@ export class UpdateRemoveDatePlantAction
           return {
             ...p,
             removeDate: this._data.removeDate,
+            version: this._data.version ?? p.version,
           };
         }
 
@@ -416,7 +458,8 @@ export class UpdateRemoveDatePlantAction
   execute(mapId: number): Promise<PlantingDto> {
     return updateRemoveDatePlanting(mapId, this._data.id, {
       removeDate: this._data.removeDate,
+      version: plantingVersion(this._data.id),
       actionId: this.actionId,
-    });
+    }).then(storeVersions);
   }
 }
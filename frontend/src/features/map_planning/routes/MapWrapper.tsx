@@ -1,4 +1,5 @@
 import { getPlantings } from '../api/getPlantings';
+import { updateSubscription } from '../api/updateSubscription';
 import { Map } from '../components/Map';
 import { useGetLayers } from '../hooks/useGetLayers';
 import { useMapId } from '../hooks/useMapId';
@@ -12,11 +13,12 @@ import { LayerType, LayerDto, GuidedToursDto } from '@/bindings/definitions';
 import { createAPI } from '@/config/axios';
 import { QUERY_KEYS } from '@/config/react_query';
 import { useSafeAuth } from '@/hooks/useSafeAuth';
-import { useQuery } from '@tanstack/react-query';
+import { useQuery, useQueryClient } from '@tanstack/react-query';
 import { useRef, useEffect, useState } from 'react';
 import { useTranslation } from 'react-i18next';
 import { ShepherdOptionsWithType, ShepherdTour } from 'react-shepherd';
 import { toast } from 'react-toastify';
+import * as uuid from 'uuid';
 
 /**
  * Extracts the default layer from the list of layers.
@@ -162,10 +164,58 @@ function useCleanMapStore() {
   }, []);
 }
 
+/**
+ * Subscribes the client to the visible layers, so the server doesn't send updates of hidden layers.
+ * Layers that become visible again are loaded again, as their updates were missed while hidden.
+ * If the subscription fails, all layers are loaded again, as their updates might be missed.
+ */
+function useLayerSubscription(clientId: string, connection: number) {
+  const mapId = useMapId();
+  const queryClient = useQueryClient();
+  const { t } = useTranslation(['layers']);
+  const visibleLayerIds = useMapStore((state) =>
+    Object.values(state.trackedState.layers)
+      .filter((layer) => layer.id > 0 && state.untrackedState.layers[layer.index].visible)
+      .map((layer) => layer.id)
+      .join(','),
+  );
+  const subscribedLayerIds = useRef<number[]>([]);
+
+  useEffect(() => {
+    if (!connection) {
+      return;
+    }
+
+    const layerIds = visibleLayerIds ? visibleLayerIds.split(',').map(Number) : [];
+    const shownLayerIds = layerIds.filter((id) => !subscribedLayerIds.current.includes(id));
+    subscribedLayerIds.current = layerIds;
+
+    updateSubscription(mapId, { client_id: clientId, layer_ids: layerIds })
+      .then(() => {
+        if (shownLayerIds.length) {
+          queryClient.invalidateQueries([QUERY_KEYS.PLANTINGS, mapId]);
+          queryClient.invalidateQueries(['baselayer', mapId]);
+        }
+      })
+      .catch((error) => {
+        console.error(error);
+        // Every layer has to be loaded again once the subscription succeeds.
+        subscribedLayerIds.current = [];
+        queryClient.invalidateQueries([QUERY_KEYS.PLANTINGS, mapId]);
+        queryClient.invalidateQueries(['baselayer', mapId]);
+        toast.warn(t('layers:error_updating_subscription'));
+      });
+  }, [mapId, clientId, connection, visibleLayerIds, queryClient, t]);
+}
+
 function useMapUpdates() {
   const mapId = useMapId();
   const { user } = useSafeAuth();
   const evRef = useRef<EventSource>();
+  const queryClient = useQueryClient();
+  const [clientId] = useState(() => uuid.v4());
+  // Counts the (re)connections, as the subscription has to be sent again for each one.
+  const [connection, setConnection] = useState(0);
 
   const userId = user?.profile.sub;
 
@@ -176,7 +226,9 @@ function useMapUpdates() {
 
     const connectionQuery = {
       map_id: mapId,
-      user_id: userId,
+      // EventSource can't set the Authorization header.
+      token: user?.access_token,
+      client_id: clientId,
     };
 
     const http = createAPI();
@@ -185,14 +237,19 @@ function useMapUpdates() {
       params: connectionQuery,
     });
 
-    // TODO: implement authentication
     evRef.current = new EventSource(uri);
-    evRef.current.onmessage = (ev) => handleRemoteAction(ev, userId);
+    evRef.current.onopen = () => setConnection((count) => count + 1);
+    evRef.current.onmessage = (ev) => handleRemoteAction(ev, userId, queryClient);
+    // The server could not replay the updates missed while reconnecting,
+    // so the map has to be loaded again.
+    evRef.current.addEventListener('resync', () => window.location.reload());
 
     return () => {
       evRef.current?.close();
     };
-  }, [userId, mapId]);
+  }, [userId, mapId, user?.access_token, queryClient, clientId]);
+
+  useLayerSubscription(clientId, connection);
 }
 
 /**
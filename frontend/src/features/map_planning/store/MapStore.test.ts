@@ -383,7 +383,14 @@ function initPlantLayerInStore(objects: PlantingDto[] = []) {
 }
 
 function createTestLayerObject(): LayerDto {
-  return { id: -1, map_id: -1, type_: LayerType.Soil, name: 'Test Layer', is_alternative: false };
+  return {
+    id: -1,
+    map_id: -1,
+    type_: LayerType.Soil,
+    name: 'Test Layer',
+    is_alternative: false,
+    order_index: 0,
+  };
 }
 
 function createPlantTestObject(testValue: number): PlantingDto {
@@ -398,5 +405,6 @@ function createPlantTestObject(testValue: number): PlantingDto {
     rotation: testValue,
     scaleX: testValue,
     scaleY: testValue,
+    version: 1,
   };
 }
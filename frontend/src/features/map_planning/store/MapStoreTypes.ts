@@ -135,6 +135,11 @@ export interface TrackedMapSlice {
    * Initializes the plant layer.
    */
   initPlantLayer: (plantLayer: PlantingDto[]) => void;
+  /**
+   * Updates the versions of the plantings after the backend changed them.
+   * The change is not tracked in the history.
+   */
+  updatePlantingVersions: (plantings: PlantingDto[]) => void;
   /**
    * Initializes the base layer.
    */
@@ -233,6 +238,7 @@ export const UNTRACKED_DEFAULT_STATE: UntrackedMapState = {
     name: 'none',
     type_: LayerType.Base,
     map_id: -1,
+    order_index: 0,
   },
   tooltipContent: '',
   tooltipPosition: { x: 0, y: 0 },
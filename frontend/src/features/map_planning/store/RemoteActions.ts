@@ -10,8 +10,14 @@ import {
 import useMapStore from './MapStore';
 import { Action } from './MapStoreTypes';
 import { Action as RemoteAction } from '@/bindings/definitions';
+import { QUERY_KEYS } from '@/config/react_query';
+import { QueryClient } from '@tanstack/react-query';
 
-export function handleRemoteAction(ev: MessageEvent<unknown>, userId: string) {
+export function handleRemoteAction(
+  ev: MessageEvent<unknown>,
+  userId: string,
+  queryClient: QueryClient,
+) {
   if (typeof ev.data !== 'string') {
     console.error('Received non-string message from server');
     return;
@@ -30,6 +36,59 @@ export function handleRemoteAction(ev: MessageEvent<unknown>, userId: string) {
     return;
   }
 
+  if (remoteAction.type === 'Batch') {
+    // Actions of a batch share one actionId and are applied one after another.
+    remoteAction.payload.actions.forEach((batchedAction) => {
+      if (!handleOutsideMapStore(batchedAction, queryClient)) {
+        applyRemoteAction(batchedAction, userId);
+      }
+    });
+    return;
+  }
+
+  if (!handleOutsideMapStore(remoteAction, queryClient)) {
+    applyRemoteAction(remoteAction, userId);
+  }
+}
+
+/**
+ * Handles actions concerning data that is not part of the map store.
+ * Returns false if the action has to be applied to the map store instead.
+ */
+function handleOutsideMapStore(remoteAction: RemoteAction, queryClient: QueryClient): boolean {
+  switch (remoteAction.type) {
+    case 'UserJoined':
+    case 'UserLeft':
+      // Presence of other users doesn't change the map.
+      return true;
+    case 'CreateLayer':
+    case 'UpdateLayer':
+    case 'DeleteLayer':
+      // Layers are not part of the map store, they are loaded again instead.
+      queryClient.invalidateQueries([QUERY_KEYS.LAYERS]);
+      return true;
+    case 'UpdateMap':
+      queryClient.invalidateQueries(['maps']);
+      return true;
+    case 'CreateBaseLayerImage':
+    case 'DeleteBaseLayerImage':
+      // Base layer images are loaded together with the base layer.
+      queryClient.invalidateQueries(['baselayer']);
+      return true;
+    case 'CreateShadeArea':
+    case 'UpdateShadeArea':
+    case 'DeleteShadeArea':
+    case 'CreateSoilZone':
+    case 'UpdateSoilZone':
+    case 'DeleteSoilZone':
+      // Shade areas and soil zones are not shown on the map yet.
+      return true;
+    default:
+      return false;
+  }
+}
+
+function applyRemoteAction(remoteAction: RemoteAction, userId: string) {
   const action = convertToAction(remoteAction);
 
   if (remoteAction.payload.userId === userId) {
@@ -55,6 +114,8 @@ function convertToAction(remoteAction: RemoteAction): Action<unknown, unknown> {
       return new CreatePlantAction({ ...remoteAction.payload }, remoteAction.payload.actionId);
     case 'DeletePlanting':
       return new DeletePlantAction({ ...remoteAction.payload }, remoteAction.payload.actionId);
+    case 'RestorePlanting':
+      return new CreatePlantAction({ ...remoteAction.payload }, remoteAction.payload.actionId);
     case 'MovePlanting':
       return new MovePlantAction([{ ...remoteAction.payload }], remoteAction.payload.actionId);
     case 'TransformPlanting':
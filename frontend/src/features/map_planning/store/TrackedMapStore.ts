@@ -55,6 +55,27 @@ export const createTrackedMapSlice: StateCreator<
         },
       }));
     },
+    updatePlantingVersions: (plantings: PlantingDto[]) => {
+      const withVersion = (p: PlantingDto) => ({
+        ...p,
+        version: plantings.find((planting) => planting.id === p.id)?.version ?? p.version,
+      });
+
+      set((state) => ({
+        ...state,
+        trackedState: {
+          ...state.trackedState,
+          layers: {
+            ...state.trackedState.layers,
+            plants: {
+              ...state.trackedState.layers.plants,
+              objects: state.trackedState.layers.plants.objects.map(withVersion),
+              loadedObjects: state.trackedState.layers.plants.loadedObjects.map(withVersion),
+            },
+          },
+        },
+      }));
+    },
     initBaseLayer(dto: BaseLayerImageDto) {
       set((state) => ({
         ...state,
@@ -1,10 +1,13 @@
-import { MapDto, UpdateMapDto } from '@/bindings/definitions';
+import { UpdateMapDto, UpdatedMapDto } from '@/bindings/definitions';
 import { createAPI } from '@/config/axios';
 
-export const updateMap = async (updateObject: UpdateMapDto, mapId: number): Promise<MapDto> => {
+export const updateMap = async (
+  updateObject: UpdateMapDto,
+  mapId: number,
+): Promise<UpdatedMapDto> => {
   const http = createAPI();
   try {
-    const response = await http.patch<MapDto>(`/api/maps/${mapId}`, updateObject);
+    const response = await http.patch<UpdatedMapDto>(`/api/maps/${mapId}`, updateObject);
     return response.data;
   } catch (error) {
     throw error as Error;
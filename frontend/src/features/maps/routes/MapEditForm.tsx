@@ -10,6 +10,7 @@ import { useTranslation } from 'react-i18next';
 import { MapContainer, TileLayer, useMapEvents } from 'react-leaflet';
 import { useNavigate, useParams } from 'react-router-dom';
 import { toast } from 'react-toastify';
+import { v4 } from 'uuid';
 
 interface MapUpdateData {
   name: string;
@@ -148,6 +149,7 @@ export default function MapEditForm() {
       privacy: updateObject.privacy,
       description: updateObject.description,
       location: updateObject.location,
+      action_id: v4(),
     };
     if (updateObject.location && isNaN(updateObject.location?.latitude)) {
       updatedMap.location = undefined;
@@ -166,7 +168,12 @@ export default function MapEditForm() {
       updatedMap.name = undefined;
     }
     try {
-      await updateMap(updatedMap, Number(mapId));
+      const { plantings_outside_geometry } = await updateMap(updatedMap, Number(mapId));
+      if (plantings_outside_geometry.length > 0) {
+        toast.warn(
+          t('maps:edit.plantings_outside_geometry', { count: plantings_outside_geometry.length }),
+        );
+      }
     } catch (error) {
       toast.error(t('maps:edit.error_map_edit'), { autoClose: false });
     }